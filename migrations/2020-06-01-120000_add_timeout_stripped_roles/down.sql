@@ -0,0 +1 @@
+ALTER TABLE timeouts DROP COLUMN stripped_roles;
@@ -0,0 +1 @@
+ALTER TABLE timeouts ADD COLUMN stripped_roles BYTEA;
@@ -0,0 +1 @@
+drop table temporary_overwrites
@@ -0,0 +1,12 @@
+create table temporary_overwrites (
+  id serial primary key,
+  guild_id bigint not null,
+  channel_id bigint not null,
+  user_id bigint not null,
+  message_id bigint not null,
+  allow bigint not null,
+  previous_allow bigint,
+  previous_deny bigint,
+  messages integer,
+  expires_on bigint
+)
@@ -0,0 +1,12 @@
+create table scheduled_messages (
+  id serial primary key,
+  guild_id bigint not null,
+  channel_id bigint not null,
+  author_id bigint not null,
+  content text not null,
+  title text,
+  embed boolean not null,
+  next_run bigint not null,
+  recurrence text,
+  delete_after integer
+)
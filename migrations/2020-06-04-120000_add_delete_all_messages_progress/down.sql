@@ -0,0 +1,4 @@
+ALTER TABLE delete_all_messages
+  DROP COLUMN resume_before,
+  DROP COLUMN sweep_started,
+  DROP COLUMN last_sweep;
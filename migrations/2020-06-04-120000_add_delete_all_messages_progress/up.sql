@@ -0,0 +1,4 @@
+ALTER TABLE delete_all_messages
+  ADD COLUMN resume_before BIGINT,
+  ADD COLUMN sweep_started BIGINT,
+  ADD COLUMN last_sweep BIGINT;
@@ -0,0 +1,2 @@
+ALTER TABLE delete_all_messages
+  DROP COLUMN oldest_failed;
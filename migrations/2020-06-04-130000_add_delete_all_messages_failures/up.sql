@@ -0,0 +1,2 @@
+ALTER TABLE delete_all_messages
+  ADD COLUMN oldest_failed BIGINT;
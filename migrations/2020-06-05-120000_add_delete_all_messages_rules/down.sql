@@ -0,0 +1,6 @@
+ALTER TABLE delete_all_messages
+  DROP COLUMN keep_pinned,
+  DROP COLUMN keep_filters,
+  DROP COLUMN only_bots,
+  DROP COLUMN attachments,
+  DROP COLUMN keep_latest;
@@ -0,0 +1,6 @@
+ALTER TABLE delete_all_messages
+  ADD COLUMN keep_pinned BOOLEAN NOT NULL DEFAULT FALSE,
+  ADD COLUMN keep_filters TEXT,
+  ADD COLUMN only_bots BOOLEAN NOT NULL DEFAULT FALSE,
+  ADD COLUMN attachments BOOLEAN,
+  ADD COLUMN keep_latest INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,3 @@
+alter table reactions drop column group_id;
+
+drop table reaction_groups
@@ -0,0 +1,10 @@
+create table reaction_groups (
+  id serial primary key,
+  server_id bigint not null,
+  name text not null,
+  mode text not null,
+  max_roles integer,
+  unique (server_id, name)
+);
+
+alter table reactions add column group_id integer references reaction_groups(id) on delete set null
@@ -0,0 +1,8 @@
+create table reaction_menus (
+  id serial primary key,
+  server_id bigint not null,
+  channel_id bigint not null,
+  message_id bigint not null unique,
+  title text not null,
+  description text
+)
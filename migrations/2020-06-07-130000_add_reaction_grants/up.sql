@@ -0,0 +1,7 @@
+create table reaction_grants (
+  id serial primary key,
+  server_id bigint not null,
+  user_id bigint not null,
+  role_id bigint not null,
+  unique (server_id, user_id, role_id)
+)
@@ -0,0 +1 @@
+alter table reactions drop column filters
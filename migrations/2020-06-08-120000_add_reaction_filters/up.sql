@@ -0,0 +1 @@
+alter table reactions add column filters text
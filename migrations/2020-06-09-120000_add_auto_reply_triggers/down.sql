@@ -0,0 +1,7 @@
+drop table auto_reply_recipients;
+
+alter table auto_replies
+  drop column trigger_kind,
+  drop column trigger,
+  drop column case_sensitive,
+  drop column once_per_user
@@ -0,0 +1,11 @@
+alter table auto_replies
+  add column trigger_kind text,
+  add column trigger text,
+  add column case_sensitive boolean not null default false,
+  add column once_per_user boolean not null default false;
+
+create table auto_reply_recipients (
+  auto_reply_id integer not null references auto_replies(id) on delete cascade,
+  user_id bigint not null,
+  primary key (auto_reply_id, user_id)
+)
@@ -0,0 +1,7 @@
+alter table auto_replies
+  drop column on_leave,
+  drop column delivery,
+  drop column plain,
+  drop column embed_title,
+  drop column embed_color,
+  drop column embed_image
@@ -0,0 +1,7 @@
+alter table auto_replies
+  add column on_leave boolean not null default false,
+  add column delivery text not null default 'channel',
+  add column plain boolean not null default false,
+  add column embed_title text,
+  add column embed_color integer,
+  add column embed_image text
@@ -0,0 +1,4 @@
+drop table auto_reply_cooldowns;
+
+alter table auto_replies
+  drop column cooldown_scope;
@@ -0,0 +1,11 @@
+alter table auto_replies
+  add column cooldown_scope text not null default 'user';
+
+create table auto_reply_cooldowns (
+  auto_reply_id integer not null references auto_replies(id) on delete cascade,
+  scope_id bigint not null,
+  expires_at bigint not null,
+  primary key (auto_reply_id, scope_id)
+);
+
+create index auto_reply_cooldowns_expires_at_idx on auto_reply_cooldowns (expires_at);
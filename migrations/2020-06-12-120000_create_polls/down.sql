@@ -0,0 +1,2 @@
+drop table poll_options;
+drop table polls
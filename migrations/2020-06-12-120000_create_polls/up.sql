@@ -0,0 +1,20 @@
+create table polls (
+  id serial primary key,
+  server_id bigint not null,
+  channel_id bigint not null,
+  message_id bigint not null unique,
+  author_id bigint not null,
+  text text not null,
+  ends_at bigint,
+  closed boolean not null default false
+);
+
+create table poll_options (
+  id serial primary key,
+  poll_id integer not null references polls(id) on delete cascade,
+  position integer not null,
+  emoji text not null,
+  text text not null,
+  votes integer,
+  unique (poll_id, position)
+)
@@ -0,0 +1,3 @@
+alter table polls
+  drop column single,
+  drop column max_votes
@@ -0,0 +1,3 @@
+alter table polls
+  add column single boolean not null default false,
+  add column max_votes integer
@@ -0,0 +1,4 @@
+alter table polls
+  drop column filters,
+  drop column joined_before,
+  drop column voters
@@ -0,0 +1,4 @@
+alter table polls
+  add column filters text,
+  add column joined_before boolean not null default false,
+  add column voters integer
@@ -0,0 +1,4 @@
+drop table poll_ballots;
+
+alter table polls
+  drop column ranked
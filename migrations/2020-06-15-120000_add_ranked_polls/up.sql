@@ -0,0 +1,11 @@
+alter table polls
+  add column ranked boolean not null default false;
+
+create table poll_ballots (
+  poll_id integer not null references polls(id) on delete cascade,
+  user_id bigint not null,
+  ranking text,
+  awaiting boolean not null default true,
+  requested_at bigint not null,
+  primary key (poll_id, user_id)
+)
@@ -0,0 +1,2 @@
+drop table logged_messages;
+drop table log_settings
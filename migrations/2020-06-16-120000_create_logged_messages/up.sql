@@ -0,0 +1,18 @@
+create table log_settings (
+  id serial primary key,
+  server_id bigint not null unique,
+  retention integer,
+  max_messages integer
+);
+
+create table logged_messages (
+  message_id bigint primary key,
+  server_id bigint not null,
+  channel_id bigint not null,
+  author_id bigint not null,
+  content text not null,
+  attachments text not null default '',
+  created_at bigint not null
+);
+
+create index logged_messages_server_id_created_at_idx on logged_messages (server_id, created_at)
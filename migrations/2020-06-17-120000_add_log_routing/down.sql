@@ -0,0 +1,2 @@
+drop table log_ignores;
+drop table log_routes;
@@ -0,0 +1,15 @@
+create table log_routes (
+  id serial primary key,
+  server_id bigint not null,
+  category text not null,
+  channel_id bigint,
+  unique (server_id, category)
+);
+
+create table log_ignores (
+  id serial primary key,
+  server_id bigint not null,
+  kind text not null,
+  target_id bigint not null,
+  unique (server_id, kind, target_id)
+);
@@ -0,0 +1,6 @@
+drop table logged_attachments;
+
+alter table log_settings
+  drop column keep_attachments,
+  drop column max_attachment_size,
+  drop column attachment_retention;
@@ -0,0 +1,16 @@
+alter table log_settings
+  add column keep_attachments boolean not null default false,
+  add column max_attachment_size integer,
+  add column attachment_retention integer;
+
+create table logged_attachments (
+  id serial primary key,
+  message_id bigint not null,
+  server_id bigint not null,
+  filename text not null,
+  path text not null,
+  size integer not null,
+  created_at bigint not null
+);
+
+create index logged_attachments_message_id_idx on logged_attachments (message_id)
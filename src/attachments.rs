@@ -0,0 +1,190 @@
+//! Local copies of logged messages' attachments, for guilds that opt in, so attachments of deleted
+//! messages can still be shown in the log after Discord removes them.
+
+use crate::{
+  database::models::{ToU64, U64, LogSettings, LoggedAttachment, NewLoggedAttachment},
+  error::*,
+};
+
+use chrono::Utc;
+
+use diesel::prelude::*;
+
+use serenity::model::{
+  channel::Message,
+  id::{GuildId, MessageId},
+};
+
+use std::{
+  collections::HashMap,
+  fs,
+  io::ErrorKind as IoErrorKind,
+  path::Path,
+};
+
+const DIRECTORY: &str = "./attachments";
+
+/// The most space one guild's attachments can take up.
+const MAX_STORED: i64 = 512 * 1024 * 1024;
+
+/// The most files a bot can upload in one message.
+pub const MAX_UPLOADS: usize = 10;
+
+/// Keep only characters that are safe in a path and in an `attachment://` URL.
+fn sanitize(filename: &str) -> String {
+  let name: String = filename.chars()
+    .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_' { c } else { '_' })
+    .collect();
+  let name = name.trim_start_matches('.');
+  if name.is_empty() {
+    "attachment".into()
+  } else {
+    name.into()
+  }
+}
+
+/// Download a message's attachments, if its guild's log settings keep them. Attachments over the
+/// guild's size limit, or that would take the guild over `MAX_STORED`, are skipped.
+pub fn save(settings: Option<&LogSettings>, guild: GuildId, message: &Message) -> Result<()> {
+  if message.attachments.is_empty() {
+    return Ok(());
+  }
+  let settings = some_or!(settings, return Ok(()));
+  if !settings.keep_attachments {
+    return Ok(());
+  }
+  // settings from before the limit was checked may be too large to upload again
+  let max_size = settings.max_attachment_size
+    .unwrap_or(LogSettings::DEFAULT_MAX_ATTACHMENT_SIZE)
+    .min(LogSettings::DEFAULT_MAX_ATTACHMENT_SIZE) as u64;
+  let mut stored: i64 = crate::bot::with_connection(|c| {
+    use crate::database::schema::logged_attachments::dsl;
+    dsl::logged_attachments
+      .filter(dsl::server_id.eq(guild.to_u64()))
+      .select(diesel::dsl::sum(dsl::size))
+      .first::<Option<i64>>(c)
+  }).chain_err(|| "could not load size of stored attachments")?.unwrap_or_default();
+
+  let directory = Path::new(DIRECTORY).join(guild.to_string());
+  fs::create_dir_all(&directory).chain_err(|| "could not create attachment directory")?;
+  for attachment in &message.attachments {
+    if attachment.size > max_size || stored + attachment.size as i64 > MAX_STORED {
+      continue;
+    }
+    let data = attachment.download().chain_err(|| format!("could not download attachment {}", attachment.id))?;
+    let filename = sanitize(&attachment.filename);
+    let path = directory.join(format!("{}-{}", attachment.id, filename));
+    fs::write(&path, &data).chain_err(|| "could not write attachment")?;
+    let new = NewLoggedAttachment {
+      message_id: message.id.into(),
+      server_id: guild.into(),
+      filename,
+      path: path.to_string_lossy().into_owned(),
+      size: data.len() as i32,
+      created_at: Utc::now().timestamp(),
+    };
+    crate::bot::with_connection(|c| {
+      diesel::insert_into(crate::database::schema::logged_attachments::table)
+        .values(&new)
+        .execute(c)
+    }).chain_err(|| "could not store attachment")?;
+    stored += data.len() as i64;
+  }
+  Ok(())
+}
+
+/// Get the attachments kept for some messages.
+pub fn saved(messages: &[MessageId]) -> Result<Vec<LoggedAttachment>> {
+  let ids: Vec<i64> = messages.iter().map(|m| m.0 as i64).collect();
+  crate::bot::with_connection(|c| {
+    use crate::database::schema::logged_attachments::dsl;
+    dsl::logged_attachments
+      .filter(dsl::message_id.eq_any(&ids))
+      .order_by(dsl::id)
+      .load(c)
+  }).chain_err(|| "could not load stored attachments")
+}
+
+/// Read kept attachments back for uploading, up to `max_files` and as many as fit in one message.
+/// Returns the files and how many attachments didn't fit or couldn't be read.
+///
+/// Files with the same name get a number in front, so each can be linked with `attachment://`.
+pub fn read(attachments: &[LoggedAttachment], max_files: usize) -> (Vec<(Vec<u8>, String)>, usize) {
+  let mut files: Vec<(Vec<u8>, String)> = Vec::new();
+  let mut total = 0;
+  for attachment in attachments {
+    if files.len() >= max_files || total + attachment.size > LogSettings::DEFAULT_MAX_ATTACHMENT_SIZE {
+      continue;
+    }
+    match fs::read(&attachment.path) {
+      Ok(data) => {
+        total += attachment.size;
+        let mut name = attachment.filename.clone();
+        let mut n = 1;
+        while files.iter().any(|(_, f)| *f == name) {
+          n += 1;
+          name = format!("{}-{}", n, attachment.filename);
+        }
+        files.push((data, name));
+      },
+      Err(e) => warn!("could not read stored attachment {}: {}", attachment.path, e),
+    }
+  }
+  let missing = attachments.len() - files.len();
+  (files, missing)
+}
+
+/// Delete kept attachments, both the files and their records.
+pub fn remove(attachments: &[LoggedAttachment]) -> Result<()> {
+  for attachment in attachments {
+    if let Err(e) = fs::remove_file(&attachment.path) {
+      if e.kind() != IoErrorKind::NotFound {
+        warn!("could not delete stored attachment {}: {}", attachment.path, e);
+      }
+    }
+  }
+  let ids: Vec<i32> = attachments.iter().map(|a| a.id).collect();
+  crate::bot::with_connection(|c| {
+    use crate::database::schema::logged_attachments::dsl;
+    diesel::delete(dsl::logged_attachments.filter(dsl::id.eq_any(&ids))).execute(c)
+  }).chain_err(|| "could not remove stored attachments")?;
+  Ok(())
+}
+
+/// Delete attachments past their guild's retention, attachments of messages that aren't logged
+/// anymore and attachments of guilds that stopped keeping them.
+pub fn prune() -> Result<usize> {
+  let now = Utc::now().timestamp();
+  let (attachments, settings): (Vec<LoggedAttachment>, Vec<LogSettings>) = crate::bot::with_connection(|c| {
+    let attachments = crate::database::schema::logged_attachments::table.load(c)?;
+    let settings = crate::database::schema::log_settings::table.load(c)?;
+    Ok((attachments, settings))
+  }).chain_err(|| "could not load stored attachments")?;
+  if attachments.is_empty() {
+    return Ok(0);
+  }
+  let settings: HashMap<u64, LogSettings> = settings.into_iter().map(|s| (*s.server_id, s)).collect();
+
+  let message_ids: Vec<U64> = attachments.iter().map(|a| a.message_id).collect();
+  let logged: Vec<U64> = crate::bot::with_connection(|c| {
+    use crate::database::schema::logged_messages::dsl;
+    dsl::logged_messages
+      .filter(dsl::message_id.eq_any(&message_ids))
+      .select(dsl::message_id)
+      .load(c)
+  }).chain_err(|| "could not load logged messages")?;
+  let logged: Vec<u64> = logged.into_iter().map(u64::from).collect();
+
+  let expired: Vec<LoggedAttachment> = attachments.into_iter()
+    .filter(|a| {
+      let settings = match settings.get(&*a.server_id) {
+        Some(s) if s.keep_attachments => s,
+        _ => return true,
+      };
+      let retention = settings.attachment_retention.unwrap_or(LogSettings::DEFAULT_ATTACHMENT_RETENTION);
+      a.created_at < now - i64::from(retention) || !logged.contains(&*a.message_id)
+    })
+    .collect();
+  remove(&expired)?;
+  Ok(expired.len())
+}
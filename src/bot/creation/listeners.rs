@@ -34,9 +34,10 @@ impl Handler {
       box GuildsExt,
       box ReactionAuthorize,
       box Timeouts,
-      box PollTagger,
+      box PollListener,
       box AutoReplyListener::default(),
       box TemporaryRolesListener,
+      box TemporaryOverwritesListener,
       box RandomPresenceListener,
       box Log::default(),
     ];
@@ -131,8 +132,11 @@ fn command_listener<'a>(env: &Arc<BotEnv>) -> CommandListener<'a> {
     "referencecount" => ReferenceCountCommand,
     "reload", "reloadconfig" => ReloadConfigCommand,
     "report" => ReportCommand,
+    "rolemenu", "reactionmenu" => RoleMenuCommand,
+    "schedule", "scheduled" => ScheduleCommand,
     "search" => SearchCommand,
     "tag" => TagCommand,
+    "temporaryaccess", "tempaccess" => TemporaryAccessCommand,
     "temporaryrole", "temprole" => TemporaryRoleCommand,
     "timeout" => TimeoutCommand,
     "untimeout" => UntimeoutCommand,
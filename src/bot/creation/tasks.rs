@@ -14,5 +14,10 @@ pub fn tasks(bot: &LalafellBot) -> Result<()> {
   task_manager.start_task(TagQueueTask::default());
   task_manager.start_task(EphemeralMessageTask::default());
   task_manager.start_task(TemporaryRolesTask::default());
+  task_manager.start_task(TemporaryOverwritesTask::default());
+  task_manager.start_task(ScheduledMessagesTask::default());
+  task_manager.start_task(AutoReplyCooldownsTask::default());
+  task_manager.start_task(PollsTask::default());
+  task_manager.start_task(MessageLogTask::default());
   Ok(())
 }
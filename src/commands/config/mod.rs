@@ -1,5 +1,5 @@
 mod channel;
-mod server;
+pub mod server;
 
 pub mod configure;
 
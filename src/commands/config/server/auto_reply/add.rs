@@ -1,5 +1,7 @@
-use crate::database::models::NewAutoReply;
+use crate::database::models::{NewAutoReply, TriggerKind, Delivery, CooldownScope};
 use crate::filters::Filter;
+use crate::listeners::auto_reply::build_regex;
+use crate::template::Template;
 use crate::util::ParsedDuration;
 
 use diesel::prelude::*;
@@ -17,14 +19,47 @@ pub struct Params {
   #[structopt(short = "j", long = "on-join", help = "If the message should be sent on join")]
   on_join: bool,
 
+  #[structopt(short = "l", long = "on-leave", help = "If the message should be sent when a member leaves", conflicts_with = "on_join")]
+  on_leave: bool,
+
+  #[structopt(short = "s", long = "send-to", help = "Where to send the message: channel, dm or both", default_value = "channel")]
+  delivery: Delivery,
+
+  #[structopt(long = "title", help = "The title of the message's embed")]
+  title: Option<String>,
+
+  #[structopt(long = "color", help = "The hex color of the message's embed")]
+  color: Option<String>,
+
+  #[structopt(long = "image", help = "The URL of an image to show in the message's embed")]
+  image: Option<String>,
+
+  #[structopt(short = "p", long = "plain", help = "If the message should be sent without an embed", conflicts_with_all = &["title", "color", "image"])]
+  plain: bool,
+
   #[structopt(short = "d", long = "delay", help = "The time string for how long to wait before sending the message again")]
   #[structopt(parse(try_from_str))]
   delay: Option<ParsedDuration>,
 
+  #[structopt(short = "S", long = "cooldown", help = "Who the delay applies to: user, channel or global", default_value = "user")]
+  cooldown_scope: CooldownScope,
+
   #[structopt(short = "f", long = "filter", help = "A filter to add to this auto reply")]
   #[structopt(number_of_values = 1)]
   filters: Vec<String>,
 
+  #[structopt(short = "m", long = "match", help = "How to match the trigger: exact, contains or regex", requires = "trigger", conflicts_with_all = &["on_join", "on_leave"])]
+  trigger_kind: Option<TriggerKind>,
+
+  #[structopt(short = "t", long = "trigger", help = "The text that makes the auto reply send its message", requires = "trigger_kind")]
+  trigger: Option<String>,
+
+  #[structopt(short = "c", long = "case-sensitive", help = "If the trigger should be case sensitive", requires = "trigger")]
+  case_sensitive: bool,
+
+  #[structopt(short = "o", long = "once", help = "If the message should only be sent once to each member")]
+  once_per_user: bool,
+
   #[structopt(help = "The channel to add the auto reply to")]
   channel: ChannelOrId,
 
@@ -44,25 +79,54 @@ impl<'a> AddCommand {
         None => return Err("Invalid filters.".into())
       }
     };
+    if let (Some(TriggerKind::Regex), Some(ref trigger)) = (params.trigger_kind, &params.trigger) {
+      if let Err(e) = build_regex(trigger, params.case_sensitive) {
+        return Err(format!("Invalid regex: {}", e).into());
+      }
+    }
+    let color = match params.color {
+      Some(ref c) => match u32::from_str_radix(c.trim_start_matches('#'), 16) {
+        Ok(c) if c <= 0xFF_FF_FF => Some(c as i32),
+        _ => return Err("Invalid color.".into()),
+      },
+      None => None,
+    };
     let delay: i32 = params.delay.unwrap_or_default().0 as i32;
     let message = params.message.join(" ");
     if message.is_empty() {
       return Err("Empty message.".into());
     }
+    if let Err(e) = Template::parse(&message) {
+      return Err(format!("Invalid template: {}.", e).into());
+    }
+    if let Some(Err(e)) = params.title.as_ref().map(|t| Template::parse(t)) {
+      return Err(format!("Invalid title template: {}.", e).into());
+    }
     let nar = NewAutoReply {
       server_id: guild.into(),
       channel_id: params.channel.0.into(),
       message,
       on_join: params.on_join,
       delay,
-      filters
+      filters,
+      trigger_kind: params.trigger_kind.map(|k| k.to_string()),
+      trigger: params.trigger,
+      case_sensitive: params.case_sensitive,
+      once_per_user: params.once_per_user,
+      on_leave: params.on_leave,
+      delivery: params.delivery.to_string(),
+      plain: params.plain,
+      embed_title: params.title,
+      embed_color: color,
+      embed_image: params.image,
+      cooldown_scope: params.cooldown_scope.to_string(),
     };
     crate::bot::with_connection(|c| {
       use crate::database::schema::auto_replies;
       diesel::insert_into(auto_replies::table)
         .values(&nar)
         .execute(c)
-    }).chain_err(|| "could not insert new dam")?;
+    }).chain_err(|| "could not insert new auto reply")?;
     Ok(CommandSuccess::default())
   }
 }
@@ -1,4 +1,4 @@
-use crate::database::models::{ToU64, Reaction};
+use crate::database::models::{ToU64, AutoReply, Delivery, CooldownScope};
 
 use diesel::prelude::*;
 
@@ -6,21 +6,66 @@ use lalafell::commands::prelude::*;
 use lalafell::error::*;
 
 use serenity::prelude::Mentionable;
-use serenity::model::id::{GuildId, ChannelId, RoleId};
+use serenity::model::id::{GuildId, ChannelId};
 
 pub struct ListCommand;
 
 impl<'a> ListCommand {
   pub fn run(&self, guild: GuildId) -> CommandResult<'a> {
-    let reactions: Vec<Reaction> = crate::bot::with_connection(|c| {
-      use crate::database::schema::reactions::dsl;
-      dsl::reactions
+    let ars: Vec<AutoReply> = crate::bot::with_connection(|c| {
+      use crate::database::schema::auto_replies::dsl;
+      dsl::auto_replies
         .filter(dsl::server_id.eq(guild.to_u64()))
         .load(c)
-    }).chain_err(|| "could not load reactions")?;
-    let strings: Vec<String> = reactions.iter()
-      .map(|r| format!("{}. {} grants {} on {} in {}", r.id, r.emoji, RoleId(*r.role_id).mention(), *r.message_id, ChannelId(*r.channel_id).mention()))
+    }).chain_err(|| "could not load auto_replies")?;
+    let strings: Vec<String> = ars.iter()
+      .map(|r| format!("{id}. Replying to {when} in {channel}{filters}{delivery} with a delay of {delay} second{plural}{scope}{once}.\n```{message}\n```",
+                      id = r.id,
+                      when = ListCommand::describe_trigger(r),
+                      channel = ChannelId(*r.channel_id).mention(),
+                      filters = r.filters.as_ref().map(|f| format!(" with filters `{}`", f)).unwrap_or_default(),
+                      delivery = ListCommand::describe_delivery(r),
+                      delay = r.delay,
+                      plural = if r.delay == 1 { "" } else { "s" },
+                      scope = ListCommand::describe_scope(r),
+                      once = if r.once_per_user { ", once per member" } else { "" },
+                      message = r.message
+      ))
       .collect();
     Ok(strings.join("\n").into())
   }
+
+  fn describe_trigger(reply: &AutoReply) -> String {
+    if reply.on_join {
+      return "joins".into();
+    }
+    if reply.on_leave {
+      return "leaves".into();
+    }
+    let trigger = match (reply.trigger_kind(), reply.trigger.as_ref()) {
+      (Some(kind), Some(trigger)) => format!("messages matching `{}` ({})", trigger, kind),
+      _ => return "messages".into(),
+    };
+    if reply.case_sensitive {
+      format!("{}, case sensitive", trigger)
+    } else {
+      trigger
+    }
+  }
+
+  fn describe_scope(reply: &AutoReply) -> &'static str {
+    match reply.cooldown_scope() {
+      CooldownScope::User => " per member",
+      CooldownScope::Channel => " per channel",
+      CooldownScope::Global => "",
+    }
+  }
+
+  fn describe_delivery(reply: &AutoReply) -> &'static str {
+    match reply.delivery() {
+      Delivery::Channel => "",
+      Delivery::Dm => ", sent by DM",
+      Delivery::Both => ", also sent by DM",
+    }
+  }
 }
@@ -26,6 +26,7 @@ impl<'a> RemoveCommand {
         .execute(c)
     }).chain_err(|| "could not delete auto_replies")?;
     if affected > 0 {
+      crate::listeners::auto_reply::forget_trigger(params.id);
       Ok(CommandSuccess::default())
     } else {
       Err("No auto replies were deleted.".into())
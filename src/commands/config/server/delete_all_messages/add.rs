@@ -1,4 +1,6 @@
 use crate::database::models::{ToU64, DeleteAllMessages, NewDeleteAllMessages};
+use crate::filters::Filter;
+use crate::tasks::delete_all_messages::{dry_run, DeleteRules};
 
 use diesel::prelude::*;
 
@@ -12,6 +14,28 @@ pub struct AddCommand;
 
 #[derive(Debug, StructOpt)]
 pub struct Params {
+  #[structopt(short = "p", long = "keep-pinned", help = "Don't delete pinned messages")]
+  keep_pinned: bool,
+
+  #[structopt(short = "f", long = "keep-filter", help = "A filter for members whose messages should not be deleted")]
+  #[structopt(number_of_values = 1)]
+  keep_filters: Vec<String>,
+
+  #[structopt(short = "b", long = "only-bots", help = "Only delete messages posted by bots")]
+  only_bots: bool,
+
+  #[structopt(long = "with-attachments", help = "Only delete messages with attachments", conflicts_with = "without_attachments")]
+  with_attachments: bool,
+
+  #[structopt(long = "without-attachments", help = "Only delete messages without attachments")]
+  without_attachments: bool,
+
+  #[structopt(short = "l", long = "keep-latest", help = "The number of newest messages in the channel not to delete", default_value = "0")]
+  keep_latest: u16,
+
+  #[structopt(short = "n", long = "dry-run", help = "Report what would be deleted instead of adding the DAM")]
+  dry_run: bool,
+
   // FIXME: Take String and check against voice channels
   #[structopt(help = "The channel to add the DAM to")]
   channel: ChannelOrId,
@@ -23,7 +47,48 @@ pub struct Params {
 
 impl<'a> AddCommand {
   #[allow(clippy::needless_pass_by_value)]
-  pub fn run(&self, guild: GuildId, params: Params) -> CommandResult<'a> {
+  pub fn run(&self, ctx: &Context, guild: GuildId, params: Params) -> CommandResult<'a> {
+    let keep_filters = if params.keep_filters.is_empty() {
+      None
+    } else {
+      match Filter::all_filters(&params.keep_filters.join(" ")) {
+        Some(f) => Some(f.into_iter().map(|x| x.to_string()).collect::<Vec<_>>().join(" ")),
+        None => return Err("Invalid filters.".into())
+      }
+    };
+
+    let mut ndam = NewDeleteAllMessages::new(guild.0, params.channel.0, i32::from(params.after), &params.except);
+    ndam.keep_pinned = params.keep_pinned;
+    ndam.keep_filters = keep_filters;
+    ndam.only_bots = params.only_bots;
+    ndam.attachments = if params.with_attachments {
+      Some(true)
+    } else if params.without_attachments {
+      Some(false)
+    } else {
+      None
+    };
+    ndam.keep_latest = i32::from(params.keep_latest);
+
+    if params.dry_run {
+      let run = dry_run(ctx, guild.to_guild_cached(&ctx), *params.channel, &DeleteRules::from(&ndam))
+        .chain_err(|| "could not check messages")?;
+      let mut report = format!(
+        "Out of the {checked} newest message{plural}, {deleted} would be deleted",
+        checked = run.checked,
+        plural = if run.checked == 1 { "" } else { "s" },
+        deleted = run.deleted,
+      );
+      if run.old > 0 {
+        report.push_str(&format!(" ({} of them older than two weeks, which are deleted slowly)", run.old));
+      }
+      report.push('.');
+      if run.more {
+        report.push_str(" Older messages were not checked, so more would likely be deleted in total.");
+      }
+      return Ok(report.into());
+    }
+
     let dams: Vec<DeleteAllMessages> = crate::bot::with_connection(|c| {
       use crate::database::schema::delete_all_messages::dsl;
       dsl::delete_all_messages
@@ -35,7 +100,6 @@ impl<'a> AddCommand {
       return Err("A delete all messages already exists for that channel.".into());
     }
 
-    let ndam = NewDeleteAllMessages::new(guild.0, params.channel.0, i32::from(params.after), &params.except);
     crate::bot::with_connection(|c| {
       use crate::database::schema::delete_all_messages;
       diesel::insert_into(delete_all_messages::table)
@@ -23,14 +23,38 @@ impl<'a> ListCommand {
         .load(c)
     }).chain_err(|| "could not load delete_all_messages")?;
     Ok(dams.iter()
-      .map(|d| format!("{id}. Deleting all messages in {channel} after {after} second{plural}{except}.",
+      .map(|d| format!("{id}. Deleting all {bots}messages{attachments} in {channel} after {after} second{plural}{except}.{rules}",
                       id = d.id,
+                      bots = if d.only_bots { "bot " } else { "" },
+                      attachments = match d.attachments {
+                        Some(true) => " with attachments",
+                        Some(false) => " without attachments",
+                        None => "",
+                      },
                       channel = ChannelId(*d.channel_id).mention(),
                       after = d.after,
                       plural = if d.after == 1 { "" } else { "s" },
-                      except = if d.exclude.is_empty() { String::new() } else { format!(" (excluding {} message{})", d.exclude.len() / 8, if d.exclude.len() / 8 == 1 { "" } else { "s" }) }
+                      except = if d.exclude.is_empty() { String::new() } else { format!(" (excluding {} message{})", d.exclude.len() / 8, if d.exclude.len() / 8 == 1 { "" } else { "s" }) },
+                      rules = ListCommand::keep_rules(d)
       ))
       .collect::<Vec<_>>()
       .join("\n"))
   }
+
+  fn keep_rules(dam: &DeleteAllMessages) -> String {
+    let mut keep = Vec::new();
+    if dam.keep_pinned {
+      keep.push("pinned messages".to_string());
+    }
+    if dam.keep_latest > 0 {
+      keep.push(format!("the latest {} message{}", dam.keep_latest, if dam.keep_latest == 1 { "" } else { "s" }));
+    }
+    if let Some(ref filters) = dam.keep_filters {
+      keep.push(format!("messages from members matching `{}`", filters));
+    }
+    if keep.is_empty() {
+      return String::new();
+    }
+    format!(" Keeping {}.", keep.join(", "))
+  }
 }
@@ -50,7 +50,7 @@ impl<'a> DeleteAllMessagesCommand {
     }
 
     match params {
-      Params::Add(p) => SUBCOMMANDS.add.run(guild, p),
+      Params::Add(p) => SUBCOMMANDS.add.run(ctx, guild, p),
       Params::Remove(p) => SUBCOMMANDS.remove.run(guild, p),
       Params::List => SUBCOMMANDS.list.run(guild)
     }
@@ -0,0 +1,105 @@
+use crate::database::models::{ToU64, LogSettings, NewLogSettings};
+use crate::util::{ParsedDuration, format_duration};
+
+use diesel::prelude::*;
+
+use lalafell::commands::prelude::*;
+use lalafell::error::*;
+
+use serenity::model::id::GuildId;
+
+pub struct AttachmentsCommand;
+
+#[derive(Debug, StructOpt)]
+pub struct Params {
+  #[structopt(short = "e", long = "enable", help = "Start keeping copies of attachments in logged messages")]
+  enable: bool,
+
+  #[structopt(short = "d", long = "disable", help = "Stop keeping copies of attachments and delete the ones kept", conflicts_with = "enable")]
+  disable: bool,
+
+  #[structopt(short = "s", long = "max-size", help = "The largest attachment to keep, in kilobytes")]
+  max_size: Option<u32>,
+
+  #[structopt(short = "t", long = "time", help = "How long to keep attachments for")]
+  time: Option<ParsedDuration>,
+
+  #[structopt(short = "r", long = "reset", help = "Go back to the default size limit and retention", conflicts_with_all = &["max_size", "time"])]
+  reset: bool
+}
+
+/// Attachments can't be kept for longer than this.
+const MAX_RETENTION: u64 = 30 * 86_400;
+
+impl<'a> AttachmentsCommand {
+  #[allow(clippy::needless_pass_by_value)]
+  pub fn run(&self, guild: GuildId, params: Params) -> CommandResult<'a> {
+    let existing: Option<LogSettings> = crate::bot::with_connection(|c| {
+      use crate::database::schema::log_settings::dsl;
+      dsl::log_settings
+        .filter(dsl::server_id.eq(guild.to_u64()))
+        .first(c)
+        .optional()
+    }).chain_err(|| "could not load log settings")?;
+
+    if !params.enable && !params.disable && params.max_size.is_none() && params.time.is_none() && !params.reset {
+      let settings = match existing {
+        Some(ref s) if s.keep_attachments => s,
+        _ => return Ok("Attachments are not being kept.".into()),
+      };
+      return Ok(format!(
+        "Attachments up to {} KB are kept for {}.",
+        settings.max_attachment_size.unwrap_or(LogSettings::DEFAULT_MAX_ATTACHMENT_SIZE) / 1024,
+        format_duration(i64::from(settings.attachment_retention.unwrap_or(LogSettings::DEFAULT_ATTACHMENT_RETENTION))),
+      ).into());
+    }
+
+    let max_kb = LogSettings::DEFAULT_MAX_ATTACHMENT_SIZE as u32 / 1024;
+    if let Some(size) = params.max_size {
+      if size == 0 || size > max_kb {
+        return Err(format!("Attachments up to {} KB can be kept, since larger ones can't be uploaded to the log.", max_kb).into());
+      }
+    }
+    if let Some(ref time) = params.time {
+      if **time == 0 || **time > MAX_RETENTION {
+        return Err(format!("Attachments can be kept for up to {}.", format_duration(MAX_RETENTION as i64)).into());
+      }
+    }
+
+    let max_size = params.max_size.map(|s| s as i32 * 1024);
+    let retention = params.time.map(|t| *t as i32);
+    match existing {
+      Some(mut settings) => {
+        if params.enable {
+          settings.keep_attachments = true;
+        } else if params.disable {
+          settings.keep_attachments = false;
+        }
+        if params.reset {
+          settings.max_attachment_size = None;
+          settings.attachment_retention = None;
+        } else {
+          settings.max_attachment_size = max_size.or(settings.max_attachment_size);
+          settings.attachment_retention = retention.or(settings.attachment_retention);
+        }
+        crate::bot::with_connection(|c| settings.save_changes::<LogSettings>(c)).chain_err(|| "could not update log settings")?;
+      },
+      None => {
+        let new = NewLogSettings {
+          server_id: guild.into(),
+          retention: None,
+          max_messages: None,
+          keep_attachments: params.enable,
+          max_attachment_size: max_size,
+          attachment_retention: retention,
+        };
+        crate::bot::with_connection(|c| {
+          diesel::insert_into(crate::database::schema::log_settings::table)
+            .values(&new)
+            .execute(c)
+        }).chain_err(|| "could not add log settings")?;
+      }
+    }
+    Ok(CommandSuccess::default())
+  }
+}
@@ -0,0 +1,70 @@
+use crate::database::models::{ToU64, LogChannel, NewLogChannel};
+use crate::util::check_channel;
+
+use diesel::prelude::*;
+
+use lalafell::commands::ChannelOrId;
+use lalafell::commands::prelude::*;
+use lalafell::error::*;
+
+use serenity::model::id::{ChannelId, GuildId};
+use serenity::prelude::Mentionable;
+
+pub struct ChannelCommand;
+
+#[derive(Debug, StructOpt)]
+pub struct Params {
+  #[structopt(short = "r", long = "remove", help = "Stop logging events that aren't routed elsewhere", conflicts_with = "channel")]
+  remove: bool,
+
+  #[structopt(help = "The channel to log events to")]
+  channel: Option<ChannelOrId>
+}
+
+impl<'a> ChannelCommand {
+  #[allow(clippy::needless_pass_by_value)]
+  pub fn run(&self, ctx: &Context, guild: GuildId, params: Params) -> CommandResult<'a> {
+    if params.remove {
+      crate::bot::with_connection(|c| {
+        use crate::database::schema::log_channels::dsl;
+        diesel::delete(dsl::log_channels.filter(dsl::server_id.eq(guild.to_u64()))).execute(c)
+      }).chain_err(|| "could not remove log channel")?;
+      return Ok(CommandSuccess::default());
+    }
+
+    let channel = match params.channel {
+      Some(c) => *c,
+      None => {
+        let current: Option<LogChannel> = crate::bot::with_connection(|c| {
+          use crate::database::schema::log_channels::dsl;
+          dsl::log_channels
+            .filter(dsl::server_id.eq(guild.to_u64()))
+            .first(c)
+            .optional()
+        }).chain_err(|| "could not load log channel")?;
+        return Ok(match current {
+          Some(l) => format!("Events are logged to {}.", ChannelId(*l.channel_id).mention()),
+          None => "There is no log channel.".to_string(),
+        }.into());
+      },
+    };
+    if let Err(e) = check_channel(ctx, guild, channel, None) {
+      return Err(e.into());
+    }
+
+    let new = NewLogChannel {
+      server_id: guild.into(),
+      channel_id: channel.into(),
+    };
+    crate::bot::with_connection(|c| {
+      use crate::database::schema::log_channels::dsl;
+      c.transaction(|| {
+        diesel::delete(dsl::log_channels.filter(dsl::server_id.eq(guild.to_u64()))).execute(c)?;
+        diesel::insert_into(dsl::log_channels)
+          .values(&new)
+          .execute(c)
+      })
+    }).chain_err(|| "could not set log channel")?;
+    Ok(CommandSuccess::default())
+  }
+}
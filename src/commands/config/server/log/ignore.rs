@@ -0,0 +1,98 @@
+use crate::database::models::{ToU64, IgnoreKind, NewLogIgnore};
+
+use diesel::prelude::*;
+
+use lalafell::commands::ChannelOrId;
+use lalafell::commands::prelude::*;
+use lalafell::error::*;
+
+use serenity::model::{
+  guild::Guild,
+  id::{ChannelId, GuildId, RoleId},
+};
+use serenity::prelude::Mentionable;
+
+use unicase::UniCase;
+
+pub struct IgnoreCommand;
+
+#[derive(Debug, StructOpt)]
+pub struct Params {
+  #[structopt(short = "r", long = "remove", help = "Log events for the channel or role again")]
+  remove: bool,
+
+  #[structopt(help = "The channel or role to ignore")]
+  #[structopt(use_delimiter = false)]
+  target: Vec<String>
+}
+
+impl<'a> IgnoreCommand {
+  #[allow(clippy::needless_pass_by_value)]
+  pub fn run(&self, ctx: &Context, guild: GuildId, params: Params) -> CommandResult<'a> {
+    let guild = guild.to_guild_cached(&ctx).chain_err(|| "could not find guild")?;
+    let target = params.target.join(" ");
+    let (kind, id) = match IgnoreCommand::find_target(&guild.read(), &target) {
+      Some(t) => t,
+      None => return Err(format!("No channel or role `{}`.", target).into()),
+    };
+    let guild_id = guild.read().id;
+    let mention = match kind {
+      IgnoreKind::Channel => ChannelId(id).mention(),
+      IgnoreKind::Role => RoleId(id).mention(),
+    };
+
+    if params.remove {
+      let removed = crate::bot::with_connection(|c| {
+        use crate::database::schema::log_ignores::dsl;
+        diesel::delete(dsl::log_ignores.filter(dsl::server_id.eq(guild_id.to_u64())
+          .and(dsl::kind.eq(kind.to_string()))
+          .and(dsl::target_id.eq(id as i64))))
+          .execute(c)
+      }).chain_err(|| "could not remove log ignore")?;
+      if removed == 0 {
+        return Err(format!("{} isn't ignored.", mention).into());
+      }
+      return Ok(format!("Events for {} will be logged again.", mention).into());
+    }
+
+    let new = NewLogIgnore {
+      server_id: guild_id.into(),
+      kind: kind.to_string(),
+      target_id: id.into(),
+    };
+    crate::bot::with_connection(|c| {
+      diesel::insert_into(crate::database::schema::log_ignores::table)
+        .values(&new)
+        .on_conflict_do_nothing()
+        .execute(c)
+    }).chain_err(|| "could not add log ignore")?;
+    Ok(format!("Events for {} won't be logged.", mention).into())
+  }
+
+  /// Find a channel or role by mention or ID, or a role by name.
+  fn find_target(guild: &Guild, target: &str) -> Option<(IgnoreKind, u64)> {
+    if target.starts_with("<#") {
+      return target.parse::<ChannelOrId>().ok()
+        .map(|c| c.0)
+        .filter(|c| guild.channels.contains_key(c))
+        .map(|c| (IgnoreKind::Channel, c.0));
+    }
+    if target.starts_with("<@&") && target.ends_with('>') {
+      return target[3..target.len() - 1].parse::<u64>().ok()
+        .filter(|&r| guild.roles.contains_key(&RoleId(r)))
+        .map(|r| (IgnoreKind::Role, r));
+    }
+    if let Ok(id) = target.parse::<u64>() {
+      if guild.channels.contains_key(&ChannelId(id)) {
+        return Some((IgnoreKind::Channel, id));
+      }
+      if guild.roles.contains_key(&RoleId(id)) {
+        return Some((IgnoreKind::Role, id));
+      }
+    }
+    let name = UniCase::new(target);
+    guild.roles.values()
+      .find(|r| UniCase::new(&r.name) == name)
+      .map(|r| (IgnoreKind::Role, r.id.0))
+  }
+}
@@ -0,0 +1,63 @@
+use crate::database::models::{ToU64, LogChannel, LogRoute, LogIgnore, LogCategory, IgnoreKind};
+
+use diesel::prelude::*;
+
+use lalafell::commands::prelude::*;
+use lalafell::error::*;
+
+use serenity::prelude::Mentionable;
+use serenity::model::id::{GuildId, ChannelId, RoleId};
+
+pub struct ListCommand;
+
+impl<'a> ListCommand {
+  pub fn run(&self, guild: GuildId) -> CommandResult<'a> {
+    Ok(ListCommand::list_all(guild)?.into())
+  }
+
+  fn list_all(guild: GuildId) -> Result<String> {
+    let (default, routes, ignores): (Option<LogChannel>, Vec<LogRoute>, Vec<LogIgnore>) = crate::bot::with_connection(|c| {
+      use crate::database::schema::{log_channels, log_ignores, log_routes};
+      let default = log_channels::table
+        .filter(log_channels::server_id.eq(guild.to_u64()))
+        .first(c)
+        .optional()?;
+      let routes = log_routes::table
+        .filter(log_routes::server_id.eq(guild.to_u64()))
+        .load(c)?;
+      let ignores = log_ignores::table
+        .filter(log_ignores::server_id.eq(guild.to_u64()))
+        .order_by(log_ignores::id)
+        .load(c)?;
+      Ok((default, routes, ignores))
+    }).chain_err(|| "could not load log settings")?;
+    let default = default.map(|l| ChannelId(*l.channel_id));
+
+    let mut lines = vec![match default {
+      Some(c) => format!("Log channel: {}", c.mention()),
+      None => "Log channel: none".to_string(),
+    }];
+    for category in &LogCategory::ALL {
+      let destination = match routes.iter().find(|r| r.category() == Some(*category)) {
+        Some(r) => r.channel().map(|c| c.mention()).unwrap_or_else(|| "off".into()),
+        None => match default {
+          Some(c) => format!("{} (log channel)", c.mention()),
+          None => "off (no log channel)".into(),
+        },
+      };
+      lines.push(format!("{} events: {}", category, destination));
+    }
+    if !ignores.is_empty() {
+      let ignored = ignores.iter()
+        .filter_map(|i| match i.kind() {
+          Some(IgnoreKind::Channel) => Some(ChannelId(*i.target_id).mention()),
+          Some(IgnoreKind::Role) => Some(RoleId(*i.target_id).mention()),
+          None => None,
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+      lines.push(format!("Ignoring {}", ignored));
+    }
+    Ok(lines.join("\n"))
+  }
+}
@@ -0,0 +1,88 @@
+mod attachments;
+mod channel;
+mod ignore;
+mod list;
+mod retention;
+mod route;
+
+use lalafell::commands::prelude::*;
+use lalafell::error::*;
+
+use serenity::model::id::{GuildId, UserId};
+
+#[derive(Debug, StructOpt)]
+pub enum Params {
+  #[structopt(name = "channel", about = "Show or change the channel events are logged to")]
+  #[structopt(template = ::lalafell::commands::TEMPLATE)]
+  Channel(channel::Params),
+
+  #[structopt(name = "route", about = "Log a category of events to its own channel")]
+  #[structopt(template = ::lalafell::commands::TEMPLATE)]
+  #[structopt(setting = ::structopt::clap::AppSettings::ArgRequiredElseHelp)]
+  Route(route::Params),
+
+  #[structopt(name = "ignore", about = "Stop logging events in a channel or about members with a role")]
+  #[structopt(template = ::lalafell::commands::TEMPLATE)]
+  #[structopt(setting = ::structopt::clap::AppSettings::ArgRequiredElseHelp)]
+  Ignore(ignore::Params),
+
+  #[structopt(name = "list", alias = "show", about = "List where events are logged and what is ignored")]
+  #[structopt(template = ::lalafell::commands::TEMPLATE)]
+  List,
+
+  #[structopt(name = "retention", about = "Show or change how long messages are kept for the edit and delete logs")]
+  #[structopt(template = ::lalafell::commands::TEMPLATE)]
+  Retention(retention::Params),
+
+  #[structopt(name = "attachments", about = "Show or change whether attachments are kept for the delete log")]
+  #[structopt(template = ::lalafell::commands::TEMPLATE)]
+  Attachments(attachments::Params)
+}
+
+pub struct LogCommand;
+
+impl<'a> LogCommand {
+  pub fn run(&self, ctx: &Context, author: UserId, guild: GuildId, params: Params) -> CommandResult<'a> {
+    struct SubCommands {
+      channel: channel::ChannelCommand,
+      route: route::RouteCommand,
+      ignore: ignore::IgnoreCommand,
+      list: list::ListCommand,
+      retention: retention::RetentionCommand,
+      attachments: attachments::AttachmentsCommand
+    }
+
+    const SUBCOMMANDS: SubCommands = SubCommands {
+      channel: channel::ChannelCommand,
+      route: route::RouteCommand,
+      ignore: ignore::IgnoreCommand,
+      list: list::ListCommand,
+      retention: retention::RetentionCommand,
+      attachments: attachments::AttachmentsCommand
+    };
+
+    let member = guild.member(ctx, author).chain_err(|| "could not get member")?;
+    if !member.permissions(&ctx).chain_err(|| "could not get permissions")?.manage_guild() {
+      return Err(ExternalCommandFailure::default()
+        .message(|e: &mut CreateEmbed| e
+          .title("Not enough permissions.")
+          .description("You don't have enough permissions to use this command."))
+        .wrap());
+    }
+
+    let changes = !matches!(params, Params::List);
+    let res = match params {
+      Params::Channel(p) => SUBCOMMANDS.channel.run(ctx, guild, p),
+      Params::Route(p) => SUBCOMMANDS.route.run(ctx, guild, p),
+      Params::Ignore(p) => SUBCOMMANDS.ignore.run(ctx, guild, p),
+      Params::List => SUBCOMMANDS.list.run(guild),
+      Params::Retention(p) => SUBCOMMANDS.retention.run(guild, p),
+      Params::Attachments(p) => SUBCOMMANDS.attachments.run(guild, p)
+    };
+    // the log keeps each guild's settings around, so it has to load them again after changes
+    if changes {
+      crate::listeners::log::invalidate_config(guild);
+    }
+    res
+  }
+}
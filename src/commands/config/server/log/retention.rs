@@ -0,0 +1,95 @@
+use crate::database::models::{ToU64, LogSettings, NewLogSettings};
+use crate::util::{ParsedDuration, format_duration};
+
+use diesel::prelude::*;
+
+use lalafell::commands::prelude::*;
+use lalafell::error::*;
+
+use serenity::model::id::GuildId;
+
+pub struct RetentionCommand;
+
+#[derive(Debug, StructOpt)]
+pub struct Params {
+  #[structopt(short = "t", long = "time", help = "How long to keep messages for")]
+  time: Option<ParsedDuration>,
+
+  #[structopt(short = "m", long = "messages", help = "How many of the newest messages to keep")]
+  messages: Option<u32>,
+
+  #[structopt(short = "r", long = "reset", help = "Go back to the default retention", conflicts_with_all = &["time", "messages"])]
+  reset: bool
+}
+
+/// Messages can't be kept for longer than this.
+const MAX_RETENTION: u64 = 30 * 86_400;
+
+/// No more than this many messages can be kept per server.
+const MAX_MESSAGES: u32 = 100_000;
+
+impl<'a> RetentionCommand {
+  #[allow(clippy::needless_pass_by_value)]
+  pub fn run(&self, guild: GuildId, params: Params) -> CommandResult<'a> {
+    let existing: Option<LogSettings> = crate::bot::with_connection(|c| {
+      use crate::database::schema::log_settings::dsl;
+      dsl::log_settings
+        .filter(dsl::server_id.eq(guild.to_u64()))
+        .first(c)
+        .optional()
+    }).chain_err(|| "could not load log settings")?;
+
+    if params.time.is_none() && params.messages.is_none() && !params.reset {
+      let (retention, max) = existing
+        .map(|s| (s.retention, s.max_messages))
+        .unwrap_or_default();
+      return Ok(format!(
+        "Messages are kept for {} or until there are more than {} of them.",
+        format_duration(i64::from(retention.unwrap_or(LogSettings::DEFAULT_RETENTION))),
+        max.unwrap_or(LogSettings::DEFAULT_MAX_MESSAGES),
+      ).into());
+    }
+
+    if let Some(ref time) = params.time {
+      if **time == 0 || **time > MAX_RETENTION {
+        return Err(format!("Messages can be kept for up to {}.", format_duration(MAX_RETENTION as i64)).into());
+      }
+    }
+    if let Some(messages) = params.messages {
+      if messages == 0 || messages > MAX_MESSAGES {
+        return Err(format!("Up to {} messages can be kept.", MAX_MESSAGES).into());
+      }
+    }
+
+    let retention = params.time.map(|t| *t as i32);
+    let max_messages = params.messages.map(|m| m as i32);
+    match existing {
+      Some(mut settings) => {
+        if params.reset {
+          settings.retention = None;
+          settings.max_messages = None;
+        } else {
+          settings.retention = retention.or(settings.retention);
+          settings.max_messages = max_messages.or(settings.max_messages);
+        }
+        crate::bot::with_connection(|c| settings.save_changes::<LogSettings>(c)).chain_err(|| "could not update log settings")?;
+      },
+      None => {
+        let new = NewLogSettings {
+          server_id: guild.into(),
+          retention,
+          max_messages,
+          keep_attachments: false,
+          max_attachment_size: None,
+          attachment_retention: None,
+        };
+        crate::bot::with_connection(|c| {
+          diesel::insert_into(crate::database::schema::log_settings::table)
+            .values(&new)
+            .execute(c)
+        }).chain_err(|| "could not add log settings")?;
+      }
+    }
+    Ok(CommandSuccess::default())
+  }
+}
@@ -0,0 +1,65 @@
+use crate::database::models::{ToU64, LogCategory, NewLogRoute};
+use crate::util::check_channel;
+
+use diesel::prelude::*;
+
+use lalafell::commands::ChannelOrId;
+use lalafell::commands::prelude::*;
+use lalafell::error::*;
+
+use serenity::model::id::GuildId;
+
+pub struct RouteCommand;
+
+#[derive(Debug, StructOpt)]
+pub struct Params {
+  #[structopt(help = "The category of events: member, message, moderation, voice or server")]
+  category: LogCategory,
+
+  #[structopt(help = "The channel to log the events to, `off` to stop logging them or `default` to use the log channel")]
+  target: String
+}
+
+impl<'a> RouteCommand {
+  #[allow(clippy::needless_pass_by_value)]
+  pub fn run(&self, ctx: &Context, guild: GuildId, params: Params) -> CommandResult<'a> {
+    let category = params.category.to_string();
+    let channel = match params.target.to_lowercase().as_str() {
+      "default" | "reset" => {
+        crate::bot::with_connection(|c| {
+          use crate::database::schema::log_routes::dsl;
+          diesel::delete(dsl::log_routes.filter(dsl::server_id.eq(guild.to_u64()).and(dsl::category.eq(&category))))
+            .execute(c)
+        }).chain_err(|| "could not remove log route")?;
+        return Ok(CommandSuccess::default());
+      },
+      "off" | "none" => None,
+      _ => {
+        let channel = match params.target.parse::<ChannelOrId>() {
+          Ok(c) => *c,
+          Err(_) => return Err("Invalid channel.".into()),
+        };
+        if let Err(e) = check_channel(ctx, guild, channel, None) {
+          return Err(e.into());
+        }
+        Some(channel.0 as i64)
+      },
+    };
+
+    let new = NewLogRoute {
+      server_id: guild.into(),
+      category,
+      channel_id: channel,
+    };
+    crate::bot::with_connection(|c| {
+      use crate::database::schema::log_routes::dsl;
+      diesel::insert_into(dsl::log_routes)
+        .values(&new)
+        .on_conflict((dsl::server_id, dsl::category))
+        .do_update()
+        .set(dsl::channel_id.eq(new.channel_id))
+        .execute(c)
+    }).chain_err(|| "could not set log route")?;
+    Ok(CommandSuccess::default())
+  }
+}
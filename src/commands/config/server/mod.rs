@@ -1,5 +1,6 @@
 pub mod auto_reply;
 pub mod delete_all_messages;
+pub mod log;
 pub mod reaction;
 pub mod timeout_role;
 
@@ -15,6 +16,7 @@ impl<'a> ServerCommand {
     struct SubCommands {
       auto_reply: auto_reply::AutoReplyCommand,
       delete_all_messages: delete_all_messages::DeleteAllMessagesCommand,
+      log: log::LogCommand,
       reaction: reaction::ReactionCommand,
       timeout_role: timeout_role::TimeoutRoleCommand
     }
@@ -22,6 +24,7 @@ impl<'a> ServerCommand {
     const SUBCOMMANDS: SubCommands = SubCommands {
       auto_reply: auto_reply::AutoReplyCommand,
       delete_all_messages: delete_all_messages::DeleteAllMessagesCommand,
+      log: log::LogCommand,
       reaction: reaction::ReactionCommand,
       timeout_role: timeout_role::TimeoutRoleCommand
     };
@@ -29,6 +32,7 @@ impl<'a> ServerCommand {
     match params {
       Params::AutoReply(p) => SUBCOMMANDS.auto_reply.run(ctx, author, guild, p),
       Params::DeleteAllMessages(p) => SUBCOMMANDS.delete_all_messages.run(ctx, author, guild, p),
+      Params::Log(p) => SUBCOMMANDS.log.run(ctx, author, guild, p),
       Params::Reaction(p) => SUBCOMMANDS.reaction.run(ctx, author, guild, p),
       Params::TimeoutRole(p) => SUBCOMMANDS.timeout_role.run(ctx, author, guild, p)
     }
@@ -49,6 +53,11 @@ pub enum Params {
   #[structopt(template = ::lalafell::commands::TEMPLATE)]
   DeleteAllMessages(delete_all_messages::Params),
 
+  #[structopt(name = "log", alias = "logs", about = "Manage logging settings")]
+  #[structopt(setting = ::structopt::clap::AppSettings::ArgRequiredElseHelp)]
+  #[structopt(template = ::lalafell::commands::TEMPLATE)]
+  Log(log::Params),
+
   #[structopt(name = "reaction", alias = "reactions", about = "Manage reaction role settings")]
   #[structopt(setting = ::structopt::clap::AppSettings::ArgRequiredElseHelp)]
   #[structopt(template = ::lalafell::commands::TEMPLATE)]
@@ -1,4 +1,5 @@
 use crate::database::models::NewReaction;
+use crate::filters::Filter;
 use crate::util::ParsedEmoji;
 
 use diesel::prelude::*;
@@ -7,7 +8,7 @@ use lalafell::commands::ChannelOrId;
 use lalafell::commands::prelude::*;
 use lalafell::error::*;
 
-use serenity::model::id::GuildId;
+use serenity::model::id::{GuildId, MessageId};
 
 use unicase::UniCase;
 
@@ -15,6 +16,11 @@ pub struct AddCommand;
 
 #[derive(Debug, StructOpt)]
 pub struct Params {
+  #[structopt(short = "g", long = "group", help = "The group to put the reaction role in")]
+  group: Option<String>,
+  #[structopt(short = "f", long = "filter", help = "A filter members have to match to get the role")]
+  #[structopt(number_of_values = 1)]
+  filters: Vec<String>,
   #[structopt(help = "The channel to add the reaction role to")]
   channel: ChannelOrId,
   #[structopt(help = "The emoji to trigger the reaction role")]
@@ -36,18 +42,36 @@ impl<'a> AddCommand {
       Some(r) => r.id,
       None => return Err("No such role.".into())
     };
+    let group_id = match params.group {
+      Some(ref name) => match super::group::find_group(guild_id, name)? {
+        Some(g) => Some(g.id),
+        None => return Err("No such group.".into())
+      },
+      None => None
+    };
+    let filters = if params.filters.is_empty() {
+      None
+    } else {
+      match Filter::all_filters(&params.filters.join(" ")) {
+        Some(f) => Some(f.into_iter().map(|x| x.to_string()).collect::<Vec<_>>().join(" ")),
+        None => return Err("Invalid filters.".into())
+      }
+    };
     let new_reaction = NewReaction {
       server_id: guild_id.into(),
       channel_id: (*params.channel).into(),
       message_id: params.message_id.into(),
       emoji: params.emoji.to_string(),
-      role_id: role_id.into()
+      role_id: role_id.into(),
+      group_id,
+      filters
     };
     crate::bot::with_connection(|c| {
       diesel::insert_into(crate::database::schema::reactions::table)
         .values(&new_reaction)
         .execute(c)
     }).chain_err(|| "could not insert reaction")?;
+    crate::commands::role_menu::update_menu(ctx, guild_id, MessageId(params.message_id))?;
     Ok(CommandSuccess::default())
   }
 }
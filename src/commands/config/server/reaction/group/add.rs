@@ -0,0 +1,53 @@
+use crate::database::models::{NewReactionGroup, ReactionGroupMode};
+
+use diesel::prelude::*;
+
+use lalafell::commands::prelude::*;
+use lalafell::error::*;
+
+use serenity::model::id::GuildId;
+
+pub struct AddCommand;
+
+#[derive(Debug, StructOpt)]
+pub struct Params {
+  #[structopt(
+    short = "m",
+    long = "max",
+    help = "The most roles a member can have from the group in limit mode"
+  )]
+  max: Option<u16>,
+  #[structopt(help = "The name of the group")]
+  name: String,
+  #[structopt(help = "How the group behaves: unique, limit, verify or drop")]
+  mode: ReactionGroupMode
+}
+
+impl<'a> AddCommand {
+  #[allow(clippy::needless_pass_by_value)]
+  pub fn run(&self, guild: GuildId, params: Params) -> CommandResult<'a> {
+    let max_roles = match (params.mode, params.max) {
+      (ReactionGroupMode::Limit, Some(0)) => return Err("The limit must be at least one.".into()),
+      (ReactionGroupMode::Limit, Some(max)) => Some(i32::from(max)),
+      (ReactionGroupMode::Limit, None) => return Err("Groups in limit mode need a `--max`.".into()),
+      (_, Some(_)) => return Err("Only groups in limit mode take a `--max`.".into()),
+      (_, None) => None,
+    };
+    let name = params.name.to_lowercase();
+    if super::find_group(guild, &name)?.is_some() {
+      return Err("A group with that name already exists.".into());
+    }
+    let group = NewReactionGroup {
+      server_id: guild.into(),
+      name,
+      mode: params.mode.to_string(),
+      max_roles,
+    };
+    crate::bot::with_connection(|c| {
+      diesel::insert_into(crate::database::schema::reaction_groups::table)
+        .values(&group)
+        .execute(c)
+    }).chain_err(|| "could not insert reaction group")?;
+    Ok(CommandSuccess::default())
+  }
+}
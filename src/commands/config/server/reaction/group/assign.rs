@@ -0,0 +1,66 @@
+use crate::database::models::{ToU64, U64};
+
+use diesel::prelude::*;
+
+use lalafell::commands::prelude::*;
+use lalafell::error::*;
+
+use serenity::model::id::{GuildId, MessageId};
+
+pub struct AssignCommand;
+
+#[derive(Debug, StructOpt)]
+pub struct Params {
+  #[structopt(
+    short = "g",
+    long = "group",
+    help = "The group to put the reaction roles in, leaving them ungrouped if not specified"
+  )]
+  group: Option<String>,
+  #[structopt(help = "The IDs of the reaction roles")]
+  #[structopt(required = true)]
+  ids: Vec<i32>
+}
+
+impl<'a> AssignCommand {
+  #[allow(clippy::needless_pass_by_value)]
+  pub fn run(&self, ctx: &Context, guild: GuildId, params: Params) -> CommandResult<'a> {
+    let group_id = match params.group {
+      Some(ref name) => match super::find_group(guild, name)? {
+        Some(g) => Some(g.id),
+        None => return Err("No such group.".into()),
+      },
+      None => None,
+    };
+    let affected = crate::bot::with_connection(|c| {
+      use crate::database::schema::reactions::dsl;
+      diesel::update(
+        dsl::reactions.filter(dsl::id.eq_any(&params.ids).and(dsl::server_id.eq(guild.to_u64())))
+      )
+        .set(dsl::group_id.eq(group_id))
+        .execute(c)
+    }).chain_err(|| "could not update reactions")?;
+    if affected == 0 {
+      return Err("No reaction roles were updated.".into());
+    }
+
+    // role menus explain how their group works, so they need updating
+    let mut messages: Vec<u64> = crate::bot::with_connection(|c| {
+      use crate::database::schema::reactions::dsl;
+      dsl::reactions
+        .filter(dsl::id.eq_any(&params.ids).and(dsl::server_id.eq(guild.to_u64())))
+        .select(dsl::message_id)
+        .load::<U64>(c)
+    }).chain_err(|| "could not load reactions")?
+      .into_iter()
+      .map(|m| *m)
+      .collect();
+    messages.sort();
+    messages.dedup();
+    for message in messages {
+      crate::commands::role_menu::update_menu(ctx, guild, MessageId(message))?;
+    }
+
+    Ok(format!("Updated {} reaction role{}.", affected, if affected == 1 { "" } else { "s" }).into())
+  }
+}
@@ -0,0 +1,34 @@
+use crate::database::models::{ToU64, ReactionGroup, ReactionGroupMode};
+
+use diesel::prelude::*;
+
+use lalafell::commands::prelude::*;
+use lalafell::error::*;
+
+use serenity::model::id::GuildId;
+
+pub struct ListCommand;
+
+impl<'a> ListCommand {
+  pub fn run(&self, guild: GuildId) -> CommandResult<'a> {
+    let groups: Vec<ReactionGroup> = crate::bot::with_connection(|c| {
+      use crate::database::schema::reaction_groups::dsl;
+      dsl::reaction_groups
+        .filter(dsl::server_id.eq(guild.to_u64()))
+        .order_by(dsl::name)
+        .load(c)
+    }).chain_err(|| "could not load reaction groups")?;
+    if groups.is_empty() {
+      return Ok("No reaction role groups.".into());
+    }
+    Ok(groups.iter()
+      .map(|g| match (g.mode(), g.max_roles) {
+        (Some(ReactionGroupMode::Limit), Some(max)) => format!("**{}**: limit of {} role{}", g.name, max, if max == 1 { "" } else { "s" }),
+        (Some(mode), _) => format!("**{}**: {}", g.name, mode),
+        (None, _) => format!("**{}**: unknown mode `{}`", g.name, g.mode),
+      })
+      .collect::<Vec<_>>()
+      .join("\n")
+      .into())
+  }
+}
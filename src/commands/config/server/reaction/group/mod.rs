@@ -0,0 +1,73 @@
+mod add;
+mod assign;
+mod list;
+mod remove;
+
+use crate::database::models::{ToU64, ReactionGroup};
+
+use diesel::prelude::*;
+
+use lalafell::commands::prelude::*;
+use lalafell::error::*;
+
+use serenity::model::id::GuildId;
+
+#[derive(Debug, StructOpt)]
+pub enum Params {
+  #[structopt(name = "add", alias = "create", about = "Add a reaction role group")]
+  #[structopt(template = ::lalafell::commands::TEMPLATE)]
+  #[structopt(setting = ::structopt::clap::AppSettings::ArgRequiredElseHelp)]
+  Add(add::Params),
+
+  #[structopt(name = "remove", alias = "delete", about = "Remove a reaction role group")]
+  #[structopt(template = ::lalafell::commands::TEMPLATE)]
+  #[structopt(setting = ::structopt::clap::AppSettings::ArgRequiredElseHelp)]
+  Remove(remove::Params),
+
+  #[structopt(name = "assign", alias = "set", about = "Put reaction roles in a group or take them out of one")]
+  #[structopt(template = ::lalafell::commands::TEMPLATE)]
+  #[structopt(setting = ::structopt::clap::AppSettings::ArgRequiredElseHelp)]
+  Assign(assign::Params),
+
+  #[structopt(name = "list", alias = "show", about = "List reaction role groups")]
+  #[structopt(template = ::lalafell::commands::TEMPLATE)]
+  List
+}
+
+pub struct GroupCommand;
+
+impl<'a> GroupCommand {
+  pub fn run(&self, ctx: &Context, guild: GuildId, params: Params) -> CommandResult<'a> {
+    struct SubCommands {
+      add: add::AddCommand,
+      remove: remove::RemoveCommand,
+      assign: assign::AssignCommand,
+      list: list::ListCommand
+    }
+
+    const SUBCOMMANDS: SubCommands = SubCommands {
+      add: add::AddCommand,
+      remove: remove::RemoveCommand,
+      assign: assign::AssignCommand,
+      list: list::ListCommand
+    };
+
+    match params {
+      Params::Add(p) => SUBCOMMANDS.add.run(guild, p),
+      Params::Remove(p) => SUBCOMMANDS.remove.run(guild, p),
+      Params::Assign(p) => SUBCOMMANDS.assign.run(ctx, guild, p),
+      Params::List => SUBCOMMANDS.list.run(guild)
+    }
+  }
+}
+
+/// Find a reaction role group in a guild by its name.
+pub fn find_group(guild: GuildId, name: &str) -> Result<Option<ReactionGroup>> {
+  crate::bot::with_connection(|c| {
+    use crate::database::schema::reaction_groups::dsl;
+    dsl::reaction_groups
+      .filter(dsl::server_id.eq(guild.to_u64()).and(dsl::name.eq(name.to_lowercase())))
+      .first(c)
+      .optional()
+  }).chain_err(|| "could not load reaction group")
+}
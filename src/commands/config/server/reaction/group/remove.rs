@@ -0,0 +1,27 @@
+use diesel::prelude::*;
+
+use lalafell::commands::prelude::*;
+use lalafell::error::*;
+
+use serenity::model::id::GuildId;
+
+pub struct RemoveCommand;
+
+#[derive(Debug, StructOpt)]
+pub struct Params {
+  #[structopt(help = "The name of the group to remove")]
+  name: String
+}
+
+impl<'a> RemoveCommand {
+  #[allow(clippy::needless_pass_by_value)]
+  pub fn run(&self, guild: GuildId, params: Params) -> CommandResult<'a> {
+    let group = match super::find_group(guild, &params.name)? {
+      Some(g) => g,
+      None => return Err("No such group.".into()),
+    };
+    // reaction roles in the group are kept, they just stop being grouped
+    crate::bot::with_connection(|c| diesel::delete(&group).execute(c)).chain_err(|| "could not delete reaction group")?;
+    Ok(CommandSuccess::default())
+  }
+}
@@ -1,4 +1,4 @@
-use crate::database::models::{ToU64, AutoReply};
+use crate::database::models::{ToU64, Reaction, ReactionGroup};
 
 use diesel::prelude::*;
 
@@ -6,7 +6,7 @@ use lalafell::commands::prelude::*;
 use lalafell::error::*;
 
 use serenity::prelude::Mentionable;
-use serenity::model::id::{GuildId, ChannelId};
+use serenity::model::id::{GuildId, ChannelId, RoleId};
 
 pub struct ListCommand;
 
@@ -16,20 +16,26 @@ impl<'a> ListCommand {
   }
 
   fn list_all(guild: GuildId) -> Result<String> {
-    let ars: Vec<AutoReply> = crate::bot::with_connection(|c| {
-      use crate::database::schema::auto_replies::dsl;
-      dsl::auto_replies
-        .filter(dsl::server_id.eq(guild.to_u64()))
+    let reactions: Vec<(Reaction, Option<ReactionGroup>)> = crate::bot::with_connection(|c| {
+      use crate::database::schema::{reactions, reaction_groups};
+      reactions::table
+        .left_join(reaction_groups::table)
+        .filter(reactions::server_id.eq(guild.to_u64()))
+        .order_by(reactions::id)
         .load(c)
-    }).chain_err(|| "could not load auto_replies")?;
-    Ok(ars.iter()
-      .map(|r| format!("{id}. Replying to messages in {channel}{filters} with a delay of {delay} second{plural}.\n```{message}\n```",
+    }).chain_err(|| "could not load reactions")?;
+    if reactions.is_empty() {
+      return Ok("No reaction roles.".into());
+    }
+    Ok(reactions.iter()
+      .map(|(r, g)| format!("{id}. {emoji} on message {message} in {channel} gives {role}{filters}{group}.",
                       id = r.id,
+                      emoji = r.emoji,
+                      message = *r.message_id,
                       channel = ChannelId(*r.channel_id).mention(),
-                      filters = r.filters.as_ref().map(|f| format!(" with filters `{}`", f)).unwrap_or_default(),
-                      delay = r.delay,
-                      plural = if r.delay == 1 { "" } else { "s" },
-                      message = r.message
+                      role = RoleId(*r.role_id).mention(),
+                      filters = r.filters.as_ref().map(|f| format!(" to members matching `{}`", f)).unwrap_or_default(),
+                      group = g.as_ref().map(|g| format!(" (group **{}**)", g.name)).unwrap_or_default()
       ))
       .collect::<Vec<_>>()
       .join("\n"))
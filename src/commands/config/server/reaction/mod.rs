@@ -1,4 +1,5 @@
 mod add;
+pub mod group;
 mod list;
 mod remove;
 
@@ -21,7 +22,12 @@ pub enum Params {
 
   #[structopt(name = "list", alias = "show", about = "List active reaction roles")]
   #[structopt(template = ::lalafell::commands::TEMPLATE)]
-  List
+  List,
+
+  #[structopt(name = "group", alias = "groups", about = "Manage reaction role groups")]
+  #[structopt(template = ::lalafell::commands::TEMPLATE)]
+  #[structopt(setting = ::structopt::clap::AppSettings::ArgRequiredElseHelp)]
+  Group(group::Params)
 }
 
 pub struct ReactionCommand;
@@ -31,13 +37,15 @@ impl<'a> ReactionCommand {
     struct SubCommands {
       add: add::AddCommand,
       remove: remove::RemoveCommand,
-      list: list::ListCommand
+      list: list::ListCommand,
+      group: group::GroupCommand
     }
 
     const SUBCOMMANDS: SubCommands = SubCommands {
       add: add::AddCommand,
       remove: remove::RemoveCommand,
-      list: list::ListCommand
+      list: list::ListCommand,
+      group: group::GroupCommand
     };
 
     let member = guild.member(ctx, author).chain_err(|| "could not get member")?;
@@ -52,7 +60,8 @@ impl<'a> ReactionCommand {
     match params {
       Params::Add(p) => SUBCOMMANDS.add.run(ctx, guild, p),
       Params::Remove(p) => SUBCOMMANDS.remove.run(ctx, guild, p),
-      Params::List => SUBCOMMANDS.list.run(ctx, guild)
+      Params::List => SUBCOMMANDS.list.run(ctx, guild),
+      Params::Group(p) => SUBCOMMANDS.group.run(ctx, guild, p)
     }
   }
 }
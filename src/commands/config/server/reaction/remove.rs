@@ -1,11 +1,12 @@
-use crate::database::models::ToU64;
+use crate::database::models::{ToU64, Reaction};
+use crate::util::parse_emoji;
 
 use diesel::prelude::*;
 
 use lalafell::commands::prelude::*;
 use lalafell::error::*;
 
-use serenity::model::id::GuildId;
+use serenity::model::id::{GuildId, ChannelId, MessageId};
 
 pub struct RemoveCommand;
 
@@ -17,18 +18,27 @@ pub struct Params {
 
 impl<'a> RemoveCommand {
   #[allow(clippy::needless_pass_by_value)]
-  pub fn run(&self, _: &Context, guild: GuildId, params: Params) -> CommandResult<'a> {
-    let affected = crate::bot::with_connection(|c| {
+  pub fn run(&self, ctx: &Context, guild: GuildId, params: Params) -> CommandResult<'a> {
+    let reaction: Option<Reaction> = crate::bot::with_connection(|c| {
       use crate::database::schema::reactions::dsl;
-      diesel::delete(
-        dsl::reactions.filter(dsl::id.eq(params.id).and(dsl::server_id.eq(guild.to_u64())))
-      )
-        .execute(c)
-    }).chain_err(|| "could not delete reaction")?;
-    if affected > 0 {
-      Ok(CommandSuccess::default())
-    } else {
-      Err("No reactions were deleted.".into())
+      dsl::reactions
+        .filter(dsl::id.eq(params.id).and(dsl::server_id.eq(guild.to_u64())))
+        .first(c)
+        .optional()
+    }).chain_err(|| "could not load reaction")?;
+    let reaction = match reaction {
+      Some(r) => r,
+      None => return Err("No reactions were deleted.".into())
+    };
+    crate::bot::with_connection(|c| diesel::delete(&reaction).execute(c)).chain_err(|| "could not delete reaction")?;
+
+    let message = MessageId(*reaction.message_id);
+    if crate::commands::role_menu::find_menu(guild, message)?.is_some() {
+      if let Err(e) = crate::commands::role_menu::clear_reaction(ctx, ChannelId(*reaction.channel_id), message, parse_emoji(&reaction.emoji)) {
+        warn!("could not remove reaction from role menu {}: {}", message, e);
+      }
+      crate::commands::role_menu::update_menu(ctx, guild, message)?;
     }
+    Ok(CommandSuccess::default())
   }
 }
@@ -0,0 +1,48 @@
+use crate::database::models::NewEphemeralMessage;
+use crate::util::{ParsedTime, check_channel};
+
+use chrono::Utc;
+
+use lalafell::error::*;
+use lalafell::commands::prelude::*;
+use lalafell::commands::ChannelOrId;
+
+use serenity::model::permissions::Permissions;
+
+use std::sync::Arc;
+
+pub struct AddCommand;
+
+#[derive(Debug, StructOpt)]
+pub struct Params {
+  #[structopt(
+    short = "c",
+    long = "channel",
+    help = "The channel the message is in"
+  )]
+  channel: ChannelOrId,
+  #[structopt(
+    short = "m",
+    long = "message",
+    help = "The ID of the message to delete"
+  )]
+  message: u64,
+  #[structopt(help = "When to delete the message, either as a date or a duration like \"2h\"")]
+  time: ParsedTime
+}
+
+impl<'a> AddCommand {
+  #[allow(clippy::needless_pass_by_value)]
+  pub fn run(&self, ctx: &Context, msg: &Message, guild_id: GuildId, params: Params) -> CommandResult<'a> {
+    if let Err(e) = check_channel(ctx, guild_id, *params.channel, Some((msg.author.id, Permissions::MANAGE_MESSAGES))) {
+      return Err(e.into());
+    }
+    if *params.time < Utc::now() {
+      return Err("Cannot create an ephemeral message with an expiration date in the past.".into());
+    }
+    let nem = NewEphemeralMessage::new(guild_id.0, params.channel.0, params.message, params.time.timestamp());
+    crate::tasks::ephemeral_messages::schedule_deletion(Arc::clone(&ctx.http), &nem).chain_err(|| "could not schedule message deletion")?;
+
+    Ok(CommandSuccess::default())
+  }
+}
@@ -0,0 +1,32 @@
+use crate::database::models::ToU64;
+
+use diesel::prelude::*;
+
+use lalafell::error::*;
+use lalafell::commands::prelude::*;
+
+pub struct CancelCommand;
+
+#[derive(Debug, StructOpt)]
+pub struct Params {
+  #[structopt(help = "The ID of the ephemeral message to keep")]
+  id: i32
+}
+
+impl<'a> CancelCommand {
+  #[allow(clippy::needless_pass_by_value)]
+  pub fn run(&self, guild_id: GuildId, params: Params) -> CommandResult<'a> {
+    let affected = crate::bot::with_connection(|c| {
+      use crate::database::schema::ephemeral_messages::dsl;
+      diesel::delete(
+        dsl::ephemeral_messages.filter(dsl::id.eq(params.id).and(dsl::guild_id.eq(guild_id.to_u64())))
+      )
+        .execute(c)
+    }).chain_err(|| "could not delete ephemeral message")?;
+    if affected > 0 {
+      Ok(CommandSuccess::default())
+    } else {
+      Err("No ephemeral messages were cancelled.".into())
+    }
+  }
+}
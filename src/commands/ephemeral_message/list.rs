@@ -0,0 +1,43 @@
+use crate::database::models::{ToU64, EphemeralMessage};
+use crate::util::format_duration;
+
+use chrono::{Utc, TimeZone};
+
+use diesel::prelude::*;
+
+use lalafell::error::*;
+use lalafell::commands::prelude::*;
+
+use serenity::prelude::Mentionable;
+use serenity::model::id::ChannelId;
+
+pub struct ListCommand;
+
+impl<'a> ListCommand {
+  pub fn run(&self, guild_id: GuildId) -> CommandResult<'a> {
+    let ephemerals: Vec<EphemeralMessage> = crate::bot::with_connection(|c| {
+      use crate::database::schema::ephemeral_messages::dsl;
+      dsl::ephemeral_messages
+        .filter(dsl::guild_id.eq(guild_id.to_u64()))
+        .order_by(dsl::expires_on)
+        .load(c)
+    }).chain_err(|| "could not load ephemeral messages")?;
+    if ephemerals.is_empty() {
+      return Ok("No ephemeral messages.".into());
+    }
+    let now = Utc::now().timestamp();
+    Ok(ephemerals.iter()
+      .map(|e| format!("{id}. [Message](https://discordapp.com/channels/{guild}/{channel_id}/{message}) in {channel} deleted at {at} UTC (in {left})",
+                      id = e.id,
+                      guild = *e.guild_id,
+                      channel_id = *e.channel_id,
+                      message = *e.message_id,
+                      channel = ChannelId(*e.channel_id).mention(),
+                      at = Utc.timestamp(e.expires_on, 0).format("%Y-%m-%d %H:%M"),
+                      left = format_duration(e.expires_on - now)
+      ))
+      .collect::<Vec<_>>()
+      .join("\n")
+      .into())
+  }
+}
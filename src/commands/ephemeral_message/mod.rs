@@ -0,0 +1,85 @@
+mod add;
+mod cancel;
+mod list;
+mod post;
+
+use lalafell::error::*;
+use lalafell::commands::prelude::*;
+
+use std::sync::Arc;
+
+/// The names and aliases of the subcommands. Anything else is given to `add`.
+const SUBCOMMAND_NAMES: &[&str] = &["add", "create", "post", "say", "list", "show", "cancel", "remove", "delete", "help"];
+
+#[derive(BotCommand)]
+pub struct EphemeralMessageCommand;
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Manage messages that expire after a given time")]
+pub enum Params {
+  #[structopt(name = "add", alias = "create", about = "Set an existing message to expire after a given time")]
+  #[structopt(template = ::lalafell::commands::TEMPLATE)]
+  #[structopt(setting = ::structopt::clap::AppSettings::ArgRequiredElseHelp)]
+  Add(add::Params),
+
+  #[structopt(name = "post", alias = "say", about = "Post a message that expires after a given time")]
+  #[structopt(template = ::lalafell::commands::TEMPLATE)]
+  #[structopt(setting = ::structopt::clap::AppSettings::ArgRequiredElseHelp)]
+  Post(post::Params),
+
+  #[structopt(name = "list", alias = "show", about = "List messages waiting to expire")]
+  #[structopt(template = ::lalafell::commands::TEMPLATE)]
+  List,
+
+  #[structopt(name = "cancel", aliases = &["remove", "delete"], about = "Stop a message from expiring")]
+  #[structopt(template = ::lalafell::commands::TEMPLATE)]
+  #[structopt(setting = ::structopt::clap::AppSettings::ArgRequiredElseHelp)]
+  Cancel(cancel::Params)
+}
+
+impl HasParams for EphemeralMessageCommand {
+  type Params = Params;
+}
+
+impl<'a> PublicChannelCommand<'a> for EphemeralMessageCommand {
+  fn run(&self, ctx: &Context, msg: &Message, guild_id: GuildId, _: Arc<RwLock<GuildChannel>>, params: &[&str]) -> CommandResult<'a> {
+    struct SubCommands {
+      add: add::AddCommand,
+      post: post::PostCommand,
+      list: list::ListCommand,
+      cancel: cancel::CancelCommand
+    }
+
+    const SUBCOMMANDS: SubCommands = SubCommands {
+      add: add::AddCommand,
+      post: post::PostCommand,
+      list: list::ListCommand,
+      cancel: cancel::CancelCommand
+    };
+
+    let member = guild_id.member(ctx, &msg.author).chain_err(|| "could not get member")?;
+    if !member.permissions(&ctx).chain_err(|| "could not get permissions")?.manage_messages() {
+      return Err(ExternalCommandFailure::default()
+        .message(|e: &mut CreateEmbed| e
+          .title("Not enough permissions.")
+          .description("You don't have enough permissions to use this command."))
+        .wrap());
+    }
+
+    // `!ephemeral -c <channel> -m <message> <time>` was the only form before there were subcommands
+    let params: Vec<&str> = match params.first() {
+      Some(first) if !["-h", "--help"].contains(first) && (first.starts_with('-') || !SUBCOMMAND_NAMES.contains(&first.to_lowercase().as_str())) => {
+        std::iter::once("add").chain(params.iter().cloned()).collect()
+      },
+      _ => params.to_vec(),
+    };
+    let params = self.params_then("ephemeralmessage", &params, |a| a.setting(structopt::clap::AppSettings::ArgRequiredElseHelp))?;
+
+    match params {
+      Params::Add(p) => SUBCOMMANDS.add.run(ctx, msg, guild_id, p),
+      Params::Post(p) => SUBCOMMANDS.post.run(ctx, msg, guild_id, msg.channel_id, p),
+      Params::List => SUBCOMMANDS.list.run(guild_id),
+      Params::Cancel(p) => SUBCOMMANDS.cancel.run(guild_id, p)
+    }
+  }
+}
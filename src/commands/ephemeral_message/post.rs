@@ -0,0 +1,55 @@
+use crate::database::models::NewEphemeralMessage;
+use crate::util::{ParsedTime, check_channel, message_content};
+
+use chrono::Utc;
+
+use lalafell::error::*;
+use lalafell::commands::prelude::*;
+use lalafell::commands::ChannelOrId;
+
+use serenity::model::{
+  id::ChannelId,
+  permissions::Permissions,
+};
+
+use std::sync::Arc;
+
+pub struct PostCommand;
+
+#[derive(Debug, StructOpt)]
+pub struct Params {
+  #[structopt(
+    short = "c",
+    long = "channel",
+    help = "The channel to post the message in, if not this one"
+  )]
+  channel: Option<ChannelOrId>,
+  #[structopt(help = "When to delete the message, either as a date or a duration like \"2h\"")]
+  time: ParsedTime,
+  #[structopt(help = "The message to post, or put it on the lines after the command")]
+  #[structopt(use_delimiter = false)]
+  message: Vec<String>
+}
+
+impl<'a> PostCommand {
+  #[allow(clippy::needless_pass_by_value)]
+  pub fn run(&self, ctx: &Context, msg: &Message, guild_id: GuildId, channel_id: ChannelId, params: Params) -> CommandResult<'a> {
+    if *params.time < Utc::now() {
+      return Err("Cannot create an ephemeral message with an expiration date in the past.".into());
+    }
+    let content = message_content(msg, &params.message);
+    if content.is_empty() {
+      return Err("Empty message.".into());
+    }
+    let channel = params.channel.map(|c| *c).unwrap_or(channel_id);
+    if let Err(e) = check_channel(ctx, guild_id, channel, Some((msg.author.id, Permissions::SEND_MESSAGES))) {
+      return Err(e.into());
+    }
+    let posted = channel.send_message(ctx, |m| m.content(&content)).chain_err(|| "could not post message")?;
+
+    let nem = NewEphemeralMessage::new(guild_id.0, channel.0, posted.id.0, params.time.timestamp());
+    crate::tasks::ephemeral_messages::schedule_deletion(Arc::clone(&ctx.http), &nem).chain_err(|| "could not schedule message deletion")?;
+
+    Ok(CommandSuccess::default())
+  }
+}
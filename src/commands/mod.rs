@@ -15,8 +15,11 @@ pub mod random_reaction;
 pub mod reference_count;
 pub mod reload_config;
 pub mod report;
+pub mod role_menu;
+pub mod schedule;
 pub mod search;
 pub mod tag;
+pub mod temporary_access;
 pub mod temporary_role;
 pub mod timeout;
 pub mod verify;
@@ -38,8 +41,11 @@ pub use self::random_reaction::RandomReactionCommand;
 pub use self::reference_count::ReferenceCountCommand;
 pub use self::reload_config::ReloadConfigCommand;
 pub use self::report::ReportCommand;
+pub use self::role_menu::RoleMenuCommand;
+pub use self::schedule::ScheduleCommand;
 pub use self::search::SearchCommand;
 pub use self::tag::{TagCommand, AutoTagCommand, QueueTagCommand, UpdateTagsCommand, UpdateTagCommand};
+pub use self::temporary_access::TemporaryAccessCommand;
 pub use self::temporary_role::TemporaryRoleCommand;
 pub use self::timeout::{TimeoutCommand, UntimeoutCommand};
 pub use self::verify::VerifyCommand;
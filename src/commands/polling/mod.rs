@@ -1,5 +1,373 @@
 pub mod poll;
 pub mod poll_results;
+pub mod ranked;
 
 pub use self::poll::PollCommand;
 pub use self::poll_results::PollResultsCommand;
+
+use crate::{
+  database::models::{ToU64, Poll, PollOption},
+  filters::{Filter, FilterKind},
+  util::parse_emoji,
+};
+
+use chrono::{Utc, TimeZone};
+
+use diesel::prelude::*;
+
+use std::{
+  collections::HashMap,
+  sync::Arc,
+};
+
+use lalafell::error::*;
+
+use serenity::{
+  Error as SError,
+  builder::CreateEmbed,
+  http::{Http, HttpError, StatusCode},
+  model::{
+    guild::{Guild, Member, Role},
+    id::{ChannelId, MessageId, UserId},
+  },
+  prelude::RwLock,
+};
+
+/// Find the stored poll posted as a message.
+pub fn find_poll(message: MessageId) -> Result<Option<Poll>> {
+  crate::bot::with_connection(|c| {
+    use crate::database::schema::polls::dsl;
+    dsl::polls
+      .filter(dsl::message_id.eq(message.to_u64()))
+      .first(c)
+      .optional()
+  }).chain_err(|| "could not load poll")
+}
+
+/// Get a poll's options in order.
+pub fn poll_options(poll: &Poll) -> Result<Vec<PollOption>> {
+  crate::bot::with_connection(|c| {
+    use crate::database::schema::poll_options::dsl;
+    dsl::poll_options
+      .filter(dsl::poll_id.eq(poll.id))
+      .order_by(dsl::position)
+      .load(c)
+  }).chain_err(|| "could not load poll options")
+}
+
+/// Get the members who reacted with each option, leaving out the bot's own reactions.
+pub fn voters<H: AsRef<Http>>(http: H, bot: UserId, poll: &Poll, options: &[PollOption]) -> Result<Vec<Vec<UserId>>> {
+  let channel = ChannelId(*poll.channel_id);
+  let message = MessageId(*poll.message_id);
+  let mut all = Vec::with_capacity(options.len());
+  for option in options {
+    let emoji = parse_emoji(&option.emoji);
+    let mut users = Vec::new();
+    let mut after: Option<UserId> = None;
+    loop {
+      let page = channel.reaction_users(&http, message, emoji.clone(), Some(100), after)
+        .chain_err(|| "could not get poll votes")?;
+      let full = page.len() >= 100;
+      after = page.iter().map(|u| u.id).max();
+      users.extend(page.into_iter().map(|u| u.id).filter(|&u| u != bot));
+      if !full {
+        break;
+      }
+    }
+    all.push(users);
+  }
+  Ok(all)
+}
+
+/// The votes for each option of a poll.
+#[derive(Debug, Default)]
+pub struct Tally {
+  pub votes: Vec<u64>,
+  /// How many members' votes counted.
+  pub voters: u64,
+}
+
+impl Tally {
+  /// The final results stored with a closed poll.
+  pub fn stored(poll: &Poll, options: &[PollOption]) -> Tally {
+    Tally {
+      votes: options.iter().map(|o| o.votes.unwrap_or_default() as u64).collect(),
+      voters: poll.voters.unwrap_or_default() as u64,
+    }
+  }
+}
+
+/// Decides whose votes count in a poll, based on its filters and whether members have to have
+/// joined before it was posted.
+pub struct Eligibility<'a> {
+  filters: Option<Vec<Filter>>,
+  guild: Option<&'a Guild>,
+  roles: Vec<&'a Role>,
+  /// Members that weren't cached, from [`Eligibility::fetch_uncached`].
+  fetched: HashMap<UserId, Member>,
+}
+
+impl<'a> Eligibility<'a> {
+  pub fn new(poll: &Poll, guild: Option<&'a Guild>, fetched: HashMap<UserId, Member>) -> Self {
+    let mut filters = poll.filters.as_ref().map(|f| Filter::all_filters(f).unwrap_or_else(|| {
+      warn!("poll {} has invalid filters: `{}`", poll.id, f);
+      Vec::new()
+    }));
+    // joining before the poll is just another filter, with the time the poll was posted
+    if poll.joined_before {
+      let posted = MessageId(*poll.message_id).created_at().timestamp();
+      filters.get_or_insert_with(Vec::new).push(Filter::Include(FilterKind::JoinedBefore(posted)));
+    }
+    Eligibility {
+      filters,
+      guild,
+      roles: guild.map(|g| g.roles.values().collect()).unwrap_or_default(),
+      fetched,
+    }
+  }
+
+  /// Fetch the members among `users` that aren't cached, if the poll needs to check members at all.
+  /// The guild is only locked to see who's missing, not while fetching.
+  pub fn fetch_uncached<H: AsRef<Http>>(http: H, poll: &Poll, guild: Option<&Arc<RwLock<Guild>>>, users: &[UserId]) -> HashMap<UserId, Member> {
+    let mut fetched = HashMap::new();
+    if poll.filters.is_none() && !poll.joined_before {
+      return fetched;
+    }
+    let mut missing: Vec<UserId> = match guild {
+      Some(g) => {
+        let g = g.read();
+        users.iter().filter(|u| !g.members.contains_key(u)).cloned().collect()
+      },
+      None => users.to_vec(),
+    };
+    missing.sort();
+    missing.dedup();
+    for user in missing {
+      match http.as_ref().get_member(*poll.server_id, user.0) {
+        Ok(m) => { fetched.insert(user, m); },
+        // members who left can't vote
+        Err(e) => debug!("could not fetch voter {} in poll {}: {}", user, poll.id, e),
+      }
+    }
+    fetched
+  }
+
+  pub fn allows(&self, user: UserId) -> bool {
+    let filters = some_or!(self.filters.as_ref(), return true);
+    // members who left or can't be found can't be checked
+    let member = match self.guild.and_then(|g| g.members.get(&user)).or_else(|| self.fetched.get(&user)) {
+      Some(m) => m,
+      None => return false,
+    };
+    filters.iter().all(|f| f.matches(member, &self.roles))
+  }
+}
+
+/// Count the votes for each option, leaving out members who aren't eligible to vote. Members over
+/// the poll's limit, which can happen with reactions added while the bot was offline, only have
+/// their first options by position counted.
+pub fn count_votes(poll: &Poll, voters: &[Vec<UserId>], guild: Option<&Guild>, fetched: HashMap<UserId, Member>) -> Tally {
+  let eligibility = Eligibility::new(poll, guild, fetched);
+  let limit = poll.vote_limit().unwrap_or(std::usize::MAX);
+  let mut counted: HashMap<UserId, usize> = HashMap::new();
+  let votes = voters.iter()
+    .map(|users| users.iter()
+      .filter(|&&u| {
+        if !counted.contains_key(&u) && !eligibility.allows(u) {
+          return false;
+        }
+        let count = counted.entry(u).or_insert(0);
+        *count += 1;
+        *count <= limit
+      })
+      .count() as u64)
+    .collect();
+  Tally {
+    votes,
+    voters: counted.len() as u64,
+  }
+}
+
+/// Check if a member has reacted to a message with an emoji.
+pub fn has_reacted<H: AsRef<Http>>(http: H, channel: ChannelId, message: MessageId, emoji: &str, user: UserId) -> Result<bool> {
+  // reaction users are sorted by ID, so the first one after the ID just below theirs is them if
+  // they reacted
+  let after = UserId(user.0.saturating_sub(1));
+  let users = channel.reaction_users(&http, message, parse_emoji(emoji), Some(1), Some(after))
+    .chain_err(|| "could not get reaction users")?;
+  Ok(users.first().map(|u| u.id == user).unwrap_or(false))
+}
+
+/// Fill in a poll's embed. `tally` is the final results, for closed polls.
+pub fn render_poll<'a>(e: &'a mut CreateEmbed, title: &str, poll: &Poll, options: &[PollOption], tally: Option<&Tally>) -> &'a mut CreateEmbed {
+  let lines = match tally {
+    Some(tally) => result_lines(options, tally),
+    None => options.iter()
+      .map(|o| format!("{} – {}", o.emoji, o.text))
+      .collect::<Vec<_>>()
+      .join("\n"),
+  };
+  let mut notes = Vec::new();
+  if poll.ranked {
+    notes.push(format!("React with {} to rank the options in a DM.", ranked::BALLOT_EMOJI));
+  }
+  match poll.vote_limit() {
+    Some(1) => notes.push("Vote for one option.".to_string()),
+    Some(n) => notes.push(format!("Vote for up to {} options.", n)),
+    None => {},
+  }
+  if poll.joined_before {
+    notes.push("Only members who joined before this poll was posted can vote.".into());
+  }
+  if let Some(ref filters) = poll.filters {
+    notes.push(format!("Only votes from members matching `{}` count.", filters));
+  }
+  let notes = if notes.is_empty() || poll.closed {
+    String::new()
+  } else {
+    format!("\n\n*{}*", notes.join(" "))
+  };
+  e.title(title);
+  e.description(format!("{}\n{}{}", poll.text, lines, notes));
+  if poll.closed {
+    e.footer(|f| f.text(format!("{} · Closed", *poll.message_id)));
+    e.timestamp(&Utc::now());
+  } else if let Some(ends) = poll.ends_at {
+    e.footer(|f| f.text(format!("{} · Closes", *poll.message_id)));
+    e.timestamp(&Utc.timestamp(ends, 0));
+  } else {
+    e.footer(|f| f.text((*poll.message_id).to_string()));
+  }
+  e
+}
+
+/// Fill in an embed with a poll's results. Ranked polls show the rounds of their runoff.
+pub fn render_results<'a>(e: &'a mut CreateEmbed, title: &str, poll: &Poll, options: &[PollOption], tally: &Tally, rounds: Option<&str>) -> &'a mut CreateEmbed {
+  let mut description = format!("{}\n\n", poll.text);
+  let winners = winners(options, tally);
+  if winners.len() > 1 {
+    description.push_str(&format!("**Tie between {}**\n\n", winners.join(" and ")));
+  }
+  description.push_str(&result_lines(options, tally));
+  description.push_str(&format!(
+    "\n\n[Go to poll](https://discordapp.com/channels/{}/{}/{})",
+    *poll.server_id,
+    *poll.channel_id,
+    *poll.message_id,
+  ));
+  if let Some(rounds) = rounds {
+    e.field("Rounds", rounds, false);
+  }
+  e
+    .title(title)
+    .description(description)
+    .footer(|f| f.text(format!("{} voter{}", tally.voters, if tally.voters == 1 { "" } else { "s" })))
+}
+
+/// Count a poll's votes as they are now. Ranked polls also get a description of their runoff.
+pub fn current_results<H: AsRef<Http>>(http: H, bot: UserId, guild: Option<Arc<RwLock<Guild>>>, poll: &Poll, options: &[PollOption]) -> Result<(Tally, Option<String>)> {
+  if poll.ranked {
+    let (tally, runoff) = ranked::tally(&http, poll, options, guild.as_ref())?;
+    return Ok((tally, Some(ranked::describe_runoff(options, &runoff))));
+  }
+  // get the reactions and any uncached voters before locking the guild
+  let voters = voters(&http, bot, poll, options)?;
+  let all: Vec<UserId> = voters.iter().flatten().cloned().collect();
+  let fetched = Eligibility::fetch_uncached(&http, poll, guild.as_ref(), &all);
+  let guild = guild.as_ref().map(|g| g.read());
+  let guild = guild.as_ref().map(|g| &**g);
+  Ok((count_votes(poll, &voters, guild, fetched), None))
+}
+
+/// The options with the most votes, if any votes were cast.
+fn winners(options: &[PollOption], tally: &Tally) -> Vec<String> {
+  let most = tally.votes.iter().cloned().max().unwrap_or_default();
+  if most == 0 {
+    return Vec::new();
+  }
+  options.iter()
+    .zip(&tally.votes)
+    .filter(|&(_, &count)| count == most)
+    .map(|(o, _)| o.emoji.clone())
+    .collect()
+}
+
+/// Show each option with a bar for its share of the voters. The options with the most votes are
+/// bolded.
+fn result_lines(options: &[PollOption], tally: &Tally) -> String {
+  const BAR_WIDTH: u64 = 12;
+  let winners = winners(options, tally);
+  options.iter()
+    .zip(&tally.votes)
+    .map(|(o, &count)| {
+      let percent = if tally.voters == 0 { 0 } else { count * 100 / tally.voters };
+      let filled = if tally.voters == 0 { 0 } else { (count * BAR_WIDTH + tally.voters / 2) / tally.voters };
+      let bar = format!("{}{}", "█".repeat(filled as usize), "░".repeat((BAR_WIDTH - filled.min(BAR_WIDTH)) as usize));
+      let text = if winners.contains(&o.emoji) { format!("**{}**", o.text) } else { o.text.clone() };
+      format!("{} {}\n`{}` {}% · {}", o.emoji, text, bar, percent, plural_votes(count))
+    })
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+/// Close a poll, storing its final results, showing them in the poll and posting a summary.
+pub fn close_poll<H: AsRef<Http>>(http: H, bot: UserId, guild: Option<Arc<RwLock<Guild>>>, mut poll: Poll) -> Result<()> {
+  let channel = ChannelId(*poll.channel_id);
+  let message = MessageId(*poll.message_id);
+  let mut options = poll_options(&poll)?;
+  let (tally, rounds) = match current_results(&http, bot, guild, &poll, &options) {
+    Ok(r) => r,
+    Err(e) => {
+      // votes on a deleted poll can't be counted, so close it without results instead of trying
+      // again forever
+      match channel.message(&http, message) {
+        Err(SError::Http(box HttpError::UnsuccessfulRequest(ref r))) if r.status_code == StatusCode::NOT_FOUND => {},
+        _ => return Err(e),
+      }
+      poll.closed = true;
+      crate::bot::with_connection(|c| poll.save_changes::<Poll>(c)).chain_err(|| "could not close poll")?;
+      let posted = channel.send_message(&http, |m| m.embed(|e| e
+        .title("Poll – closed")
+        .description(format!("{}\n\nThe poll was deleted, so its results are unavailable.", poll.text))));
+      if let Err(e) = posted {
+        info!("could not post that poll {} was deleted: {}", poll.id, e);
+      }
+      return Ok(());
+    },
+  };
+
+  poll.closed = true;
+  poll.voters = Some(tally.voters as i32);
+  for (option, &count) in options.iter_mut().zip(&tally.votes) {
+    option.votes = Some(count as i32);
+  }
+  crate::bot::with_connection(|c| {
+    c.transaction(|| {
+      poll.save_changes::<Poll>(c)?;
+      for option in &options {
+        option.save_changes::<PollOption>(c)?;
+      }
+      Ok(())
+    })
+  }).chain_err(|| "could not save poll results")?;
+
+  let title = match channel.message(&http, message) {
+    Ok(m) => {
+      let title = m.embeds.get(0).and_then(|e| e.title.clone()).unwrap_or_else(|| "Poll".into());
+      if let Err(e) = channel.edit_message(&http, message, |m| m.embed(|e| render_poll(e, &title, &poll, &options, Some(&tally)))) {
+        warn!("could not show results in poll {}: {}", poll.id, e);
+      }
+      title
+    },
+    // the poll was deleted, but its results can still be posted
+    Err(_) => "Poll".into(),
+  };
+
+  channel.send_message(&http, |m| m.embed(|e| render_results(e, &format!("{} – closed", title), &poll, &options, &tally, rounds.as_ref().map(String::as_str))))
+    .chain_err(|| "could not post poll results")?;
+  Ok(())
+}
+
+fn plural_votes(count: u64) -> String {
+  format!("{} vote{}", count, if count == 1 { "" } else { "s" })
+}
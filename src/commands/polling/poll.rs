@@ -1,84 +1,327 @@
-use lalafell::commands::prelude::*;
+use crate::{
+  commands::ChannelOrId,
+  filters::Filter,
+  database::models::{ToU64, Poll, NewPoll, NewPollOption},
+  util::{ParsedDuration, format_duration, parse_emoji},
+};
+
+use super::{poll_options, render_poll, ranked};
 
-use serenity::model::id::UserId;
-use serenity::model::channel::Channel;
-use serenity::builder::CreateEmbed;
+use chrono::Utc;
 
+use diesel::prelude::*;
+
+use lalafell::commands::prelude::*;
 use lalafell::error::*;
 
-const USAGE: &str = "!poll <poll text>\n<option>\n<option>...";
+use serenity::{
+  builder::CreateEmbed,
+  model::{
+    channel::Channel,
+    guild::Guild,
+    id::{ChannelId, UserId},
+    misc::EmojiIdentifier,
+  },
+  prelude::Mentionable,
+};
+
+use std::{
+  str::FromStr,
+  sync::Arc,
+};
+
+const USAGE: &str = "!poll [#channel] [--ends <duration>] [--single | --max <n> | --ranked] [--filter <filter>] [--joined-before] <poll text>\n[emoji] <option>\n[emoji] <option>...\n\n!poll list";
+
+/// The most options a poll can have, since that's how many reactions a message can have.
+const MAX_OPTIONS: usize = 20;
 
 #[derive(BotCommand)]
 pub struct PollCommand;
 
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Post a poll, with its options on the lines after the command")]
+pub struct Params {
+  #[structopt(short = "e", long = "ends", help = "How long the poll stays open before its results are posted")]
+  ends: Option<ParsedDuration>,
+
+  #[structopt(short = "s", long = "single", help = "Only allow voting for one option, taking back other votes")]
+  single: bool,
+
+  #[structopt(short = "m", long = "max", help = "The most options each member can vote for", conflicts_with = "single")]
+  max: Option<u32>,
+
+  #[structopt(short = "r", long = "ranked", help = "Have members rank the options in a DM, finding the winner by instant runoff", conflicts_with_all = &["single", "max"])]
+  ranked: bool,
+
+  #[structopt(short = "f", long = "filter", help = "A filter members have to match for their votes to count, like `role:Verified` or `joined:2020-06-01`")]
+  #[structopt(number_of_values = 1)]
+  filters: Vec<String>,
+
+  #[structopt(short = "j", long = "joined-before", help = "Only count votes from members who joined before the poll was posted, like a `joined:` filter with the poll's time")]
+  joined_before: bool,
+
+  #[structopt(help = "The poll text")]
+  #[structopt(use_delimiter = false)]
+  text: Vec<String>
+}
+
+impl HasParams for PollCommand {
+  type Params = Params;
+}
+
 impl PollCommand {
+  /// Parse option lines, which can start with the emoji to vote for them with. Options without an
+  /// emoji get numbers, or letters if there are more than nine options.
+  fn parse_options(guild: &Guild, lines: &[&str]) -> std::result::Result<Vec<(String, String)>, String> {
+    let mut parsed: Vec<(Option<String>, String)> = Vec::with_capacity(lines.len());
+    for line in lines {
+      let (emoji, text) = match line.find(char::is_whitespace) {
+        Some(i) if PollCommand::is_emoji(&line[..i]) => (Some(&line[..i]), line[i..].trim()),
+        _ => (None, *line),
+      };
+      let emoji = match emoji {
+        Some(e) => {
+          if let Ok(custom) = EmojiIdentifier::from_str(e) {
+            if !guild.emojis.contains_key(&custom.id) {
+              return Err(format!("{} isn't an emoji from this server.", e));
+            }
+          }
+          Some(parse_emoji(e).to_string())
+        },
+        None => None,
+      };
+      if let Some(ref e) = emoji {
+        if parsed.iter().any(|(other, _)| other.as_ref() == Some(e)) {
+          return Err(format!("{} is used for more than one option.", e));
+        }
+      }
+      parsed.push((emoji, text.to_string()));
+    }
+
+    let automatic: Vec<String> = if lines.len() <= 9 {
+      (1..=9).map(|i| format!("{}⃣", i)).collect()
+    } else {
+      // regional indicator letters
+      (0..MAX_OPTIONS as u32).filter_map(|i| std::char::from_u32(0x1F1E6 + i)).map(String::from).collect()
+    };
+    let mut automatic = automatic.into_iter()
+      .filter(|a| !parsed.iter().any(|(e, _)| e.as_ref() == Some(a)));
+    parsed.into_iter()
+      .map(|(emoji, text)| match emoji {
+        Some(e) => Ok((e, text)),
+        None => automatic.next()
+          .map(|e| (e, text))
+          .ok_or_else(|| "There aren't enough emoji left for the options without one.".to_string()),
+      })
+      .collect()
+  }
+
+  fn is_emoji(word: &str) -> bool {
+    EmojiIdentifier::from_str(word).is_ok()
+      || word.ends_with('\u{20e3}')
+      || (word.chars().any(PollCommand::is_pictograph)
+        && word.chars().all(|c| PollCommand::is_pictograph(c) || PollCommand::is_emoji_modifier(c)))
+  }
+
+  /// Check if a character is in one of the blocks Unicode emoji are drawn from. Other symbols, like
+  /// dashes or ellipses, aren't emoji and can't be reacted with.
+  fn is_pictograph(c: char) -> bool {
+    match c as u32 {
+      0x1F000..=0x1FAFF
+        | 0x2600..=0x27BF
+        | 0x2300..=0x23FF
+        | 0x2B00..=0x2BFF
+        | 0x2190..=0x21FF
+        | 0x25A0..=0x25FF
+        | 0x2934 | 0x2935
+        | 0x3030 | 0x303D | 0x3297 | 0x3299
+        | 0x00A9 | 0x00AE | 0x203C | 0x2049 | 0x2122 | 0x2139 | 0x24C2 => true,
+      _ => false,
+    }
+  }
+
+  /// Check if a character only changes how the emoji before it looks, like variation selectors,
+  /// zero-width joiners and tag characters.
+  fn is_emoji_modifier(c: char) -> bool {
+    match c as u32 {
+      0xFE0E | 0xFE0F | 0x200D | 0xE0020..=0xE007F => true,
+      _ => false,
+    }
+  }
+
   fn nick_or_name(&self, ctx: &Context, guild: GuildId, user: UserId) -> Option<String> {
     match guild.member(ctx, user) {
       Ok(m) => Some(m.display_name().to_string()),
       Err(_) => None
     }
   }
+
+  fn list<'a>(&self, guild: GuildId) -> CommandResult<'a> {
+    let polls: Vec<Poll> = crate::bot::with_connection(|c| {
+      use crate::database::schema::polls::dsl;
+      dsl::polls
+        .filter(dsl::server_id.eq(guild.to_u64()).and(dsl::closed.eq(false)))
+        .order_by(dsl::id)
+        .load(c)
+    }).chain_err(|| "could not load polls")?;
+    if polls.is_empty() {
+      return Ok("No open polls.".into());
+    }
+    let now = Utc::now().timestamp();
+    Ok(polls.iter()
+      .map(|p| format!("{id}. [{text}](https://discordapp.com/channels/{guild}/{channel_id}/{message}) in {channel}, {ends}",
+                      id = p.id,
+                      text = p.text,
+                      guild = *p.server_id,
+                      channel_id = *p.channel_id,
+                      message = *p.message_id,
+                      channel = ChannelId(*p.channel_id).mention(),
+                      ends = match p.ends_at {
+                        Some(e) => format!("closes in {}", format_duration(e - now)),
+                        None => "no deadline".into(),
+                      }
+      ))
+      .collect::<Vec<_>>()
+      .join("\n")
+      .into())
+  }
 }
 
-impl<'a> Command<'a> for PollCommand {
-  fn run(&self, ctx: &Context, msg: &Message, _: &[&str]) -> CommandResult<'a> {
+impl<'a> PublicChannelCommand<'a> for PollCommand {
+  fn run(&self, ctx: &Context, msg: &Message, guild_id: GuildId, channel: Arc<RwLock<GuildChannel>>, _: &[&str]) -> CommandResult<'a> {
     let lines: Vec<&str> = msg.content.split('\n').collect();
-    let params: Vec<&str> = lines[0].split_whitespace().skip(1).collect();
-    let options = &lines[1..];
-    if params.is_empty() || options.len() < 2 {
+    let mut words: Vec<&str> = lines[0].split_whitespace().skip(1).collect();
+    if words == ["list"] && lines.len() == 1 {
+      return self.list(guild_id);
+    }
+    // the poll can be posted in another channel by mentioning it first
+    let target = match words.first().filter(|w| w.starts_with("<#")).map(|w| w.parse::<ChannelOrId>()) {
+      Some(Ok(c)) => {
+        words.remove(0);
+        Some(*c)
+      },
+      Some(Err(_)) => return Err("Invalid channel.".into()),
+      None => None,
+    };
+    let options: Vec<&str> = lines[1..].iter().map(|l| l.trim()).filter(|l| !l.is_empty()).collect();
+    if words.is_empty() || options.len() < 2 {
       return Err(ExternalCommandFailure::default()
         .message(|e: &mut CreateEmbed| e
           .title("Not enough parameters.")
           .description(USAGE))
         .wrap());
     }
-    if options.len() > 9 {
-      return Err("No more than nine poll options can be specified.".into());
+    let params = self.params("poll", &words)?;
+    if options.len() > MAX_OPTIONS {
+      return Err(format!("No more than {} poll options can be specified.", MAX_OPTIONS).into());
     }
-    let message = params.join(" ");
-    let channel = match msg.channel_id.to_channel(ctx) {
-      Ok(Channel::Guild(c)) => c,
-      _ => return Err("This command must be used in a guild channel.".into())
+    let guild = guild_id.to_guild_cached(&ctx).chain_err(|| "could not find guild")?;
+    let options = match PollCommand::parse_options(&guild.read(), &options) {
+      Ok(o) => o,
+      Err(e) => return Err(e.into()),
+    };
+    let channel_id = match target {
+      Some(target) => {
+        match target.to_channel(ctx) {
+          Ok(Channel::Guild(c)) if c.read().guild_id == guild_id => {},
+          _ => return Err("That channel is not in this guild.".into()),
+        }
+        let can_send = guild.read().permissions_in(target, msg.author.id).send_messages();
+        if !can_send {
+          return Err("You can't send messages in that channel.".into());
+        }
+        target
+      },
+      None => channel.read().id,
     };
+    let message = params.text.join(" ");
+    if message.is_empty() {
+      return Err("The poll needs some text.".into());
+    }
+    if let Some(ref ends) = params.ends {
+      if **ends < 60 {
+        return Err("Polls have to stay open for at least a minute.".into());
+      }
+    }
+    if let Some(max) = params.max {
+      if max == 0 {
+        return Err("Members have to be able to vote for at least one option.".into());
+      }
+    }
+    let filters = if params.filters.is_empty() {
+      None
+    } else {
+      match Filter::all_filters(&params.filters.join(" ")) {
+        Some(f) => Some(f.into_iter().map(|x| x.to_string()).collect::<Vec<_>>().join(" ")),
+        None => return Err("Invalid filters.".into())
+      }
+    };
+    let ends_at = params.ends.map(|e| Utc::now().timestamp() + *e as i64);
+
     msg.delete(ctx).chain_err(|| "could not delete original message")?;
-    let name = self.nick_or_name(ctx, channel.read().guild_id, msg.author.id).unwrap_or_else(|| "someone".into());
-    let poll = Poll::new(name, &message, options);
-    channel.read().send_message(ctx, |c| c
-      .embed(poll.create_embed())
-      .reactions((0..poll.options.len()).map(|i| format!("{}⃣", i + 1))))
+    let name = self.nick_or_name(ctx, guild_id, msg.author.id).unwrap_or_else(|| "someone".into());
+    let title = format!("Poll by {}", name);
+    // post a placeholder, since the poll's embed shows its message ID
+    let posted = channel_id.send_message(ctx, |c| c.embed(|e| e.title(&title).description(&message)))
       .chain_err(|| "could not send embed")?;
-    Ok(CommandSuccess::default())
-  }
-}
-
-struct Poll {
-  author: String,
-  text: String,
-  options: Vec<String>
-}
-
-impl Poll {
-  fn new(author: String, text: &str, options: &[&str]) -> Poll {
-    Poll {
-      author,
-      text: text.to_string(),
-      options: options.iter().map(|x| x.to_string()).collect()
+    // react before storing the poll, so an emoji that can't be used doesn't leave a broken poll behind
+    let reacted: serenity::Result<()> = if params.ranked {
+      channel_id.create_reaction(ctx, posted.id, ranked::BALLOT_EMOJI)
+    } else {
+      options.iter()
+        .map(|(emoji, _)| channel_id.create_reaction(ctx, posted.id, parse_emoji(emoji)))
+        .collect()
+    };
+    if let Err(e) = reacted {
+      if let Err(e) = channel_id.delete_message(ctx, posted.id) {
+        warn!("could not delete poll that couldn't be reacted to: {}", e);
+      }
+      if params.ranked {
+        return Err(e).chain_err(|| "could not react to poll")?;
+      }
+      return Err("Could not react with one of the options. Make sure each option starts with an emoji the bot can use.".into());
     }
-  }
 
-  fn create_embed(&self) -> Box<dyn Fn(&mut CreateEmbed) -> &mut CreateEmbed> {
-    let name = self.author.clone();
-    let options = self.options.iter()
-      .enumerate()
-      .map(|(i, x)| format!("{}⃣ – {}", i + 1, x))
-      .collect::<Vec<_>>()
-      .join("\n");
-    let desc = format!("{}\n{}", self.text, options);
-    box move |e: &mut CreateEmbed| {
-      e
-        .title(&format!("Poll by {}", name))
-        .description(&desc)
-        .footer(|f| f.text("Loading poll ID..."))
-    }
+    let new_poll = NewPoll {
+      server_id: guild_id.into(),
+      channel_id: channel_id.into(),
+      message_id: posted.id.into(),
+      author_id: msg.author.id.into(),
+      text: message,
+      ends_at,
+      closed: false,
+      single: params.single,
+      max_votes: params.max.map(|m| m as i32),
+      filters,
+      joined_before: params.joined_before,
+      voters: None,
+      ranked: params.ranked,
+    };
+    let poll: Poll = crate::bot::with_connection(|c| {
+      c.transaction(|| {
+        let poll: Poll = diesel::insert_into(crate::database::schema::polls::table)
+          .values(&new_poll)
+          .get_result(c)?;
+        let options: Vec<NewPollOption> = options.iter()
+          .enumerate()
+          .map(|(i, (emoji, text))| NewPollOption {
+            poll_id: poll.id,
+            position: i as i32,
+            emoji: emoji.clone(),
+            text: text.clone(),
+            votes: None,
+          })
+          .collect();
+        diesel::insert_into(crate::database::schema::poll_options::table)
+          .values(&options)
+          .execute(c)?;
+        Ok(poll)
+      })
+    }).chain_err(|| "could not store poll")?;
+
+    let options = poll_options(&poll)?;
+    channel_id.edit_message(ctx, posted.id, |m| m.embed(|e| render_poll(e, &title, &poll, &options, None)))
+      .chain_err(|| "could not update poll")?;
+    Ok(CommandSuccess::default())
   }
 }
@@ -1,42 +1,13 @@
-/*
-!poll #announcements Poll text goes here!
-:one: Option one
-:two: Option two
-
-From @Bot in #announcements
-Poll from @jkcclemens:
-Poll text goes here!
-:one: – Option one
-:two: – Option two
-
-@Bot reacts to the message with all the options
-
-!poll #general Some other poll
-Auto-generate options
-No custom emoji
-
-!poll #channel Another custom poll
-:thumbsup: Yes
-:thumbsdown: No
-*/
-
 use crate::commands::*;
 
-use lalafell::commands::prelude::*;
+use super::{find_poll, poll_options, current_results, render_results, Tally};
 
-use serenity::model::channel::ReactionType;
+use lalafell::commands::prelude::*;
 
-const VALID_EMOJI: &[&str] = &[
-  "1⃣",
-  "2⃣",
-  "3⃣",
-  "4⃣",
-  "5⃣",
-  "6⃣",
-  "7⃣",
-  "8⃣",
-  "9⃣"
-];
+use serenity::{
+  builder::CreateEmbed,
+  model::id::MessageId,
+};
 
 #[derive(BotCommand)]
 pub struct PollResultsCommand;
@@ -59,19 +30,33 @@ impl<'a> Command<'a> for PollResultsCommand {
     let params = self.params_then("pollresults", params, |a| a.setting(structopt::clap::AppSettings::ArgRequiredElseHelp))?;
     let channel = params.channel;
     let message_id = params.message_id;
+    // stored polls are counted with their vote limits, and closed ones keep their final results
+    if let Some(poll) = find_poll(MessageId(message_id))? {
+      let options = poll_options(&poll)?;
+      let guild = GuildId(*poll.server_id).to_guild_cached(&ctx);
+      let (tally, rounds) = if poll.closed && !poll.ranked {
+        (Tally::stored(&poll, &options), None)
+      } else {
+        // ranked polls keep their ballots, so their rounds can be worked out again
+        let bot = ctx.cache.read().user.id;
+        current_results(ctx, bot, guild, &poll, &options)?
+      };
+      let title = if poll.closed { "Poll results" } else { "Poll results so far" };
+      return Ok(CommandSuccess::default()
+        .message(move |e: &mut CreateEmbed| render_results(e, title, &poll, &options, &tally, rounds.as_ref().map(String::as_str))));
+    }
     let message = match channel.message(&ctx, message_id) {
       Ok(m) => m,
       Err(_) => return Err("Could not get that message.".into())
     };
-    let mut reactions: Vec<(&String, u64)> = message.reactions.iter()
-      .filter_map(|r| match r.reaction_type {
-        ReactionType::Unicode(ref s) if VALID_EMOJI.contains(&s.as_str()) && r.me => Some((s, r.count - 1)),
-        _ => None
-      })
+    // polls from before they were stored have the bot's reactions as their options
+    let mut reactions: Vec<(String, u64)> = message.reactions.iter()
+      .filter(|r| r.me)
+      .map(|r| (r.reaction_type.to_string(), r.count - 1))
       .collect();
     reactions.sort_by_key(|x| !x.1);
     let votes = reactions.iter()
-      .map(|&(emoji, count)| format!("{} with {} vote{}", emoji, count, if count == 1 { "" } else { "s" }))
+      .map(|(emoji, count)| format!("{} with {} vote{}", emoji, count, if *count == 1 { "" } else { "s" }))
       .collect::<Vec<_>>()
       .join("\n");
     Ok(votes.into())
@@ -0,0 +1,385 @@
+//! Ranked-choice polls, where members rank the options in a DM and the winner is found by instant
+//! runoff.
+
+use crate::{
+  database::models::{ToU64, Poll, PollOption, PollBallot, NewPollBallot},
+  util::parse_emoji,
+};
+
+use super::{Eligibility, Tally, poll_options};
+
+use chrono::Utc;
+
+use diesel::prelude::*;
+
+use lalafell::error::*;
+
+use serenity::{
+  client::Context,
+  http::Http,
+  model::{
+    channel::Message,
+    guild::Guild,
+    id::{GuildId, UserId},
+  },
+  prelude::RwLock,
+};
+
+use std::sync::Arc;
+
+/// The emoji members react with to get a ballot.
+pub const BALLOT_EMOJI: &str = "🗳\u{fe0f}";
+
+pub fn is_ballot_emoji(emoji: &str) -> bool {
+  emoji.trim_end_matches('\u{fe0f}') == BALLOT_EMOJI.trim_end_matches('\u{fe0f}')
+}
+
+/// One round of an instant runoff.
+#[derive(Debug)]
+pub struct Round {
+  /// The options still in the running and their votes.
+  pub counts: Vec<(usize, u64)>,
+  /// The options knocked out at the end of the round.
+  pub eliminated: Vec<usize>,
+  /// How many ballots had no options left in the running.
+  pub exhausted: u64,
+}
+
+#[derive(Debug)]
+pub struct Runoff {
+  pub rounds: Vec<Round>,
+  /// The winning option, or the options tied at the end.
+  pub winners: Vec<usize>,
+}
+
+/// Run an instant runoff. Each round, every ballot counts for its highest ranked option still in
+/// the running. An option with a majority of those votes wins, otherwise the options with the
+/// fewest votes are knocked out. If every option left is tied, they all tie.
+pub fn instant_runoff(options: usize, ballots: &[Vec<usize>]) -> Runoff {
+  let mut continuing: Vec<usize> = (0..options).collect();
+  let mut rounds = Vec::new();
+  loop {
+    let mut counts = vec![0u64; options];
+    let mut exhausted = 0;
+    for ballot in ballots {
+      match ballot.iter().find(|o| continuing.contains(o)) {
+        Some(&o) => counts[o] += 1,
+        None => exhausted += 1,
+      }
+    }
+    let round_counts: Vec<(usize, u64)> = continuing.iter().map(|&o| (o, counts[o])).collect();
+    let active: u64 = round_counts.iter().map(|&(_, c)| c).sum();
+    let most = round_counts.iter().map(|&(_, c)| c).max().unwrap_or_default();
+    let fewest = round_counts.iter().map(|&(_, c)| c).min().unwrap_or_default();
+
+    if active == 0 {
+      rounds.push(Round { counts: round_counts, eliminated: Vec::new(), exhausted });
+      return Runoff { rounds, winners: Vec::new() };
+    }
+    if most * 2 > active || most == fewest {
+      let winners = round_counts.iter().filter(|&&(_, c)| c == most).map(|&(o, _)| o).collect();
+      rounds.push(Round { counts: round_counts, eliminated: Vec::new(), exhausted });
+      return Runoff { rounds, winners };
+    }
+    let eliminated: Vec<usize> = round_counts.iter().filter(|&&(_, c)| c == fewest).map(|&(o, _)| o).collect();
+    continuing.retain(|o| !eliminated.contains(o));
+    rounds.push(Round { counts: round_counts, eliminated, exhausted });
+  }
+}
+
+/// Describe each round of a runoff, ending with the winner.
+pub fn describe_runoff(options: &[PollOption], runoff: &Runoff) -> String {
+  let label = |o: usize| options.get(o).map(|o| o.emoji.as_str()).unwrap_or("?");
+  let mut lines: Vec<String> = runoff.rounds.iter()
+    .enumerate()
+    .map(|(i, round)| {
+      let counts = round.counts.iter()
+        .map(|&(o, c)| format!("{} {}", label(o), c))
+        .collect::<Vec<_>>()
+        .join(" · ");
+      let mut line = format!("**Round {}:** {}", i + 1, counts);
+      if !round.eliminated.is_empty() {
+        let eliminated: Vec<&str> = round.eliminated.iter().map(|&o| label(o)).collect();
+        line.push_str(&format!(" – {} out", eliminated.join(", ")));
+      }
+      if round.exhausted > 0 {
+        line.push_str(&format!(" ({} exhausted)", round.exhausted));
+      }
+      line
+    })
+    .collect();
+  let winners: Vec<String> = runoff.winners.iter()
+    .filter_map(|&o| options.get(o))
+    .map(|o| format!("{} {}", o.emoji, o.text))
+    .collect();
+  match winners.len() {
+    0 => lines.push("No votes.".into()),
+    1 => lines.push(format!("**Winner:** {}", winners[0])),
+    _ => lines.push(format!("**Tie between** {}", winners.join(" and "))),
+  }
+  let mut text = lines.join("\n");
+  // embed fields can't be longer than this
+  if text.chars().count() > 1024 {
+    text = text.chars().take(1023).collect::<String>() + "…";
+  }
+  text
+}
+
+/// Count a ranked poll's ballots from eligible members. The tally shows the votes in the last round.
+pub fn tally<H: AsRef<Http>>(http: H, poll: &Poll, options: &[PollOption], guild: Option<&Arc<RwLock<Guild>>>) -> Result<(Tally, Runoff)> {
+  let ballots: Vec<PollBallot> = crate::bot::with_connection(|c| {
+    use crate::database::schema::poll_ballots::dsl;
+    dsl::poll_ballots
+      .filter(dsl::poll_id.eq(poll.id).and(dsl::ranking.is_not_null()))
+      .load(c)
+  }).chain_err(|| "could not load poll ballots")?;
+  let users: Vec<UserId> = ballots.iter().map(|b| UserId(*b.user_id)).collect();
+  let fetched = Eligibility::fetch_uncached(&http, poll, guild, &users);
+  let guild = guild.map(|g| g.read());
+  let eligibility = Eligibility::new(poll, guild.as_ref().map(|g| &**g), fetched);
+  let rankings: Vec<Vec<usize>> = ballots.iter()
+    .filter(|b| eligibility.allows(UserId(*b.user_id)))
+    .map(PollBallot::ranking)
+    .filter(|r| !r.is_empty())
+    .collect();
+  let runoff = instant_runoff(options.len(), &rankings);
+  let mut votes = vec![0; options.len()];
+  if let Some(last) = runoff.rounds.last() {
+    for &(o, c) in &last.counts {
+      votes[o] = c;
+    }
+  }
+  let tally = Tally {
+    votes,
+    voters: rankings.len() as u64,
+  };
+  Ok((tally, runoff))
+}
+
+/// Send a member a ballot for a ranked poll, and wait for their ranking.
+pub fn request_ballot(ctx: &Context, poll: &Poll, user: UserId) -> Result<()> {
+  let allowed = {
+    let guild = GuildId(*poll.server_id).to_guild_cached(&ctx);
+    let fetched = Eligibility::fetch_uncached(ctx, poll, guild.as_ref(), &[user]);
+    let guild = guild.as_ref().map(|g| g.read());
+    Eligibility::new(poll, guild.as_ref().map(|g| &**g), fetched).allows(user)
+  };
+  let dm = user.create_dm_channel(ctx).chain_err(|| "could not open dm for ballot")?;
+  if !allowed {
+    dm.say(ctx, format!("You can't vote in the poll \"{}\".", poll.text)).chain_err(|| "could not send ballot")?;
+    return Ok(());
+  }
+
+  let options = poll_options(poll)?;
+  let now = Utc::now().timestamp();
+  let ballot = NewPollBallot {
+    poll_id: poll.id,
+    user_id: user.into(),
+    ranking: None,
+    awaiting: true,
+    requested_at: now,
+  };
+  let existing: Option<PollBallot> = crate::bot::with_connection(|c| {
+    use crate::database::schema::poll_ballots::dsl;
+    c.transaction(|| {
+      // only the newest ballot can be answered
+      diesel::update(dsl::poll_ballots.filter(dsl::user_id.eq(user.to_u64()).and(dsl::awaiting.eq(true))))
+        .set(dsl::awaiting.eq(false))
+        .execute(c)?;
+      diesel::insert_into(dsl::poll_ballots)
+        .values(&ballot)
+        .on_conflict((dsl::poll_id, dsl::user_id))
+        .do_update()
+        .set((dsl::awaiting.eq(true), dsl::requested_at.eq(now)))
+        .execute(c)?;
+      dsl::poll_ballots
+        .filter(dsl::poll_id.eq(poll.id).and(dsl::user_id.eq(user.to_u64())))
+        .first(c)
+        .optional()
+    })
+  }).chain_err(|| "could not store ballot request")?;
+
+  let list = options.iter()
+    .enumerate()
+    .map(|(i, o)| format!("`{}` {} {}", i + 1, o.emoji, o.text))
+    .collect::<Vec<_>>()
+    .join("\n");
+  let current = existing
+    .map(|b| describe_ranking(&options, &b.ranking()))
+    .filter(|r| !r.is_empty())
+    .map(|r| format!("\n\nYour current ranking: {}", r))
+    .unwrap_or_default();
+  dm.send_message(ctx, |m| m.embed(|e| e
+    .title("Ballot")
+    .description(format!(
+      "**{}**\n\n{}\n\nReply with the options' numbers in order of preference, like `2 1 3`. You don't have to rank every option.{}",
+      poll.text,
+      list,
+      current,
+    ))))
+    .chain_err(|| "could not send ballot")?;
+  Ok(())
+}
+
+/// Store the ranking in a DM from a member the bot is waiting on for a ballot.
+pub fn record_ballot(ctx: &Context, msg: &Message) -> Result<()> {
+  // commands can be used in DMs, too
+  if msg.content.starts_with('!') {
+    return Ok(());
+  }
+  let waiting: Option<(PollBallot, Poll)> = crate::bot::with_connection(|c| {
+    use crate::database::schema::{poll_ballots, polls};
+    poll_ballots::table
+      .inner_join(polls::table)
+      .filter(poll_ballots::user_id.eq(msg.author.id.to_u64())
+        .and(poll_ballots::awaiting.eq(true))
+        .and(polls::closed.eq(false)))
+      .order_by(poll_ballots::requested_at.desc())
+      .first(c)
+      .optional()
+  }).chain_err(|| "could not load ballot")?;
+  let (_, poll) = some_or!(waiting, return Ok(()));
+
+  let options = poll_options(&poll)?;
+  let ranking = match parse_ranking(&options, &msg.content) {
+    Ok(r) => r,
+    Err(e) => {
+      msg.channel_id.say(ctx, format!("{} Try again.", e)).chain_err(|| "could not reply to ballot")?;
+      return Ok(());
+    },
+  };
+  let stored = ranking.iter().map(ToString::to_string).collect::<Vec<_>>().join(",");
+  crate::bot::with_connection(|c| {
+    use crate::database::schema::poll_ballots::dsl;
+    diesel::update(dsl::poll_ballots.filter(dsl::poll_id.eq(poll.id).and(dsl::user_id.eq(msg.author.id.to_u64()))))
+      .set((dsl::ranking.eq(&stored), dsl::awaiting.eq(false)))
+      .execute(c)
+  }).chain_err(|| "could not store ballot")?;
+  msg.channel_id.say(ctx, format!(
+    "Your ranking was saved: {}\nReact to the poll again to change it.",
+    describe_ranking(&options, &ranking),
+  )).chain_err(|| "could not reply to ballot")?;
+  Ok(())
+}
+
+/// Parse a ranking of option numbers or emoji, separated by spaces, commas or `>`.
+pub fn parse_ranking(options: &[PollOption], content: &str) -> std::result::Result<Vec<usize>, String> {
+  let mut ranking = Vec::new();
+  for word in content.split(|c: char| c.is_whitespace() || c == ',' || c == '>').filter(|w| !w.is_empty()) {
+    let option = match word.parse::<usize>() {
+      Ok(n) if n >= 1 && n <= options.len() => Some(n - 1),
+      _ => {
+        let emoji = parse_emoji(word).to_string();
+        options.iter().position(|o| o.emoji.trim_end_matches('\u{fe0f}') == emoji.trim_end_matches('\u{fe0f}'))
+      },
+    };
+    let option = match option {
+      Some(o) => o,
+      None => return Err(format!("`{}` isn't one of the options.", word)),
+    };
+    if ranking.contains(&option) {
+      return Err(format!("You ranked {} more than once.", options[option].emoji));
+    }
+    ranking.push(option);
+  }
+  if ranking.is_empty() {
+    return Err("Rank at least one option.".into());
+  }
+  Ok(ranking)
+}
+
+fn describe_ranking(options: &[PollOption], ranking: &[usize]) -> String {
+  ranking.iter()
+    .filter_map(|&o| options.get(o))
+    .enumerate()
+    .map(|(i, o)| format!("{}. {}", i + 1, o.text))
+    .collect::<Vec<_>>()
+    .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn ballots(groups: &[(usize, &[usize])]) -> Vec<Vec<usize>> {
+    groups.iter()
+      .flat_map(|&(count, ranking)| std::iter::repeat(ranking.to_vec()).take(count))
+      .collect()
+  }
+
+  fn options(emoji: &[&str]) -> Vec<PollOption> {
+    emoji.iter()
+      .enumerate()
+      .map(|(i, e)| PollOption {
+        id: i as i32,
+        poll_id: 1,
+        position: i as i32,
+        emoji: e.to_string(),
+        text: format!("option {}", i + 1),
+        votes: None,
+      })
+      .collect()
+  }
+
+  #[test]
+  fn majority_in_first_round() {
+    let runoff = instant_runoff(3, &ballots(&[(2, &[0]), (1, &[1, 0])]));
+    assert_eq!(runoff.winners, vec![0]);
+    assert_eq!(runoff.rounds.len(), 1);
+    assert_eq!(runoff.rounds[0].counts, vec![(0, 2), (1, 1), (2, 0)]);
+  }
+
+  #[test]
+  fn eliminations_over_rounds() {
+    let runoff = instant_runoff(3, &ballots(&[(4, &[0]), (3, &[1, 0]), (2, &[2, 1])]));
+    assert_eq!(runoff.rounds.len(), 2);
+    assert_eq!(runoff.rounds[0].eliminated, vec![2]);
+    assert_eq!(runoff.rounds[1].counts, vec![(0, 4), (1, 5)]);
+    assert_eq!(runoff.winners, vec![1]);
+  }
+
+  #[test]
+  fn ties_for_fewest_are_all_eliminated() {
+    let runoff = instant_runoff(4, &ballots(&[(3, &[0]), (2, &[1]), (1, &[2, 1]), (1, &[3, 1])]));
+    assert_eq!(runoff.rounds[0].eliminated, vec![2, 3]);
+    assert_eq!(runoff.rounds[1].counts, vec![(0, 3), (1, 4)]);
+    assert_eq!(runoff.winners, vec![1]);
+  }
+
+  #[test]
+  fn all_tied() {
+    let runoff = instant_runoff(2, &ballots(&[(1, &[0]), (1, &[1])]));
+    assert_eq!(runoff.rounds.len(), 1);
+    assert_eq!(runoff.winners, vec![0, 1]);
+  }
+
+  #[test]
+  fn exhausted_ballots() {
+    let runoff = instant_runoff(3, &ballots(&[(2, &[0]), (2, &[1]), (1, &[2])]));
+    assert_eq!(runoff.rounds[0].eliminated, vec![2]);
+    assert_eq!(runoff.rounds[1].exhausted, 1);
+    assert_eq!(runoff.winners, vec![0, 1]);
+
+    let runoff = instant_runoff(2, &[]);
+    assert!(runoff.winners.is_empty());
+  }
+
+  #[test]
+  fn rankings() {
+    let options = options(&["🍎", "🍌", "❤\u{fe0f}"]);
+    assert_eq!(parse_ranking(&options, "2 1"), Ok(vec![1, 0]));
+    assert_eq!(parse_ranking(&options, "🍌 > 🍎, ❤"), Ok(vec![1, 0, 2]));
+    assert_eq!(parse_ranking(&options, "3,1"), Ok(vec![2, 0]));
+  }
+
+  #[test]
+  fn invalid_rankings() {
+    let options = options(&["🍎", "🍌"]);
+    assert!(parse_ranking(&options, "1 1").is_err());
+    assert!(parse_ranking(&options, "🍎 1").is_err());
+    assert!(parse_ranking(&options, "3").is_err());
+    assert!(parse_ranking(&options, "0").is_err());
+    assert!(parse_ranking(&options, "pear").is_err());
+    assert!(parse_ranking(&options, " , ").is_err());
+  }
+}
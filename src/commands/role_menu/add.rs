@@ -0,0 +1,93 @@
+use crate::commands::config::server::reaction::group::find_group;
+use crate::database::models::NewReaction;
+use crate::filters::Filter;
+use crate::util::ParsedEmoji;
+
+use diesel::prelude::*;
+
+use lalafell::error::*;
+use lalafell::commands::prelude::*;
+
+use serenity::model::id::MessageId;
+
+use unicase::UniCase;
+
+use super::{find_menu, menu_entries, update_menu};
+
+pub struct AddCommand;
+
+#[derive(Debug, StructOpt)]
+pub struct Params {
+  #[structopt(short = "g", long = "group", help = "The reaction role group to put the role in, if not the menu's")]
+  group: Option<String>,
+  #[structopt(short = "f", long = "filter", help = "A filter members have to match to get the role")]
+  #[structopt(number_of_values = 1)]
+  filters: Vec<String>,
+  #[structopt(help = "The message ID of the role menu")]
+  message_id: u64,
+  #[structopt(help = "The emoji for the role")]
+  #[structopt(parse(from_str))]
+  emoji: ParsedEmoji,
+  #[structopt(help = "The name of the role")]
+  #[structopt(use_delimiter = false)]
+  role: Vec<String>
+}
+
+impl<'a> AddCommand {
+  #[allow(clippy::needless_pass_by_value)]
+  pub fn run(&self, ctx: &Context, guild_id: GuildId, params: Params) -> CommandResult<'a> {
+    let message_id = MessageId(params.message_id);
+    let menu = match find_menu(guild_id, message_id)? {
+      Some(m) => m,
+      None => return Err("That message is not a role menu.".into()),
+    };
+    let guild = guild_id.to_guild_cached(&ctx).chain_err(|| "could not find guild")?;
+    let role = UniCase::new(params.role.join(" "));
+    let role_id = match guild.read().roles.values().find(|r| UniCase::new(&r.name) == role) {
+      Some(r) => r.id,
+      None => return Err("No such role.".into()),
+    };
+
+    let entries = menu_entries(message_id)?;
+    let emoji = params.emoji.to_string();
+    if entries.iter().any(|(r, _)| r.emoji == emoji) {
+      return Err("That emoji is already on the menu.".into());
+    }
+    if entries.len() >= 20 {
+      return Err("A role menu can have no more than twenty roles.".into());
+    }
+    let group_id = match params.group {
+      Some(ref name) => match find_group(guild_id, name)? {
+        Some(g) => Some(g.id),
+        None => return Err("No such group.".into()),
+      },
+      None => entries.first().and_then(|(r, _)| r.group_id),
+    };
+
+    let filters = if params.filters.is_empty() {
+      None
+    } else {
+      match Filter::all_filters(&params.filters.join(" ")) {
+        Some(f) => Some(f.into_iter().map(|x| x.to_string()).collect::<Vec<_>>().join(" ")),
+        None => return Err("Invalid filters.".into()),
+      }
+    };
+    let new_reaction = NewReaction {
+      server_id: guild_id.into(),
+      channel_id: menu.channel_id,
+      message_id: message_id.into(),
+      emoji,
+      role_id: role_id.into(),
+      group_id,
+      filters,
+    };
+    crate::bot::with_connection(|c| {
+      diesel::insert_into(crate::database::schema::reactions::table)
+        .values(&new_reaction)
+        .execute(c)
+    }).chain_err(|| "could not insert reaction")?;
+
+    update_menu(ctx, guild_id, message_id)?;
+    Ok(CommandSuccess::default())
+  }
+}
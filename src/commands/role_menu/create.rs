@@ -0,0 +1,94 @@
+use crate::commands::config::server::reaction::group::find_group;
+use crate::database::models::{NewReaction, NewReactionMenu};
+use crate::util::{message_content, parse_emoji};
+
+use diesel::prelude::*;
+
+use lalafell::error::*;
+use lalafell::commands::prelude::*;
+use lalafell::commands::ChannelOrId;
+
+use serenity::model::channel::Channel;
+
+use super::{MenuSpec, render_menu, menu_entries};
+
+pub struct CreateCommand;
+
+#[derive(Debug, StructOpt)]
+pub struct Params {
+  #[structopt(short = "c", long = "channel", help = "The channel to post the menu in, if not this one")]
+  channel: Option<ChannelOrId>,
+
+  #[structopt(short = "g", long = "group", help = "The reaction role group to put the menu's roles in")]
+  group: Option<String>
+}
+
+impl<'a> CreateCommand {
+  #[allow(clippy::needless_pass_by_value)]
+  pub fn run(&self, ctx: &Context, msg: &Message, guild_id: GuildId, params: Params) -> CommandResult<'a> {
+    let content = message_content(msg, &[]);
+    if content.is_empty() {
+      return Err("Put the menu's title, description and roles on the lines after the command.".into());
+    }
+    let guild = guild_id.to_guild_cached(&ctx).chain_err(|| "could not find guild")?;
+    let spec = match MenuSpec::parse(&guild.read(), &content) {
+      Ok(s) => s,
+      Err(e) => return Err(e.into()),
+    };
+    let group_id = match params.group {
+      Some(ref name) => match find_group(guild_id, name)? {
+        Some(g) => Some(g.id),
+        None => return Err("No such group.".into()),
+      },
+      None => None,
+    };
+
+    let channel_id = params.channel.map(|c| *c).unwrap_or(msg.channel_id);
+    match channel_id.to_channel(ctx) {
+      Ok(Channel::Guild(c)) if c.read().guild_id == guild_id => {},
+      _ => return Err("That channel is not in this guild.".into()),
+    }
+
+    // post a placeholder, since the real embed needs the stored reaction roles
+    let posted = channel_id.send_message(ctx, |m| m.embed(|e| e.title(&spec.title)))
+      .chain_err(|| "could not post role menu")?;
+
+    let menu = NewReactionMenu {
+      server_id: guild_id.into(),
+      channel_id: channel_id.into(),
+      message_id: posted.id.into(),
+      title: spec.title.clone(),
+      description: spec.description.clone(),
+    };
+    let reactions: Vec<NewReaction> = spec.entries.iter()
+      .map(|(emoji, role)| NewReaction {
+        server_id: guild_id.into(),
+        channel_id: channel_id.into(),
+        message_id: posted.id.into(),
+        emoji: emoji.clone(),
+        role_id: (*role).into(),
+        group_id,
+        filters: None,
+      })
+      .collect();
+    crate::bot::with_connection(|c| {
+      c.transaction(|| {
+        diesel::insert_into(crate::database::schema::reaction_menus::table)
+          .values(&menu)
+          .execute(c)?;
+        diesel::insert_into(crate::database::schema::reactions::table)
+          .values(&reactions)
+          .execute(c)
+      })
+    }).chain_err(|| "could not store role menu")?;
+
+    let entries = menu_entries(posted.id)?;
+    channel_id.edit_message(ctx, posted.id, |m| m.embed(|e| render_menu(e, &spec.title, spec.description.as_ref().map(String::as_str), &entries)))
+      .chain_err(|| "could not update role menu")?;
+    for (emoji, _) in &spec.entries {
+      channel_id.create_reaction(ctx, posted.id, parse_emoji(emoji)).chain_err(|| "could not react to role menu")?;
+    }
+
+    Ok(CommandSuccess::default())
+  }
+}
@@ -0,0 +1,38 @@
+use crate::database::models::ToU64;
+
+use diesel::prelude::*;
+
+use lalafell::error::*;
+use lalafell::commands::prelude::*;
+
+use serenity::model::id::{ChannelId, MessageId};
+
+use super::find_menu;
+
+pub struct DeleteCommand;
+
+#[derive(Debug, StructOpt)]
+pub struct Params {
+  #[structopt(help = "The message ID of the role menu")]
+  message_id: u64
+}
+
+impl<'a> DeleteCommand {
+  #[allow(clippy::needless_pass_by_value)]
+  pub fn run(&self, ctx: &Context, guild_id: GuildId, params: Params) -> CommandResult<'a> {
+    let message_id = MessageId(params.message_id);
+    let menu = match find_menu(guild_id, message_id)? {
+      Some(m) => m,
+      None => return Err("That message is not a role menu.".into()),
+    };
+    crate::bot::with_connection(|c| {
+      use crate::database::schema::reactions::dsl;
+      diesel::delete(dsl::reactions.filter(dsl::message_id.eq(message_id.to_u64()))).execute(c)?;
+      diesel::delete(&menu).execute(c)
+    }).chain_err(|| "could not delete role menu")?;
+    if let Err(e) = ChannelId(*menu.channel_id).delete_message(ctx, message_id) {
+      warn!("could not delete role menu message {}: {}", message_id, e);
+    }
+    Ok(CommandSuccess::default())
+  }
+}
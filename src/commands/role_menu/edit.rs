@@ -0,0 +1,91 @@
+use crate::database::models::{ToU64, NewReaction, ReactionMenu};
+use crate::util::{message_content, parse_emoji};
+
+use diesel::prelude::*;
+
+use lalafell::error::*;
+use lalafell::commands::prelude::*;
+
+use serenity::model::id::{ChannelId, MessageId};
+
+use super::{MenuSpec, clear_reaction, find_menu, menu_entries, update_menu};
+
+pub struct EditCommand;
+
+#[derive(Debug, StructOpt)]
+pub struct Params {
+  #[structopt(help = "The message ID of the role menu")]
+  message_id: u64
+}
+
+impl<'a> EditCommand {
+  #[allow(clippy::needless_pass_by_value)]
+  pub fn run(&self, ctx: &Context, msg: &Message, guild_id: GuildId, params: Params) -> CommandResult<'a> {
+    let message_id = MessageId(params.message_id);
+    let mut menu = match find_menu(guild_id, message_id)? {
+      Some(m) => m,
+      None => return Err("That message is not a role menu.".into()),
+    };
+    let content = message_content(msg, &[]);
+    if content.is_empty() {
+      return Err("Put the menu's new title, description and roles on the lines after the command.".into());
+    }
+    let guild = guild_id.to_guild_cached(&ctx).chain_err(|| "could not find guild")?;
+    let spec = match MenuSpec::parse(&guild.read(), &content) {
+      Ok(s) => s,
+      Err(e) => return Err(e.into()),
+    };
+
+    let existing = menu_entries(message_id)?;
+    // new roles go in the same group as the rest of the menu
+    let group_id = existing.first().and_then(|(r, _)| r.group_id);
+    let channel = ChannelId(*menu.channel_id);
+
+    for (reaction, _) in &existing {
+      match spec.entries.iter().find(|(emoji, _)| *emoji == reaction.emoji) {
+        Some((_, role)) if role.0 == *reaction.role_id => {},
+        Some((_, role)) => {
+          crate::bot::with_connection(|c| {
+            use crate::database::schema::reactions::dsl;
+            diesel::update(reaction)
+              .set(dsl::role_id.eq(role.to_u64()))
+              .execute(c)
+          }).chain_err(|| "could not update reaction role")?;
+        },
+        None => {
+          crate::bot::with_connection(|c| diesel::delete(reaction).execute(c)).chain_err(|| "could not remove reaction role")?;
+          if let Err(e) = clear_reaction(ctx, channel, message_id, parse_emoji(&reaction.emoji)) {
+            warn!("could not remove reaction from role menu {}: {}", message_id, e);
+          }
+        },
+      }
+    }
+
+    let added: Vec<NewReaction> = spec.entries.iter()
+      .filter(|(emoji, _)| !existing.iter().any(|(r, _)| r.emoji == *emoji))
+      .map(|(emoji, role)| NewReaction {
+        server_id: guild_id.into(),
+        channel_id: channel.into(),
+        message_id: message_id.into(),
+        emoji: emoji.clone(),
+        role_id: (*role).into(),
+        group_id,
+        filters: None,
+      })
+      .collect();
+    if !added.is_empty() {
+      crate::bot::with_connection(|c| {
+        diesel::insert_into(crate::database::schema::reactions::table)
+          .values(&added)
+          .execute(c)
+      }).chain_err(|| "could not add reaction roles")?;
+    }
+
+    menu.title = spec.title;
+    menu.description = spec.description;
+    crate::bot::with_connection(|c| menu.save_changes::<ReactionMenu>(c)).chain_err(|| "could not update role menu")?;
+
+    update_menu(ctx, guild_id, message_id)?;
+    Ok(CommandSuccess::default())
+  }
+}
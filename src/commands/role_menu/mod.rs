@@ -0,0 +1,255 @@
+mod add;
+mod create;
+mod delete;
+mod edit;
+mod remove;
+
+use crate::{
+  database::models::{ToU64, Reaction, ReactionGroup, ReactionGroupMode, ReactionMenu},
+  util::parse_emoji,
+};
+
+use diesel::prelude::*;
+
+use lalafell::error::*;
+use lalafell::commands::prelude::*;
+
+use serenity::{
+  http::Http,
+  model::{
+    channel::ReactionType,
+    guild::Guild,
+    id::{ChannelId, MessageId, RoleId, UserId},
+    misc::EmojiIdentifier,
+  },
+  prelude::Mentionable,
+};
+
+use unicase::UniCase;
+
+use std::{
+  str::FromStr,
+  sync::Arc,
+};
+
+#[derive(BotCommand)]
+pub struct RoleMenuCommand;
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Post and manage messages members can react to for roles")]
+pub enum Params {
+  #[structopt(name = "create", alias = "post", about = "Post a new role menu")]
+  #[structopt(template = ::lalafell::commands::TEMPLATE)]
+  Create(create::Params),
+
+  #[structopt(name = "edit", about = "Change a role menu's text and roles")]
+  #[structopt(template = ::lalafell::commands::TEMPLATE)]
+  #[structopt(setting = ::structopt::clap::AppSettings::ArgRequiredElseHelp)]
+  Edit(edit::Params),
+
+  #[structopt(name = "add", about = "Add a role to a role menu")]
+  #[structopt(template = ::lalafell::commands::TEMPLATE)]
+  #[structopt(setting = ::structopt::clap::AppSettings::ArgRequiredElseHelp)]
+  Add(add::Params),
+
+  #[structopt(name = "remove", about = "Remove a role from a role menu")]
+  #[structopt(template = ::lalafell::commands::TEMPLATE)]
+  #[structopt(setting = ::structopt::clap::AppSettings::ArgRequiredElseHelp)]
+  Remove(remove::Params),
+
+  #[structopt(name = "delete", about = "Delete a role menu and its reaction roles")]
+  #[structopt(template = ::lalafell::commands::TEMPLATE)]
+  #[structopt(setting = ::structopt::clap::AppSettings::ArgRequiredElseHelp)]
+  Delete(delete::Params)
+}
+
+impl HasParams for RoleMenuCommand {
+  type Params = Params;
+}
+
+impl<'a> PublicChannelCommand<'a> for RoleMenuCommand {
+  fn run(&self, ctx: &Context, msg: &Message, guild_id: GuildId, _: Arc<RwLock<GuildChannel>>, _: &[&str]) -> CommandResult<'a> {
+    struct SubCommands {
+      create: create::CreateCommand,
+      edit: edit::EditCommand,
+      add: add::AddCommand,
+      remove: remove::RemoveCommand,
+      delete: delete::DeleteCommand
+    }
+
+    const SUBCOMMANDS: SubCommands = SubCommands {
+      create: create::CreateCommand,
+      edit: edit::EditCommand,
+      add: add::AddCommand,
+      remove: remove::RemoveCommand,
+      delete: delete::DeleteCommand
+    };
+
+    let member = guild_id.member(ctx, &msg.author).chain_err(|| "could not get member")?;
+    if !member.permissions(&ctx).chain_err(|| "could not get permissions")?.manage_roles() {
+      return Err(ExternalCommandFailure::default()
+        .message(|e: &mut CreateEmbed| e
+          .title("Not enough permissions.")
+          .description("You don't have enough permissions to use this command."))
+        .wrap());
+    }
+
+    // only look at the first line for arguments, since the rest is the menu itself
+    let first_line = msg.content.lines().next().unwrap_or_default();
+    let words: Vec<&str> = first_line.split_whitespace().skip(1).collect();
+    let params = self.params_then("rolemenu", &words, |a| a.setting(structopt::clap::AppSettings::ArgRequiredElseHelp))?;
+
+    match params {
+      Params::Create(p) => SUBCOMMANDS.create.run(ctx, msg, guild_id, p),
+      Params::Edit(p) => SUBCOMMANDS.edit.run(ctx, msg, guild_id, p),
+      Params::Add(p) => SUBCOMMANDS.add.run(ctx, guild_id, p),
+      Params::Remove(p) => SUBCOMMANDS.remove.run(ctx, guild_id, p),
+      Params::Delete(p) => SUBCOMMANDS.delete.run(ctx, guild_id, p)
+    }
+  }
+}
+
+/// A role menu as written in a command: a title on the first line, then any number of lines of
+/// description, with lines of an emoji followed by a role name setting up the roles.
+#[derive(Debug)]
+pub struct MenuSpec {
+  pub title: String,
+  pub description: Option<String>,
+  pub entries: Vec<(String, RoleId)>,
+}
+
+impl MenuSpec {
+  pub fn parse(guild: &Guild, content: &str) -> std::result::Result<MenuSpec, String> {
+    let mut lines = content.trim().lines();
+    let title = match lines.next() {
+      Some(t) if !t.trim().is_empty() => t.trim().to_string(),
+      _ => return Err("The first line after the command should be the menu's title.".into()),
+    };
+    let mut description = Vec::new();
+    let mut entries: Vec<(String, RoleId)> = Vec::new();
+    for line in lines {
+      match MenuSpec::parse_entry(guild, line) {
+        Some(entry) => {
+          if entries.iter().any(|(emoji, _)| *emoji == entry.0) {
+            return Err(format!("{} is used for more than one role.", entry.0));
+          }
+          entries.push(entry);
+        },
+        None => description.push(line),
+      }
+    }
+    if entries.is_empty() {
+      return Err("A role menu needs at least one line with an emoji followed by a role name.".into());
+    }
+    // discord only allows twenty different reactions on a message
+    if entries.len() > 20 {
+      return Err("A role menu can have no more than twenty roles.".into());
+    }
+    let description = description.join("\n").trim().to_string();
+    Ok(MenuSpec {
+      title,
+      description: if description.is_empty() { None } else { Some(description) },
+      entries,
+    })
+  }
+
+  fn parse_entry(guild: &Guild, line: &str) -> Option<(String, RoleId)> {
+    let line = line.trim();
+    let space = line.find(char::is_whitespace)?;
+    let (emoji, role) = (&line[..space], line[space..].trim());
+    let is_emoji = EmojiIdentifier::from_str(emoji).is_ok() || !emoji.chars().any(|c| c.is_ascii_alphabetic());
+    if !is_emoji {
+      return None;
+    }
+    let role = UniCase::new(role.trim_start_matches('@'));
+    guild.roles.values()
+      .find(|r| UniCase::new(r.name.as_str()) == role)
+      .map(|r| (parse_emoji(emoji).to_string(), r.id))
+  }
+}
+
+/// Fill out the embed for a role menu.
+pub fn render_menu<'e>(e: &'e mut CreateEmbed, title: &str, description: Option<&str>, entries: &[(Reaction, Option<ReactionGroup>)]) -> &'e mut CreateEmbed {
+  let roles = entries.iter()
+    .map(|(r, _)| format!("{} {}", r.emoji, RoleId(*r.role_id).mention()))
+    .collect::<Vec<_>>()
+    .join("\n");
+  let text = match description {
+    Some(d) => format!("{}\n\n{}", d, roles),
+    None => roles,
+  };
+  e.title(title).description(text);
+
+  // explain how the roles behave if they're all in the same group
+  let first_group = entries.first().and_then(|(_, g)| g.as_ref());
+  if let Some(group) = first_group {
+    if entries.iter().all(|(_, g)| g.as_ref().map(|g| g.id) == Some(group.id)) {
+      let hint = match (group.mode(), group.max_roles) {
+        (Some(ReactionGroupMode::Unique), _) => Some("Pick one.".to_string()),
+        (Some(ReactionGroupMode::Limit), Some(max)) => Some(format!("Pick up to {}.", max)),
+        (Some(ReactionGroupMode::Verify), _) => Some("Roles stay when you remove your reaction.".to_string()),
+        (Some(ReactionGroupMode::Drop), _) => Some("React to give up a role.".to_string()),
+        _ => None,
+      };
+      if let Some(hint) = hint {
+        e.footer(|f| f.text(hint));
+      }
+    }
+  }
+  e
+}
+
+/// Load the reaction roles on a message, along with their groups.
+pub fn menu_entries(message: MessageId) -> Result<Vec<(Reaction, Option<ReactionGroup>)>> {
+  crate::bot::with_connection(|c| {
+    use crate::database::schema::{reactions, reaction_groups};
+    reactions::table
+      .left_join(reaction_groups::table)
+      .filter(reactions::message_id.eq(message.to_u64()))
+      .order_by(reactions::id)
+      .load(c)
+  }).chain_err(|| "could not load reactions")
+}
+
+/// Find the role menu posted as a message, if there is one.
+pub fn find_menu(guild: GuildId, message: MessageId) -> Result<Option<ReactionMenu>> {
+  crate::bot::with_connection(|c| {
+    use crate::database::schema::reaction_menus::dsl;
+    dsl::reaction_menus
+      .filter(dsl::server_id.eq(guild.to_u64()).and(dsl::message_id.eq(message.to_u64())))
+      .first(c)
+      .optional()
+  }).chain_err(|| "could not load role menu")
+}
+
+/// Remove everyone's reactions with an emoji from a message, including the bot's.
+pub fn clear_reaction<H: AsRef<Http>>(http: H, channel: ChannelId, message: MessageId, emoji: ReactionType) -> Result<()> {
+  loop {
+    let users = channel.reaction_users(&http, message, emoji.clone(), Some(100), None::<UserId>)
+      .chain_err(|| "could not get reaction users")?;
+    if users.is_empty() {
+      break;
+    }
+    for user in &users {
+      channel.delete_reaction(&http, message, Some(user.id), emoji.clone()).chain_err(|| "could not remove reaction")?;
+    }
+    if users.len() < 100 {
+      break;
+    }
+  }
+  Ok(())
+}
+
+/// Re-render a role menu after its reaction roles have changed, making sure the bot has reacted with
+/// every emoji on it. Does nothing if the message isn't a role menu.
+pub fn update_menu<H: AsRef<Http>>(http: H, guild: GuildId, message: MessageId) -> Result<()> {
+  let menu = some_or!(find_menu(guild, message)?, return Ok(()));
+  let entries = menu_entries(message)?;
+  let channel = ChannelId(*menu.channel_id);
+  channel.edit_message(&http, message, |m| m.embed(|e| render_menu(e, &menu.title, menu.description.as_ref().map(String::as_str), &entries)))
+    .chain_err(|| "could not edit role menu")?;
+  for (reaction, _) in &entries {
+    channel.create_reaction(&http, message, parse_emoji(&reaction.emoji)).chain_err(|| "could not react to role menu")?;
+  }
+  Ok(())
+}
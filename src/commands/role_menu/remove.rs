@@ -0,0 +1,48 @@
+use crate::database::models::ToU64;
+use crate::util::ParsedEmoji;
+
+use diesel::prelude::*;
+
+use lalafell::error::*;
+use lalafell::commands::prelude::*;
+
+use serenity::model::id::{ChannelId, MessageId};
+
+use super::{clear_reaction, find_menu, update_menu};
+
+pub struct RemoveCommand;
+
+#[derive(Debug, StructOpt)]
+pub struct Params {
+  #[structopt(help = "The message ID of the role menu")]
+  message_id: u64,
+  #[structopt(help = "The emoji of the role to remove")]
+  #[structopt(parse(from_str))]
+  emoji: ParsedEmoji
+}
+
+impl<'a> RemoveCommand {
+  #[allow(clippy::needless_pass_by_value)]
+  pub fn run(&self, ctx: &Context, guild_id: GuildId, params: Params) -> CommandResult<'a> {
+    let message_id = MessageId(params.message_id);
+    let menu = match find_menu(guild_id, message_id)? {
+      Some(m) => m,
+      None => return Err("That message is not a role menu.".into()),
+    };
+    let affected = crate::bot::with_connection(|c| {
+      use crate::database::schema::reactions::dsl;
+      diesel::delete(
+        dsl::reactions.filter(dsl::message_id.eq(message_id.to_u64()).and(dsl::emoji.eq(params.emoji.to_string())))
+      )
+        .execute(c)
+    }).chain_err(|| "could not delete reaction")?;
+    if affected == 0 {
+      return Err("That emoji is not on the menu.".into());
+    }
+    if let Err(e) = clear_reaction(ctx, ChannelId(*menu.channel_id), message_id, params.emoji.0.clone()) {
+      warn!("could not remove reaction from role menu {}: {}", message_id, e);
+    }
+    update_menu(ctx, guild_id, message_id)?;
+    Ok(CommandSuccess::default())
+  }
+}
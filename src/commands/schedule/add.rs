@@ -0,0 +1,98 @@
+use crate::database::models::{ScheduledMessage, NewScheduledMessage};
+use crate::recurrence::Recurrence;
+use crate::template::Template;
+use crate::util::{ParsedDuration, ParsedTime, check_channel, format_duration, message_content};
+
+use chrono::Utc;
+
+use diesel::prelude::*;
+
+use lalafell::error::*;
+use lalafell::commands::prelude::*;
+use lalafell::commands::ChannelOrId;
+
+use serenity::model::permissions::Permissions;
+
+use structopt::clap::ArgGroup;
+
+pub struct AddCommand;
+
+#[derive(Debug, StructOpt)]
+#[structopt(group = ArgGroup::with_name("when").args(&["at", "repeat"]).multiple(true).required(true))]
+pub struct Params {
+  #[structopt(short = "c", long = "channel", help = "The channel to post the message in")]
+  channel: ChannelOrId,
+
+  #[structopt(short = "a", long = "at", help = "When to post the message (or first post it, if it repeats)")]
+  at: Option<ParsedTime>,
+
+  #[structopt(short = "r", long = "repeat", help = "How often to post the message, such as \"weekly tuesday 08:00\"")]
+  repeat: Option<Recurrence>,
+
+  #[structopt(short = "e", long = "embed", help = "If the message should be posted as an embed")]
+  embed: bool,
+
+  #[structopt(short = "t", long = "title", help = "The title of the embed")]
+  title: Option<String>,
+
+  #[structopt(short = "d", long = "delete-after", help = "How long to wait before deleting each posted message")]
+  delete_after: Option<ParsedDuration>,
+
+  #[structopt(help = "The message to post, or put it on the lines after the command")]
+  #[structopt(use_delimiter = false)]
+  message: Vec<String>
+}
+
+impl<'a> AddCommand {
+  #[allow(clippy::needless_pass_by_value)]
+  pub fn run(&self, ctx: &Context, msg: &Message, guild_id: GuildId, params: Params) -> CommandResult<'a> {
+    if let Err(e) = check_channel(ctx, guild_id, *params.channel, Some((msg.author.id, Permissions::SEND_MESSAGES))) {
+      return Err(e.into());
+    }
+    let content = message_content(msg, &params.message);
+    if content.is_empty() {
+      return Err("Empty message.".into());
+    }
+    for text in std::iter::once(&content).chain(params.title.as_ref()) {
+      if let Err(e) = Template::parse(text) {
+        return Err(format!("Invalid template: {}.", e).into());
+      }
+    }
+
+    let now = Utc::now();
+    let next_run = match (params.at, params.repeat.as_ref()) {
+      (Some(at), _) if *at <= now => return Err("Cannot schedule a message in the past.".into()),
+      (Some(at), _) => *at,
+      (None, Some(repeat)) => match repeat.next_after(now) {
+        Some(n) => n,
+        None => return Err("That schedule never happens.".into())
+      },
+      (None, None) => unreachable!("required by arg group")
+    };
+
+    let nsm = NewScheduledMessage {
+      guild_id: guild_id.into(),
+      channel_id: params.channel.0.into(),
+      author_id: msg.author.id.into(),
+      content,
+      embed: params.embed || params.title.is_some(),
+      title: params.title,
+      next_run: next_run.timestamp(),
+      recurrence: params.repeat.map(|r| r.to_string()),
+      delete_after: params.delete_after.map(|d| d.0 as i32),
+    };
+    let scheduled: ScheduledMessage = crate::bot::with_connection(|c| {
+      use crate::database::schema::scheduled_messages;
+      diesel::insert_into(scheduled_messages::table)
+        .values(&nsm)
+        .get_result(c)
+    }).chain_err(|| "could not insert scheduled message")?;
+
+    Ok(format!(
+      "Scheduled message {}. It will be posted at {} UTC (in {}).",
+      scheduled.id,
+      next_run.format("%Y-%m-%d %H:%M"),
+      format_duration(next_run.timestamp() - now.timestamp()),
+    ).into())
+  }
+}
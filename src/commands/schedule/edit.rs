@@ -0,0 +1,125 @@
+use crate::database::models::{ToU64, ScheduledMessage};
+use crate::recurrence::Recurrence;
+use crate::template::Template;
+use crate::util::{ParsedDuration, ParsedTime, check_channel, message_content};
+
+use chrono::Utc;
+
+use diesel::prelude::*;
+
+use lalafell::error::*;
+use lalafell::commands::prelude::*;
+use lalafell::commands::ChannelOrId;
+
+use serenity::model::permissions::Permissions;
+
+pub struct EditCommand;
+
+#[derive(Debug, StructOpt)]
+pub struct Params {
+  #[structopt(help = "The ID of the scheduled message to edit")]
+  id: i32,
+
+  #[structopt(short = "c", long = "channel", help = "The channel to post the message in")]
+  channel: Option<ChannelOrId>,
+
+  #[structopt(short = "a", long = "at", help = "When to next post the message")]
+  at: Option<ParsedTime>,
+
+  #[structopt(short = "r", long = "repeat", help = "How often to post the message, such as \"weekly tuesday 08:00\"")]
+  repeat: Option<Recurrence>,
+
+  #[structopt(long = "once", help = "Stop repeating the message", conflicts_with = "repeat")]
+  once: bool,
+
+  #[structopt(short = "e", long = "embed", help = "Post the message as an embed")]
+  embed: bool,
+
+  #[structopt(short = "p", long = "plain", help = "Post the message as plain text", conflicts_with_all = &["embed", "title"])]
+  plain: bool,
+
+  #[structopt(short = "t", long = "title", help = "The title of the embed")]
+  title: Option<String>,
+
+  #[structopt(short = "d", long = "delete-after", help = "How long to wait before deleting each posted message")]
+  delete_after: Option<ParsedDuration>,
+
+  #[structopt(long = "keep", help = "Stop deleting posted messages", conflicts_with = "delete_after")]
+  keep: bool,
+
+  #[structopt(help = "The new message to post, or put it on the lines after the command")]
+  #[structopt(use_delimiter = false)]
+  message: Vec<String>
+}
+
+impl<'a> EditCommand {
+  #[allow(clippy::needless_pass_by_value)]
+  pub fn run(&self, ctx: &Context, msg: &Message, guild_id: GuildId, params: Params) -> CommandResult<'a> {
+    let scheduled: Option<ScheduledMessage> = crate::bot::with_connection(|c| {
+      use crate::database::schema::scheduled_messages::dsl;
+      dsl::scheduled_messages
+        .filter(dsl::id.eq(params.id).and(dsl::guild_id.eq(guild_id.to_u64())))
+        .first(c)
+        .optional()
+    }).chain_err(|| "could not load scheduled message")?;
+    let mut scheduled = match scheduled {
+      Some(s) => s,
+      None => return Err("No scheduled message with that ID.".into())
+    };
+
+    let content = message_content(msg, &params.message);
+    for text in std::iter::once(&content).chain(params.title.as_ref()) {
+      if let Err(e) = Template::parse(text) {
+        return Err(format!("Invalid template: {}.", e).into());
+      }
+    }
+    if !content.is_empty() {
+      scheduled.content = content;
+    }
+    if let Some(channel) = params.channel {
+      if let Err(e) = check_channel(ctx, guild_id, *channel, Some((msg.author.id, Permissions::SEND_MESSAGES))) {
+        return Err(e.into());
+      }
+      scheduled.channel_id = channel.0.into();
+    }
+    if params.once {
+      scheduled.recurrence = None;
+    }
+    let now = Utc::now();
+    if let Some(ref repeat) = params.repeat {
+      scheduled.recurrence = Some(repeat.to_string());
+      if params.at.is_none() {
+        scheduled.next_run = match repeat.next_after(now) {
+          Some(n) => n.timestamp(),
+          None => return Err("That schedule never happens.".into())
+        };
+      }
+    }
+    if let Some(at) = params.at {
+      if *at <= now {
+        return Err("Cannot schedule a message in the past.".into());
+      }
+      scheduled.next_run = at.timestamp();
+    }
+    if params.embed {
+      scheduled.embed = true;
+    }
+    if params.plain {
+      scheduled.embed = false;
+      scheduled.title = None;
+    }
+    if let Some(title) = params.title {
+      scheduled.embed = true;
+      scheduled.title = Some(title);
+    }
+    if let Some(delete_after) = params.delete_after {
+      scheduled.delete_after = Some(delete_after.0 as i32);
+    }
+    if params.keep {
+      scheduled.delete_after = None;
+    }
+
+    crate::bot::with_connection(|c| scheduled.save_changes::<ScheduledMessage>(c)).chain_err(|| "could not update scheduled message")?;
+    Ok(CommandSuccess::default())
+  }
+}
@@ -0,0 +1,43 @@
+use crate::database::models::{ToU64, ScheduledMessage};
+use crate::util::format_duration;
+
+use chrono::{Utc, TimeZone};
+
+use diesel::prelude::*;
+
+use lalafell::error::*;
+use lalafell::commands::prelude::*;
+
+use serenity::prelude::Mentionable;
+use serenity::model::id::ChannelId;
+
+pub struct ListCommand;
+
+impl<'a> ListCommand {
+  pub fn run(&self, guild_id: GuildId) -> CommandResult<'a> {
+    let scheduled: Vec<ScheduledMessage> = crate::bot::with_connection(|c| {
+      use crate::database::schema::scheduled_messages::dsl;
+      dsl::scheduled_messages
+        .filter(dsl::guild_id.eq(guild_id.to_u64()))
+        .order_by(dsl::next_run)
+        .load(c)
+    }).chain_err(|| "could not load scheduled messages")?;
+    if scheduled.is_empty() {
+      return Ok("No scheduled messages.".into());
+    }
+    let now = Utc::now().timestamp();
+    Ok(scheduled.iter()
+      .map(|s| format!("{id}. Posting in {channel} at {next} UTC (in {left}){repeat}{delete}.\n```{message}\n```",
+                      id = s.id,
+                      channel = ChannelId(*s.channel_id).mention(),
+                      next = Utc.timestamp(s.next_run, 0).format("%Y-%m-%d %H:%M"),
+                      left = format_duration(s.next_run - now),
+                      repeat = s.recurrence.as_ref().map(|r| format!(", repeating `{}`", r)).unwrap_or_default(),
+                      delete = s.delete_after.map(|d| format!(", deleted after {}", format_duration(i64::from(d)))).unwrap_or_default(),
+                      message = s.content
+      ))
+      .collect::<Vec<_>>()
+      .join("\n")
+      .into())
+  }
+}
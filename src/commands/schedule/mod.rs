@@ -0,0 +1,75 @@
+mod add;
+mod cancel;
+mod edit;
+mod list;
+
+use lalafell::error::*;
+use lalafell::commands::prelude::*;
+
+use std::sync::Arc;
+
+#[derive(BotCommand)]
+pub struct ScheduleCommand;
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Manage messages posted at a later time or on a schedule")]
+pub enum Params {
+  #[structopt(name = "add", alias = "create", about = "Schedule a message")]
+  #[structopt(template = ::lalafell::commands::TEMPLATE)]
+  #[structopt(setting = ::structopt::clap::AppSettings::ArgRequiredElseHelp)]
+  Add(add::Params),
+
+  #[structopt(name = "list", alias = "show", about = "List scheduled messages")]
+  #[structopt(template = ::lalafell::commands::TEMPLATE)]
+  List,
+
+  #[structopt(name = "edit", about = "Change a scheduled message")]
+  #[structopt(template = ::lalafell::commands::TEMPLATE)]
+  #[structopt(setting = ::structopt::clap::AppSettings::ArgRequiredElseHelp)]
+  Edit(edit::Params),
+
+  #[structopt(name = "cancel", aliases = &["remove", "delete"], about = "Cancel a scheduled message")]
+  #[structopt(template = ::lalafell::commands::TEMPLATE)]
+  #[structopt(setting = ::structopt::clap::AppSettings::ArgRequiredElseHelp)]
+  Cancel(cancel::Params)
+}
+
+impl HasParams for ScheduleCommand {
+  type Params = Params;
+}
+
+impl<'a> PublicChannelCommand<'a> for ScheduleCommand {
+  fn run(&self, ctx: &Context, msg: &Message, guild_id: GuildId, _: Arc<RwLock<GuildChannel>>, params: &[&str]) -> CommandResult<'a> {
+    struct SubCommands {
+      add: add::AddCommand,
+      list: list::ListCommand,
+      edit: edit::EditCommand,
+      cancel: cancel::CancelCommand
+    }
+
+    const SUBCOMMANDS: SubCommands = SubCommands {
+      add: add::AddCommand,
+      list: list::ListCommand,
+      edit: edit::EditCommand,
+      cancel: cancel::CancelCommand
+    };
+
+    let member = guild_id.member(ctx, &msg.author).chain_err(|| "could not get member")?;
+    if !member.permissions(&ctx).chain_err(|| "could not get permissions")?.manage_messages() {
+      return Err(ExternalCommandFailure::default()
+        .message(|e: &mut CreateEmbed| e
+          .title("Not enough permissions.")
+          .description("You don't have enough permissions to use this command."))
+        .wrap());
+    }
+
+    let params = self.params_then("schedule", params, |a| a.setting(structopt::clap::AppSettings::ArgRequiredElseHelp))?;
+
+    match params {
+      Params::Add(p) => SUBCOMMANDS.add.run(ctx, msg, guild_id, p),
+      Params::List => SUBCOMMANDS.list.run(guild_id),
+      Params::Edit(p) => SUBCOMMANDS.edit.run(ctx, msg, guild_id, p),
+      Params::Cancel(p) => SUBCOMMANDS.cancel.run(guild_id, p)
+    }
+  }
+}
@@ -0,0 +1,155 @@
+use crate::bot::BotEnv;
+use crate::database::models::{ToU64, TemporaryOverwrite, NewTemporaryOverwrite};
+use crate::util::ParsedDuration;
+
+use chrono::{Utc, Duration};
+
+use diesel::prelude::*;
+
+use lalafell::error::*;
+use lalafell::commands::prelude::*;
+use lalafell::commands::{ChannelOrId, MentionOrId};
+
+use serenity::model::{
+  channel::{Channel, PermissionOverwrite, PermissionOverwriteType},
+  permissions::Permissions,
+};
+
+use structopt::clap::ArgGroup;
+
+use std::sync::Arc;
+
+lazy_static! {
+  static ref READ_PERMISSIONS: Permissions = {
+    let mut perm = Permissions::empty();
+    perm.insert(Permissions::READ_MESSAGES);
+    perm.insert(Permissions::READ_MESSAGE_HISTORY);
+    perm.insert(Permissions::CONNECT);
+    perm
+  };
+  static ref WRITE_PERMISSIONS: Permissions = {
+    let mut perm = *READ_PERMISSIONS;
+    perm.insert(Permissions::SEND_MESSAGES);
+    perm.insert(Permissions::ADD_REACTIONS);
+    perm.insert(Permissions::EMBED_LINKS);
+    perm.insert(Permissions::ATTACH_FILES);
+    perm.insert(Permissions::SPEAK);
+    perm
+  };
+}
+
+pub struct AddCommand;
+
+#[derive(Debug, StructOpt)]
+#[structopt(group = ArgGroup::with_name("m_or_t").args(&["messages", "time"]).multiple(true).required(true))]
+pub struct Params {
+  #[structopt(
+    short = "w",
+    long = "who",
+    help = "The member to give access to"
+  )]
+  who: MentionOrId,
+  #[structopt(
+    short = "c",
+    long = "channel",
+    help = "The channel to give access to"
+  )]
+  channel: ChannelOrId,
+  #[structopt(
+    short = "r",
+    long = "read-only",
+    help = "Only allow the member to read the channel"
+  )]
+  read_only: bool,
+  #[structopt(
+    short = "m",
+    long = "messages",
+    help = "The amount of messages in the channel for the access to last"
+  )]
+  messages: Option<u32>,
+  #[structopt(
+    short = "t",
+    long = "time",
+    help = "The amount of time for the access to last"
+  )]
+  time: Option<ParsedDuration>
+}
+
+impl<'a> AddCommand {
+  pub fn run(&self, ctx: &Context, env: &Arc<BotEnv>, msg: &Message, guild_id: GuildId, params: Params) -> CommandResult<'a> {
+    if guild_id.member(ctx, *params.who).is_err() {
+      return Err("That person is not in this guild.".into());
+    }
+
+    let channel = match params.channel.to_channel(ctx) {
+      Ok(Channel::Guild(c)) if c.read().guild_id == guild_id => c,
+      _ => return Err("That channel is not in this guild.".into())
+    };
+
+    // changing a channel's overwrites takes being able to manage it
+    let guild = guild_id.to_guild_cached(&ctx).chain_err(|| "could not find guild")?;
+    let can_manage = guild.read().permissions_in(*params.channel, msg.author.id).manage_channels();
+    if !can_manage {
+      return Err("You don't have permission to manage that channel.".into());
+    }
+
+    let existing: Option<TemporaryOverwrite> = crate::bot::with_connection(|c| {
+      use crate::database::schema::temporary_overwrites::dsl;
+      dsl::temporary_overwrites
+        .filter(dsl::user_id.eq(params.who.to_u64()).and(dsl::channel_id.eq(params.channel.to_u64())))
+        .first(c)
+        .optional()
+    }).chain_err(|| "could not load temporary overwrites")?;
+    if existing.is_some() {
+      return Err("That member already has temporary access to that channel.".into());
+    }
+
+    let kind = PermissionOverwriteType::Member(*params.who);
+    let previous = channel.read().permission_overwrites.iter()
+      .find(|o| o.kind == kind)
+      .map(|o| (o.allow, o.deny));
+
+    // keep whatever the member's overwrite already had, only adding the access on top
+    let grant = if params.read_only { *READ_PERMISSIONS } else { *WRITE_PERMISSIONS };
+    let (allow, deny) = previous.unwrap_or((Permissions::empty(), Permissions::empty()));
+    let overwrite = PermissionOverwrite {
+      kind,
+      allow: allow | grant,
+      deny: deny - grant,
+    };
+    channel.read().create_permission(ctx, &overwrite).chain_err(|| "could not create permission overwrite")?;
+
+    let messages = params.messages.map(|m| m as i32);
+    let time = params.time
+      .as_ref()
+      .map(|t| Utc::now() + Duration::seconds(**t as i64))
+      .map(|t| t.timestamp());
+    let nto = NewTemporaryOverwrite::new(
+      guild_id.0,
+      params.channel.0,
+      params.who.0,
+      msg.id.0,
+      grant.bits(),
+      previous.map(|(allow, deny)| (allow.bits(), deny.bits())),
+      messages,
+      time,
+    );
+    let temp = crate::bot::with_connection(|c| {
+      use crate::database::schema::temporary_overwrites::dsl;
+
+      diesel::insert_into(dsl::temporary_overwrites).values(&nto).get_result(c)
+    }).chain_err(|| "could not store new temporary overwrite")?;
+
+    if let Some(t) = params.time {
+      if *t < 600 {
+        let env = Arc::clone(env);
+        std::thread::spawn(move || {
+          std::thread::sleep(Duration::seconds(*t as i64).to_std().unwrap());
+          crate::tasks::temporary_overwrites::remove_temporary_overwrite(&env, &temp);
+        });
+      }
+    }
+
+    Ok(CommandSuccess::default())
+  }
+}
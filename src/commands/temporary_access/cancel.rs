@@ -0,0 +1,35 @@
+use crate::database::models::{ToU64, TemporaryOverwrite};
+
+use diesel::prelude::*;
+
+use lalafell::error::*;
+use lalafell::commands::prelude::*;
+
+pub struct CancelCommand;
+
+#[derive(Debug, StructOpt)]
+pub struct Params {
+  #[structopt(help = "The ID of the temporary access to cancel")]
+  id: i32
+}
+
+impl<'a> CancelCommand {
+  #[allow(clippy::needless_pass_by_value)]
+  pub fn run(&self, ctx: &Context, guild_id: GuildId, params: Params) -> CommandResult<'a> {
+    let temp: Option<TemporaryOverwrite> = crate::bot::with_connection(|c| {
+      use crate::database::schema::temporary_overwrites::dsl;
+      dsl::temporary_overwrites
+        .filter(dsl::id.eq(params.id).and(dsl::guild_id.eq(guild_id.to_u64())))
+        .first(c)
+        .optional()
+    }).chain_err(|| "could not load temporary overwrite")?;
+    let temp = match temp {
+      Some(t) => t,
+      None => return Err("No temporary access with that ID.".into())
+    };
+
+    crate::tasks::temporary_overwrites::restore_overwrite(ctx, &temp).chain_err(|| "could not remove temporary access")?;
+
+    Ok(CommandSuccess::default())
+  }
+}
@@ -0,0 +1,73 @@
+use crate::database::models::{ToU64, TemporaryOverwrite};
+use crate::util::format_duration;
+
+use chrono::Utc;
+
+use diesel::prelude::*;
+
+use lalafell::error::*;
+use lalafell::commands::prelude::*;
+use lalafell::commands::{ChannelOrId, MentionOrId};
+
+use serenity::prelude::Mentionable;
+use serenity::model::id::{ChannelId, UserId};
+
+pub struct ListCommand;
+
+#[derive(Debug, StructOpt)]
+pub struct Params {
+  #[structopt(
+    short = "w",
+    long = "who",
+    help = "Only list access given to this member"
+  )]
+  who: Option<MentionOrId>,
+  #[structopt(
+    short = "c",
+    long = "channel",
+    help = "Only list access to this channel"
+  )]
+  channel: Option<ChannelOrId>
+}
+
+impl<'a> ListCommand {
+  #[allow(clippy::needless_pass_by_value)]
+  pub fn run(&self, guild_id: GuildId, params: Params) -> CommandResult<'a> {
+    let mut temps: Vec<TemporaryOverwrite> = crate::bot::with_connection(|c| {
+      use crate::database::schema::temporary_overwrites::dsl;
+      dsl::temporary_overwrites
+        .filter(dsl::guild_id.eq(guild_id.to_u64()))
+        .order_by(dsl::id)
+        .load(c)
+    }).chain_err(|| "could not load temporary overwrites")?;
+    if let Some(ref who) = params.who {
+      temps.retain(|t| *t.user_id == who.0);
+    }
+    if let Some(ref channel) = params.channel {
+      temps.retain(|t| *t.channel_id == channel.0);
+    }
+
+    if temps.is_empty() {
+      return Ok("No active temporary channel access.".into());
+    }
+
+    let now = Utc::now().timestamp();
+    let strings: Vec<String> = temps.iter()
+      .map(|t| {
+        let mut limits = Vec::new();
+        if let Some(messages) = t.messages {
+          limits.push(format!("{} more message{}", messages, if messages == 1 { "" } else { "s" }));
+        }
+        if let Some(expires_on) = t.expires_on {
+          limits.push(format!("{} more", format_duration(expires_on - now)));
+        }
+        format!("{}. {} has access to {} for {}",
+                t.id,
+                UserId(*t.user_id).mention(),
+                ChannelId(*t.channel_id).mention(),
+                limits.join(" or "))
+      })
+      .collect();
+    Ok(strings.join("\n").into())
+  }
+}
@@ -0,0 +1,70 @@
+mod add;
+mod cancel;
+mod list;
+
+use crate::bot::BotEnv;
+
+use lalafell::error::*;
+use lalafell::commands::prelude::*;
+
+use std::sync::Arc;
+
+#[derive(BotCommand)]
+pub struct TemporaryAccessCommand {
+  env: Arc<BotEnv>,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Manage channel access given to members for a given number of messages or time")]
+pub enum Params {
+  #[structopt(name = "add", aliases = &["give", "create"], about = "Give a member access to a channel for a given number of messages or time")]
+  #[structopt(template = ::lalafell::commands::TEMPLATE)]
+  #[structopt(setting = ::structopt::clap::AppSettings::ArgRequiredElseHelp)]
+  Add(add::Params),
+
+  #[structopt(name = "list", alias = "show", about = "List active temporary channel access")]
+  #[structopt(template = ::lalafell::commands::TEMPLATE)]
+  List(list::Params),
+
+  #[structopt(name = "cancel", aliases = &["remove", "delete"], about = "Remove temporary channel access early")]
+  #[structopt(template = ::lalafell::commands::TEMPLATE)]
+  #[structopt(setting = ::structopt::clap::AppSettings::ArgRequiredElseHelp)]
+  Cancel(cancel::Params)
+}
+
+impl HasParams for TemporaryAccessCommand {
+  type Params = Params;
+}
+
+impl<'a> PublicChannelCommand<'a> for TemporaryAccessCommand {
+  fn run(&self, ctx: &Context, msg: &Message, guild_id: GuildId, _: Arc<RwLock<GuildChannel>>, params: &[&str]) -> CommandResult<'a> {
+    struct SubCommands {
+      add: add::AddCommand,
+      list: list::ListCommand,
+      cancel: cancel::CancelCommand
+    }
+
+    const SUBCOMMANDS: SubCommands = SubCommands {
+      add: add::AddCommand,
+      list: list::ListCommand,
+      cancel: cancel::CancelCommand
+    };
+
+    let member = guild_id.member(ctx, &msg.author).chain_err(|| "could not get member")?;
+    if !member.permissions(&ctx).chain_err(|| "could not get permissions")?.manage_roles() {
+      return Err(ExternalCommandFailure::default()
+        .message(|e: &mut CreateEmbed| e
+          .title("Not enough permissions.")
+          .description("You don't have enough permissions to use this command."))
+        .wrap());
+    }
+
+    let params = self.params_then("temporaryaccess", params, |a| a.setting(structopt::clap::AppSettings::ArgRequiredElseHelp))?;
+
+    match params {
+      Params::Add(p) => SUBCOMMANDS.add.run(ctx, &self.env, msg, guild_id, p),
+      Params::List(p) => SUBCOMMANDS.list.run(guild_id, p),
+      Params::Cancel(p) => SUBCOMMANDS.cancel.run(ctx, guild_id, p)
+    }
+  }
+}
@@ -16,13 +16,10 @@ use unicase::UniCase;
 
 use std::sync::Arc;
 
-#[derive(BotCommand)]
-pub struct TemporaryRoleCommand {
-  env: Arc<BotEnv>,
-}
+pub struct AddCommand;
 
 #[derive(Debug, StructOpt)]
-#[structopt(about = "Give a role to a member for a given number of messages or time")]
+#[structopt(group = ArgGroup::with_name("m_or_t").args(&["messages", "time"]).multiple(true).required(true))]
 pub struct Params {
   #[structopt(
     short = "w",
@@ -57,28 +54,8 @@ pub struct Params {
   time: Option<ParsedDuration>
 }
 
-impl HasParams for TemporaryRoleCommand {
-  type Params = Params;
-}
-
-impl<'a> PublicChannelCommand<'a> for TemporaryRoleCommand {
-  fn run(&self, ctx: &Context, msg: &Message, guild_id: GuildId, _: Arc<RwLock<GuildChannel>>, params: &[&str]) -> CommandResult<'a> {
-    let member = guild_id.member(ctx, &msg.author).chain_err(|| "could not get member")?;
-    if !member.permissions(&ctx).chain_err(|| "could not get permissions")?.manage_roles() {
-      return Err(ExternalCommandFailure::default()
-        .message(|e: &mut CreateEmbed| e
-          .title("Not enough permissions.")
-          .description("You don't have enough permissions to use this command."))
-        .wrap());
-    }
-
-    let params = self.params_then("temporaryrole", params, |a| a
-      .setting(structopt::clap::AppSettings::ArgRequiredElseHelp)
-      .group(ArgGroup::with_name("m_or_t")
-        .args(&["messages", "time"])
-        .multiple(true)
-        .required(true)))?;
-
+impl<'a> AddCommand {
+  pub fn run(&self, ctx: &Context, env: &Arc<BotEnv>, msg: &Message, guild_id: GuildId, params: Params) -> CommandResult<'a> {
     let guild = guild_id.to_guild_cached(&ctx).chain_err(|| "could not find guild in cache")?;
 
     let mut target = match guild_id.member(ctx, *params.who) {
@@ -92,6 +69,9 @@ impl<'a> PublicChannelCommand<'a> for TemporaryRoleCommand {
       None => return Err("No such role.".into())
     };
 
+    // give the role before storing it, so the role is never seen as removed by hand
+    target.add_role(&ctx, role).chain_err(|| "could not add role")?;
+
     let messages = params.messages.map(|m| m as i32);
     let time = params.time
       .as_ref()
@@ -106,7 +86,7 @@ impl<'a> PublicChannelCommand<'a> for TemporaryRoleCommand {
 
     if let Some(t) = params.time {
       if *t < 600 {
-        let env = Arc::clone(&self.env);
+        let env = Arc::clone(env);
         std::thread::spawn(move || {
           std::thread::sleep(Duration::seconds(*t as i64).to_std().unwrap());
           crate::tasks::temporary_roles::remove_temporary_role(&env, &temp_role);
@@ -114,7 +94,6 @@ impl<'a> PublicChannelCommand<'a> for TemporaryRoleCommand {
       }
     }
 
-    target.add_role(&ctx, role).chain_err(|| "could not add role")?;
     Ok(CommandSuccess::default())
   }
 }
@@ -0,0 +1,51 @@
+use crate::database::models::{ToU64, TemporaryRole};
+
+use diesel::prelude::*;
+
+use lalafell::error::*;
+use lalafell::commands::prelude::*;
+
+use serenity::model::id::RoleId;
+
+pub struct CancelCommand;
+
+#[derive(Debug, StructOpt)]
+pub struct Params {
+  #[structopt(help = "The ID of the temporary role to cancel")]
+  id: i32
+}
+
+impl<'a> CancelCommand {
+  #[allow(clippy::needless_pass_by_value)]
+  pub fn run(&self, ctx: &Context, guild_id: GuildId, params: Params) -> CommandResult<'a> {
+    let temp: Option<TemporaryRole> = crate::bot::with_connection(|c| {
+      use crate::database::schema::temporary_roles::dsl;
+      dsl::temporary_roles
+        .filter(dsl::id.eq(params.id).and(dsl::guild_id.eq(guild_id.to_u64())))
+        .first(c)
+        .optional()
+    }).chain_err(|| "could not load temporary role")?;
+    let temp = match temp {
+      Some(t) => t,
+      None => return Err("No temporary role with that ID.".into())
+    };
+
+    let still_had_role = match guild_id.member(ctx, *temp.user_id) {
+      Ok(mut member) => if member.roles.contains(&RoleId(*temp.role_id)) {
+        member.remove_role(&ctx, *temp.role_id).chain_err(|| "could not remove role")?;
+        true
+      } else {
+        false
+      },
+      Err(_) => false
+    };
+
+    crate::bot::with_connection(|c| diesel::delete(&temp).execute(c)).chain_err(|| "could not delete temporary role")?;
+
+    if still_had_role {
+      Ok(CommandSuccess::default())
+    } else {
+      Ok("The role had already been removed, so the temporary role was just cleared.".into())
+    }
+  }
+}
@@ -0,0 +1,99 @@
+use crate::bot::BotEnv;
+use crate::database::models::{ToU64, TemporaryRole};
+use crate::util::{ParsedDuration, format_duration};
+
+use chrono::{Utc, Duration};
+
+use diesel::prelude::*;
+
+use lalafell::error::*;
+use lalafell::commands::prelude::*;
+
+use serenity::model::id::RoleId;
+
+use structopt::clap::ArgGroup;
+
+use std::sync::Arc;
+
+pub struct ExtendCommand;
+
+#[derive(Debug, StructOpt)]
+#[structopt(group = ArgGroup::with_name("m_or_t").args(&["messages", "time"]).multiple(true).required(true))]
+pub struct Params {
+  #[structopt(help = "The ID of the temporary role to extend")]
+  id: i32,
+  #[structopt(
+    short = "m",
+    long = "messages",
+    help = "The amount of messages to add"
+  )]
+  messages: Option<u32>,
+  #[structopt(
+    short = "t",
+    long = "time",
+    help = "The amount of time to add"
+  )]
+  time: Option<ParsedDuration>
+}
+
+impl<'a> ExtendCommand {
+  #[allow(clippy::needless_pass_by_value)]
+  pub fn run(&self, ctx: &Context, env: &Arc<BotEnv>, guild_id: GuildId, params: Params) -> CommandResult<'a> {
+    let temp: Option<TemporaryRole> = crate::bot::with_connection(|c| {
+      use crate::database::schema::temporary_roles::dsl;
+      dsl::temporary_roles
+        .filter(dsl::id.eq(params.id).and(dsl::guild_id.eq(guild_id.to_u64())))
+        .first(c)
+        .optional()
+    }).chain_err(|| "could not load temporary role")?;
+    let mut temp = match temp {
+      Some(t) => t,
+      None => return Err("No temporary role with that ID.".into())
+    };
+
+    let has_role = guild_id.member(ctx, *temp.user_id)
+      .map(|m| m.roles.contains(&RoleId(*temp.role_id)))
+      .unwrap_or(false);
+    if !has_role {
+      crate::bot::with_connection(|c| diesel::delete(&temp).execute(c)).chain_err(|| "could not delete temporary role")?;
+      return Err("That member no longer has the role, so the temporary role was cleared instead.".into());
+    }
+
+    // the role ends when any limit is reached, so adding a new limit would shorten it
+    if params.messages.is_some() && temp.messages.is_none() {
+      return Err("That temporary role has no message limit to extend.".into());
+    }
+    if params.time.is_some() && temp.expires_on.is_none() {
+      return Err("That temporary role has no time limit to extend.".into());
+    }
+
+    if let (Some(messages), Some(current)) = (params.messages, temp.messages) {
+      temp.messages = Some(current + messages as i32);
+    }
+    let now = Utc::now().timestamp();
+    if let (Some(time), Some(expires_on)) = (&params.time, temp.expires_on) {
+      temp.expires_on = Some(expires_on.max(now) + **time as i64);
+    }
+
+    let temp: TemporaryRole = crate::bot::with_connection(|c| temp.save_changes(c)).chain_err(|| "could not save temporary role changes")?;
+
+    let mut limits = Vec::new();
+    if let Some(messages) = temp.messages {
+      limits.push(format!("{} more message{}", messages, if messages == 1 { "" } else { "s" }));
+    }
+    if let Some(expires_on) = temp.expires_on {
+      let left = expires_on - now;
+      limits.push(format!("{} more", format_duration(left)));
+      // the task only picks up roles expiring in the next ten minutes, so handle it here
+      if params.time.is_some() && left < 600 {
+        let env = Arc::clone(env);
+        std::thread::spawn(move || {
+          std::thread::sleep(Duration::seconds(left).to_std().unwrap());
+          crate::tasks::temporary_roles::remove_temporary_role(&env, &temp);
+        });
+      }
+    }
+
+    Ok(format!("Temporary role extended. It now lasts for {}.", limits.join(" or ")).into())
+  }
+}
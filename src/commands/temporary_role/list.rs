@@ -0,0 +1,99 @@
+use crate::database::models::{ToU64, TemporaryRole};
+use crate::util::format_duration;
+
+use chrono::Utc;
+
+use diesel::prelude::*;
+
+use lalafell::error::*;
+use lalafell::commands::prelude::*;
+use lalafell::commands::MentionOrId;
+
+use serenity::prelude::Mentionable;
+use serenity::model::id::{ChannelId, RoleId, UserId};
+
+use unicase::UniCase;
+
+pub struct ListCommand;
+
+#[derive(Debug, StructOpt)]
+pub struct Params {
+  #[structopt(
+    short = "w",
+    long = "who",
+    help = "Only list temporary roles given to this member"
+  )]
+  who: Option<MentionOrId>,
+  #[structopt(
+    short = "r",
+    long = "role",
+    help = "Only list temporary roles for this role"
+  )]
+  role: Option<String>
+}
+
+impl<'a> ListCommand {
+  #[allow(clippy::needless_pass_by_value)]
+  pub fn run(&self, ctx: &Context, guild_id: GuildId, params: Params) -> CommandResult<'a> {
+    let guild = guild_id.to_guild_cached(&ctx).chain_err(|| "could not find guild in cache")?;
+
+    let role = match params.role {
+      Some(ref name) => {
+        let name = UniCase::new(name.as_str());
+        match guild.read().roles.values().find(|r| UniCase::new(r.name.as_str()) == name) {
+          Some(r) => Some(r.id),
+          None => return Err("No such role.".into())
+        }
+      },
+      None => None
+    };
+
+    let mut temps: Vec<TemporaryRole> = crate::bot::with_connection(|c| {
+      use crate::database::schema::temporary_roles::dsl;
+      dsl::temporary_roles
+        .filter(dsl::guild_id.eq(guild_id.to_u64()))
+        .order_by(dsl::id)
+        .load(c)
+    }).chain_err(|| "could not load temporary roles")?;
+    if let Some(ref who) = params.who {
+      temps.retain(|t| *t.user_id == who.0);
+    }
+    if let Some(role) = role {
+      temps.retain(|t| *t.role_id == role.0);
+    }
+
+    if temps.is_empty() {
+      return Ok("No active temporary roles.".into());
+    }
+
+    let now = Utc::now().timestamp();
+    let guild = guild.read();
+    let strings: Vec<String> = temps.iter()
+      .map(|t| {
+        let mut limits = Vec::new();
+        if let Some(messages) = t.messages {
+          let channel = t.channel_id
+            .map(|c| format!(" in {}", ChannelId(c as u64).mention()))
+            .unwrap_or_default();
+          limits.push(format!("{} more message{}{}", messages, if messages == 1 { "" } else { "s" }, channel));
+        }
+        if let Some(expires_on) = t.expires_on {
+          limits.push(format!("{} more", format_duration(expires_on - now)));
+        }
+        // the role may have been taken away by hand since it was given
+        let removed = match guild.members.get(&UserId(*t.user_id)) {
+          Some(m) if !m.roles.contains(&RoleId(*t.role_id)) => " (role was removed manually)",
+          Some(_) => "",
+          None => " (member is not in the guild)"
+        };
+        format!("{}. {} has {} for {}{}",
+                t.id,
+                UserId(*t.user_id).mention(),
+                RoleId(*t.role_id).mention(),
+                limits.join(" or "),
+                removed)
+      })
+      .collect();
+    Ok(strings.join("\n").into())
+  }
+}
@@ -0,0 +1,89 @@
+mod add;
+mod cancel;
+mod extend;
+mod list;
+
+use crate::bot::BotEnv;
+
+use lalafell::error::*;
+use lalafell::commands::prelude::*;
+
+use std::sync::Arc;
+
+/// The names and aliases of the subcommands. Anything else is given to `add`.
+const SUBCOMMAND_NAMES: &[&str] = &["add", "give", "create", "list", "show", "cancel", "remove", "delete", "extend", "help"];
+
+#[derive(BotCommand)]
+pub struct TemporaryRoleCommand {
+  env: Arc<BotEnv>,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Manage roles given to members for a given number of messages or time")]
+pub enum Params {
+  #[structopt(name = "add", aliases = &["give", "create"], about = "Give a role to a member for a given number of messages or time")]
+  #[structopt(template = ::lalafell::commands::TEMPLATE)]
+  #[structopt(setting = ::structopt::clap::AppSettings::ArgRequiredElseHelp)]
+  Add(add::Params),
+
+  #[structopt(name = "list", alias = "show", about = "List active temporary roles")]
+  #[structopt(template = ::lalafell::commands::TEMPLATE)]
+  List(list::Params),
+
+  #[structopt(name = "cancel", aliases = &["remove", "delete"], about = "Remove a temporary role early")]
+  #[structopt(template = ::lalafell::commands::TEMPLATE)]
+  #[structopt(setting = ::structopt::clap::AppSettings::ArgRequiredElseHelp)]
+  Cancel(cancel::Params),
+
+  #[structopt(name = "extend", about = "Extend a temporary role by a number of messages or time")]
+  #[structopt(template = ::lalafell::commands::TEMPLATE)]
+  #[structopt(setting = ::structopt::clap::AppSettings::ArgRequiredElseHelp)]
+  Extend(extend::Params)
+}
+
+impl HasParams for TemporaryRoleCommand {
+  type Params = Params;
+}
+
+impl<'a> PublicChannelCommand<'a> for TemporaryRoleCommand {
+  fn run(&self, ctx: &Context, msg: &Message, guild_id: GuildId, _: Arc<RwLock<GuildChannel>>, params: &[&str]) -> CommandResult<'a> {
+    struct SubCommands {
+      add: add::AddCommand,
+      list: list::ListCommand,
+      cancel: cancel::CancelCommand,
+      extend: extend::ExtendCommand
+    }
+
+    const SUBCOMMANDS: SubCommands = SubCommands {
+      add: add::AddCommand,
+      list: list::ListCommand,
+      cancel: cancel::CancelCommand,
+      extend: extend::ExtendCommand
+    };
+
+    let member = guild_id.member(ctx, &msg.author).chain_err(|| "could not get member")?;
+    if !member.permissions(&ctx).chain_err(|| "could not get permissions")?.manage_roles() {
+      return Err(ExternalCommandFailure::default()
+        .message(|e: &mut CreateEmbed| e
+          .title("Not enough permissions.")
+          .description("You don't have enough permissions to use this command."))
+        .wrap());
+    }
+
+    // `!temprole <who> <role> ...` was how roles were given before there were subcommands
+    let params: Vec<&str> = match params.first() {
+      Some(first) if !first.starts_with('-') && !SUBCOMMAND_NAMES.contains(&first.to_lowercase().as_str()) => {
+        std::iter::once("add").chain(params.iter().cloned()).collect()
+      },
+      _ => params.to_vec(),
+    };
+    let params = self.params_then("temporaryrole", &params, |a| a.setting(structopt::clap::AppSettings::ArgRequiredElseHelp))?;
+
+    match params {
+      Params::Add(p) => SUBCOMMANDS.add.run(ctx, &self.env, msg, guild_id, p),
+      Params::List(p) => SUBCOMMANDS.list.run(ctx, guild_id, p),
+      Params::Cancel(p) => SUBCOMMANDS.cancel.run(ctx, guild_id, p),
+      Params::Extend(p) => SUBCOMMANDS.extend.run(ctx, &self.env, guild_id, p)
+    }
+  }
+}
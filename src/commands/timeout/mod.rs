@@ -1,14 +1,15 @@
 use crate::error::*;
-use crate::database::models::{ToU64, ServerConfig};
+use crate::database::models::{ToU64, ServerConfig, Timeout, encode_roles};
 
 use diesel::prelude::*;
 
 use serenity::{
   client::Context,
+  http::Http,
   model::{
     channel::{PermissionOverwrite, PermissionOverwriteType},
-    guild::Guild,
-    id::RoleId,
+    guild::{Guild, Member},
+    id::{GuildId, RoleId, UserId},
     permissions::Permissions,
   },
 };
@@ -77,3 +78,72 @@ pub fn set_up_timeouts(ctx: &Context, guild: &Guild) -> Result<RoleId> {
   }
   Ok(role_id)
 }
+
+fn strict_timeouts(guild: GuildId, user: UserId) -> Result<Vec<Timeout>> {
+  crate::bot::with_connection(|c| {
+    use crate::database::schema::timeouts::dsl;
+    dsl::timeouts
+      .filter(dsl::server_id.eq(guild.to_u64())
+        .and(dsl::user_id.eq(user.to_u64()))
+        .and(dsl::stripped_roles.is_not_null()))
+      .load(c)
+  }).chain_err(|| "could not load timeouts")
+}
+
+/// Get the roles taken from a member for strict timeouts they're in.
+pub fn stripped_roles(guild: GuildId, user: UserId) -> Result<Vec<RoleId>> {
+  Ok(strict_timeouts(guild, user)?.iter()
+    .flat_map(|t| t.stripped_roles().unwrap_or_default())
+    .map(RoleId)
+    .collect())
+}
+
+/// Stop giving a role back when a member's strict timeouts end, because it was only meant to be
+/// theirs for a while and that time is up.
+pub fn forget_stripped_role(guild: GuildId, user: UserId, role: RoleId) -> Result<()> {
+  for timeout in strict_timeouts(guild, user)? {
+    let roles = timeout.stripped_roles().unwrap_or_default();
+    if !roles.contains(&role.0) {
+      continue;
+    }
+    let kept: Vec<u64> = roles.into_iter().filter(|&r| r != role.0).collect();
+    crate::bot::with_connection(|c| {
+      use crate::database::schema::timeouts::dsl;
+      diesel::update(&timeout)
+        .set(dsl::stripped_roles.eq(Some(encode_roles(&kept))))
+        .execute(c)
+    }).chain_err(|| "could not update timeout")?;
+  }
+  Ok(())
+}
+
+/// Take a member out of a timeout.
+///
+/// This removes the timeout role, gives back any roles taken for a strict timeout, and deletes the
+/// timeout from the database. If the timeout role can't be removed or a role that still exists
+/// can't be given back, the timeout is kept so lifting it can be tried again.
+pub fn lift_timeout<H: AsRef<Http>>(http: H, member: &mut Member, timeout: &Timeout) -> Result<()> {
+  let http = http.as_ref();
+  if member.roles.contains(&RoleId(*timeout.role_id)) {
+    member.remove_role(http, *timeout.role_id).chain_err(|| "could not remove timeout role")?;
+  }
+  // restore roles one at a time, since any of them may have been deleted during the timeout
+  let mut failed = Vec::new();
+  for role in timeout.stripped_roles().unwrap_or_default() {
+    if member.roles.contains(&RoleId(role)) {
+      continue;
+    }
+    if let Err(e) = member.add_role(http, role) {
+      warn!("could not restore role {} to {}: {}", role, *timeout.user_id, e);
+      failed.push(RoleId(role));
+    }
+  }
+  if !failed.is_empty() {
+    let roles = member.guild_id.roles(http).chain_err(|| "could not get roles")?;
+    if failed.iter().any(|r| roles.contains_key(r)) {
+      return Err("could not restore every role taken for the timeout".into());
+    }
+  }
+  crate::bot::with_connection(|c| diesel::delete(timeout).execute(c)).chain_err(|| "could not delete timeout")?;
+  Ok(())
+}
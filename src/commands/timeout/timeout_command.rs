@@ -8,6 +8,7 @@ use lalafell::commands::prelude::*;
 
 use serenity::builder::CreateEmbed;
 use serenity::model::channel::{Message, GuildChannel};
+use serenity::model::id::RoleId;
 use serenity::model::misc::Mentionable;
 
 use diesel::prelude::*;
@@ -27,6 +28,12 @@ pub struct TimeoutCommand {
 pub struct Params {
   #[structopt(help = "Who to timeout")]
   who: MentionOrId,
+  #[structopt(
+    short = "s",
+    long = "strict",
+    help = "Take away the member's roles for the duration of the timeout"
+  )]
+  strict: bool,
   #[structopt(help = "How long to time out the person for")]
   length: Vec<String>
 }
@@ -67,27 +74,52 @@ impl<'a> PublicChannelCommand<'a> for TimeoutCommand {
       return Err(format!("{} is already timed out.", who.mention()).into());
     }
 
+    let duration = match parse_duration_secs(&params.length.into_iter().collect::<String>()) {
+      Ok(d) => d,
+      Err(_) => return Err("Invalid time length. Try \"15m\" or \"3 hours\" for example.".into())
+    };
+
     let role_id = match timeout::set_up_timeouts(ctx, &guild.read()) {
-      Ok(r) => {
-        if let Err(e) = timeout_member.add_role(&ctx, r) {
-          warn!("could not add user {} to timeout role: {}", who.0, e);
-        }
-        r
-      },
+      Ok(r) => r,
       Err(e) => {
         warn!("could not set up timeouts for {}: {}", guild.read().id.0, e);
         return Err("Could not set up timeouts for this server. Do I have enough permissions?".into());
       }
     };
 
-    let duration = match parse_duration_secs(&params.length.into_iter().collect::<String>()) {
-      Ok(d) => d,
-      Err(_) => return Err("Invalid time length. Try \"15m\" or \"3 hours\" for example.".into())
+    let stripped_roles = if params.strict {
+      // managed roles belong to integrations and can't be taken away
+      let guild = guild.read();
+      let roles: Vec<u64> = timeout_member.roles.iter()
+        .filter(|&&r| r != role_id && guild.roles.get(&r).map(|x| !x.managed).unwrap_or(false))
+        .map(|r| r.0)
+        .collect();
+      Some(roles)
+    } else {
+      None
     };
 
-    let timeout_user = NewTimeout::new(who.0, server_id.0, role_id.0, duration as i32, Utc::now().timestamp());
+    // store the timeout before touching roles, so it can always be lifted
+    let timeout_user = NewTimeout::new(who.0, server_id.0, role_id.0, duration as i32, Utc::now().timestamp(), stripped_roles.as_ref().map(Vec::as_slice));
     let timeout = crate::bot::with_connection(|c| diesel::insert_into(timeouts::table).values(&timeout_user).get_result(c)).chain_err(|| "could not insert timeout")?;
 
+    if let Err(e) = timeout_member.add_role(&ctx, role_id) {
+      warn!("could not add user {} to timeout role: {}", who.0, e);
+    }
+
+    if let Some(ref roles) = stripped_roles {
+      if !roles.is_empty() {
+        let roles: Vec<RoleId> = roles.iter().map(|&r| RoleId(r)).collect();
+        if let Err(e) = timeout_member.remove_roles(&ctx, &roles) {
+          warn!("could not remove roles for strict timeout of {}: {}", who.0, e);
+          if let Err(e) = timeout::lift_timeout(&ctx, &mut timeout_member, &timeout) {
+            warn!("could not undo timeout {}: {}", timeout.id, e);
+          }
+          return Err("Could not take away that member's roles. Do I have enough permissions?".into());
+        }
+      }
+    }
+
     // spawn a task if the duration is less than the check task period
     if duration < 300 {
       let env = Arc::clone(&self.env);
@@ -55,8 +55,7 @@ impl<'a> PublicChannelCommand<'a> for UntimeoutCommand {
     }
     let timeout = &timeouts[0];
 
-    crate::bot::with_connection(|c| diesel::delete(timeout).execute(c)).chain_err(|| "could not delete timeout")?;
-    timeout_member.remove_role(&ctx, *timeout.role_id).chain_err(|| "could not remove role")?;
+    crate::commands::timeout::lift_timeout(&ctx, &mut timeout_member, timeout).chain_err(|| "could not lift timeout")?;
 
     Ok(CommandSuccess::default())
   }
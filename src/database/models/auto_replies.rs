@@ -3,6 +3,11 @@ use crate::database::{
   models::U64,
 };
 
+use std::{
+  fmt::{Display, Formatter, Result as FmtResult},
+  str::FromStr,
+};
+
 insertable! {
   #[derive(Debug, Queryable, Identifiable)]
   #[table_name = "auto_replies"]
@@ -16,5 +21,166 @@ insertable! {
     pub on_join: bool,
     pub delay: i32,
     pub filters: Option<String>,
+    pub trigger_kind: Option<String>,
+    pub trigger: Option<String>,
+    pub case_sensitive: bool,
+    pub once_per_user: bool,
+    pub on_leave: bool,
+    pub delivery: String,
+    pub plain: bool,
+    pub embed_title: Option<String>,
+    pub embed_color: Option<i32>,
+    pub embed_image: Option<String>,
+    pub cooldown_scope: String,
+  }
+}
+
+impl AutoReply {
+  /// What kind of trigger this auto reply has. Auto replies without one reply to every message.
+  pub fn trigger_kind(&self) -> Option<TriggerKind> {
+    self.trigger_kind.as_ref().and_then(|k| k.parse().ok())
+  }
+
+  /// Where this auto reply sends its message, defaulting to its channel.
+  pub fn delivery(&self) -> Delivery {
+    self.delivery.parse().unwrap_or(Delivery::Channel)
+  }
+
+  /// Who this auto reply's delay applies to, defaulting to each member.
+  pub fn cooldown_scope(&self) -> CooldownScope {
+    self.cooldown_scope.parse().unwrap_or(CooldownScope::User)
+  }
+}
+
+/// Someone who has already been replied to by an auto reply that only replies once per member.
+#[derive(Debug, Insertable)]
+#[table_name = "auto_reply_recipients"]
+pub struct NewAutoReplyRecipient {
+  pub auto_reply_id: i32,
+  pub user_id: U64,
+}
+
+/// A time before which an auto reply won't send its message again. `scope_id` is a user ID, a
+/// channel ID or `0`, depending on the auto reply's cooldown scope.
+#[derive(Debug, Insertable)]
+#[table_name = "auto_reply_cooldowns"]
+pub struct NewAutoReplyCooldown {
+  pub auto_reply_id: i32,
+  pub scope_id: U64,
+  pub expires_at: i64,
+}
+
+/// How an auto reply's trigger is matched against messages.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TriggerKind {
+  /// The whole message is the trigger.
+  Exact,
+  /// The trigger appears in the message as whole words.
+  Contains,
+  /// The message matches the trigger as a regular expression.
+  Regex,
+}
+
+impl FromStr for TriggerKind {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.to_lowercase().as_str() {
+      "exact" => Ok(TriggerKind::Exact),
+      "contains" => Ok(TriggerKind::Contains),
+      "regex" => Ok(TriggerKind::Regex),
+      _ => Err(format!("invalid trigger type `{}` (expected exact, contains or regex)", s)),
+    }
+  }
+}
+
+impl Display for TriggerKind {
+  fn fmt(&self, f: &mut Formatter) -> FmtResult {
+    let s = match *self {
+      TriggerKind::Exact => "exact",
+      TriggerKind::Contains => "contains",
+      TriggerKind::Regex => "regex",
+    };
+    write!(f, "{}", s)
+  }
+}
+
+/// Where an auto reply sends its message.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Delivery {
+  /// The auto reply's channel.
+  Channel,
+  /// A direct message to the member.
+  Dm,
+  /// Both the channel and a direct message.
+  Both,
+}
+
+impl Delivery {
+  pub fn sends_to_channel(self) -> bool {
+    self != Delivery::Dm
+  }
+
+  pub fn sends_dm(self) -> bool {
+    self != Delivery::Channel
+  }
+}
+
+impl FromStr for Delivery {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.to_lowercase().as_str() {
+      "channel" => Ok(Delivery::Channel),
+      "dm" => Ok(Delivery::Dm),
+      "both" => Ok(Delivery::Both),
+      _ => Err(format!("invalid delivery `{}` (expected channel, dm or both)", s)),
+    }
+  }
+}
+
+impl Display for Delivery {
+  fn fmt(&self, f: &mut Formatter) -> FmtResult {
+    let s = match *self {
+      Delivery::Channel => "channel",
+      Delivery::Dm => "dm",
+      Delivery::Both => "both",
+    };
+    write!(f, "{}", s)
+  }
+}
+
+/// Who an auto reply's delay applies to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CooldownScope {
+  /// Each member has their own delay.
+  User,
+  /// Each channel the auto reply is set off in has its own delay.
+  Channel,
+  /// One delay for everyone.
+  Global,
+}
+
+impl FromStr for CooldownScope {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.to_lowercase().as_str() {
+      "user" | "member" => Ok(CooldownScope::User),
+      "channel" => Ok(CooldownScope::Channel),
+      "global" => Ok(CooldownScope::Global),
+      _ => Err(format!("invalid cooldown scope `{}` (expected user, channel or global)", s)),
+    }
+  }
+}
+
+impl Display for CooldownScope {
+  fn fmt(&self, f: &mut Formatter) -> FmtResult {
+    let s = match *self {
+      CooldownScope::User => "user",
+      CooldownScope::Channel => "channel",
+      CooldownScope::Global => "global",
+    };
+    write!(f, "{}", s)
   }
 }
@@ -4,6 +4,6 @@ pub mod server;
 
 pub use self::{
   channel::{ChannelConfig, NewChannelConfig},
-  reactions::{Reaction, NewReaction},
+  reactions::{Reaction, NewReaction, ReactionGrant, NewReactionGrant, ReactionGroup, NewReactionGroup, ReactionGroupMode, ReactionMenu, NewReactionMenu},
   server::{ServerConfig, NewServerConfig},
 };
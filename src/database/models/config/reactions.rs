@@ -3,6 +3,11 @@ use crate::database::{
   models::U64,
 };
 
+use std::{
+  fmt::{Display, Formatter, Result as FmtResult},
+  str::FromStr,
+};
+
 insertable! {
   #[derive(Debug, Queryable, Identifiable)]
   pub struct Reaction,
@@ -14,5 +19,93 @@ insertable! {
     pub message_id: U64,
     pub emoji: String,
     pub role_id: U64,
+    pub group_id: Option<i32>,
+    pub filters: Option<String>,
+  }
+}
+
+insertable! {
+  #[derive(Debug, Queryable, Identifiable)]
+  pub struct ReactionGroup,
+  #[derive(Debug, Insertable)]
+  #[table_name = "reaction_groups"]
+  pub struct NewReactionGroup {
+    pub server_id: U64,
+    pub name: String,
+    pub mode: String,
+    pub max_roles: Option<i32>,
+  }
+}
+
+insertable! {
+  #[derive(Debug, Queryable, Identifiable, AsChangeset)]
+  #[changeset_options(treat_none_as_null = "true")]
+  pub struct ReactionMenu,
+  #[derive(Debug, Insertable)]
+  #[table_name = "reaction_menus"]
+  pub struct NewReactionMenu {
+    pub server_id: U64,
+    pub channel_id: U64,
+    pub message_id: U64,
+    pub title: String,
+    pub description: Option<String>,
+  }
+}
+
+insertable! {
+  #[derive(Debug, Queryable, Identifiable)]
+  pub struct ReactionGrant,
+  /// A role the bot gave a member because of a reaction, so it knows which roles it may take back.
+  #[derive(Debug, Insertable)]
+  #[table_name = "reaction_grants"]
+  pub struct NewReactionGrant {
+    pub server_id: U64,
+    pub user_id: U64,
+    pub role_id: U64,
+  }
+}
+
+impl ReactionGroup {
+  pub fn mode(&self) -> Option<ReactionGroupMode> {
+    self.mode.parse().ok()
+  }
+}
+
+/// How the reaction roles in a group behave together.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReactionGroupMode {
+  /// Members can have one role from the group. Picking another removes the old one and its reaction.
+  Unique,
+  /// Members can have up to `max_roles` roles from the group. Reactions past the limit are removed.
+  Limit,
+  /// Reacting adds the role, but removing the reaction doesn't remove it.
+  Verify,
+  /// Reacting removes the role, and nothing ever adds it.
+  Drop,
+}
+
+impl FromStr for ReactionGroupMode {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.to_lowercase().as_str() {
+      "unique" => Ok(ReactionGroupMode::Unique),
+      "limit" => Ok(ReactionGroupMode::Limit),
+      "verify" => Ok(ReactionGroupMode::Verify),
+      "drop" => Ok(ReactionGroupMode::Drop),
+      _ => Err(format!("invalid mode `{}` (expected unique, limit, verify or drop)", s)),
+    }
+  }
+}
+
+impl Display for ReactionGroupMode {
+  fn fmt(&self, f: &mut Formatter) -> FmtResult {
+    let s = match *self {
+      ReactionGroupMode::Unique => "unique",
+      ReactionGroupMode::Limit => "limit",
+      ReactionGroupMode::Verify => "verify",
+      ReactionGroupMode::Drop => "drop",
+    };
+    write!(f, "{}", s)
   }
 }
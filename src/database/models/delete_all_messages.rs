@@ -6,8 +6,9 @@ use crate::database::{
 use byteorder::{ByteOrder, LittleEndian};
 
 insertable! {
-  #[derive(Debug, Queryable, Identifiable)]
+  #[derive(Debug, Queryable, Identifiable, AsChangeset)]
   #[table_name = "delete_all_messages"]
+  #[changeset_options(treat_none_as_null = "true")]
   pub struct DeleteAllMessages,
   #[derive(Debug, Insertable)]
   #[table_name = "delete_all_messages"]
@@ -16,6 +17,15 @@ insertable! {
     pub channel_id: U64,
     pub after: i32,
     pub exclude: Vec<u8>,
+    pub resume_before: Option<i64>,
+    pub sweep_started: Option<i64>,
+    pub last_sweep: Option<i64>,
+    pub oldest_failed: Option<i64>,
+    pub keep_pinned: bool,
+    pub keep_filters: Option<String>,
+    pub only_bots: bool,
+    pub attachments: Option<bool>,
+    pub keep_latest: i32,
   }
 }
 
@@ -26,6 +36,10 @@ impl DeleteAllMessages {
 }
 
 impl NewDeleteAllMessages {
+  pub fn exclude(&self) -> Vec<u64> {
+    self.exclude.chunks(8).map(|x| LittleEndian::read_u64(x)).collect()
+  }
+
   pub fn new(server_id: u64, channel_id: u64, after: i32, exclude: &[u64]) -> Self {
     let mut bytes = vec![0; exclude.len() * 8];
     if !exclude.is_empty() {
@@ -36,6 +50,15 @@ impl NewDeleteAllMessages {
       channel_id: channel_id.into(),
       after,
       exclude: bytes,
+      resume_before: None,
+      sweep_started: None,
+      last_sweep: None,
+      oldest_failed: None,
+      keep_pinned: false,
+      keep_filters: None,
+      only_bots: false,
+      attachments: None,
+      keep_latest: 0,
     }
   }
 }
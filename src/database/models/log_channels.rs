@@ -3,6 +3,13 @@ use crate::database::{
   models::U64,
 };
 
+use serenity::model::id::ChannelId;
+
+use std::{
+  fmt::{Display, Formatter, Result as FmtResult},
+  str::FromStr,
+};
+
 #[derive(Debug, Queryable)]
 pub struct LogChannel {
   pub server_id: U64,
@@ -15,3 +22,129 @@ pub struct NewLogChannel {
   pub server_id: U64,
   pub channel_id: U64,
 }
+
+insertable! {
+  #[derive(Debug, Queryable)]
+  pub struct LogRoute,
+  #[derive(Debug, Insertable)]
+  #[table_name = "log_routes"]
+  pub struct NewLogRoute {
+    pub server_id: U64,
+    pub category: String,
+    pub channel_id: Option<i64>,
+  }
+}
+
+impl LogRoute {
+  pub fn category(&self) -> Option<LogCategory> {
+    self.category.parse().ok()
+  }
+
+  /// The channel the category's events go to, or `None` if they aren't logged.
+  pub fn channel(&self) -> Option<ChannelId> {
+    self.channel_id.map(|c| ChannelId(c as u64))
+  }
+}
+
+insertable! {
+  #[derive(Debug, Queryable)]
+  pub struct LogIgnore,
+  #[derive(Debug, Insertable)]
+  #[table_name = "log_ignores"]
+  pub struct NewLogIgnore {
+    pub server_id: U64,
+    pub kind: String,
+    pub target_id: U64,
+  }
+}
+
+impl LogIgnore {
+  pub fn kind(&self) -> Option<IgnoreKind> {
+    self.kind.parse().ok()
+  }
+}
+
+/// A group of events that can be logged to their own channel. Events in a category without a route
+/// go to the guild's log channel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogCategory {
+  /// Joins, leaves, nickname and role changes.
+  Member,
+  /// Message edits and deletions.
+  Message,
+  /// Bans and unbans.
+  Moderation,
+  /// Joining, leaving and moving between voice channels.
+  Voice,
+  /// Channels and roles being created, deleted or changed.
+  Server,
+}
+
+impl LogCategory {
+  pub const ALL: [LogCategory; 5] = [
+    LogCategory::Member,
+    LogCategory::Message,
+    LogCategory::Moderation,
+    LogCategory::Voice,
+    LogCategory::Server,
+  ];
+}
+
+impl FromStr for LogCategory {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.to_lowercase().as_str() {
+      "member" | "members" => Ok(LogCategory::Member),
+      "message" | "messages" => Ok(LogCategory::Message),
+      "moderation" | "mod" => Ok(LogCategory::Moderation),
+      "voice" => Ok(LogCategory::Voice),
+      "server" => Ok(LogCategory::Server),
+      _ => Err(format!("invalid log category `{}` (expected member, message, moderation, voice or server)", s)),
+    }
+  }
+}
+
+impl Display for LogCategory {
+  fn fmt(&self, f: &mut Formatter) -> FmtResult {
+    let s = match *self {
+      LogCategory::Member => "member",
+      LogCategory::Message => "message",
+      LogCategory::Moderation => "moderation",
+      LogCategory::Voice => "voice",
+      LogCategory::Server => "server",
+    };
+    write!(f, "{}", s)
+  }
+}
+
+/// What a log ignore's target ID refers to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IgnoreKind {
+  /// Events in the channel aren't logged.
+  Channel,
+  /// Events about members with the role aren't logged.
+  Role,
+}
+
+impl FromStr for IgnoreKind {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.to_lowercase().as_str() {
+      "channel" => Ok(IgnoreKind::Channel),
+      "role" => Ok(IgnoreKind::Role),
+      _ => Err(format!("invalid ignore kind `{}` (expected channel or role)", s)),
+    }
+  }
+}
+
+impl Display for IgnoreKind {
+  fn fmt(&self, f: &mut Formatter) -> FmtResult {
+    let s = match *self {
+      IgnoreKind::Channel => "channel",
+      IgnoreKind::Role => "role",
+    };
+    write!(f, "{}", s)
+  }
+}
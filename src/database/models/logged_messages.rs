@@ -0,0 +1,85 @@
+use crate::database::{
+  schema::*,
+  models::U64,
+};
+
+/// A message kept so its original content can be logged when it's edited or deleted.
+#[derive(Debug, Clone, Queryable)]
+pub struct LoggedMessage {
+  pub message_id: U64,
+  pub server_id: U64,
+  pub channel_id: U64,
+  pub author_id: U64,
+  pub content: String,
+  /// The URLs of the message's attachments, one per line.
+  pub attachments: String,
+  pub created_at: i64,
+}
+
+impl LoggedMessage {
+  pub fn attachments(&self) -> Vec<&str> {
+    self.attachments.lines().filter(|a| !a.is_empty()).collect()
+  }
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "logged_messages"]
+pub struct NewLoggedMessage {
+  pub message_id: U64,
+  pub server_id: U64,
+  pub channel_id: U64,
+  pub author_id: U64,
+  pub content: String,
+  pub attachments: String,
+  pub created_at: i64,
+}
+
+insertable! {
+  #[derive(Debug, Queryable, Identifiable, AsChangeset)]
+  #[table_name = "log_settings"]
+  #[changeset_options(treat_none_as_null = "true")]
+  pub struct LogSettings,
+  #[derive(Debug, Insertable)]
+  #[table_name = "log_settings"]
+  pub struct NewLogSettings {
+    pub server_id: U64,
+    /// How many seconds to keep messages for.
+    pub retention: Option<i32>,
+    /// How many of the newest messages to keep.
+    pub max_messages: Option<i32>,
+    /// Whether to keep local copies of logged messages' attachments.
+    pub keep_attachments: bool,
+    /// The largest attachment to keep, in bytes.
+    pub max_attachment_size: Option<i32>,
+    /// How many seconds to keep attachments for.
+    pub attachment_retention: Option<i32>,
+  }
+}
+
+impl LogSettings {
+  /// Messages are kept for a day by default.
+  pub const DEFAULT_RETENTION: i32 = 86_400;
+  pub const DEFAULT_MAX_MESSAGES: i32 = 10_000;
+  /// A bot can upload 8 MiB in one message, including the rest of the message, so attachments have
+  /// to be a little smaller to be uploaded to the log again. This is also the most that can be set.
+  pub const DEFAULT_MAX_ATTACHMENT_SIZE: i32 = 8000 * 1024;
+  pub const DEFAULT_ATTACHMENT_RETENTION: i32 = 86_400;
+}
+
+insertable! {
+  /// A local copy of a logged message's attachment, so it can be uploaded again if the message is
+  /// deleted.
+  #[derive(Debug, Clone, Queryable)]
+  pub struct LoggedAttachment,
+  #[derive(Debug, Insertable)]
+  #[table_name = "logged_attachments"]
+  pub struct NewLoggedAttachment {
+    pub message_id: U64,
+    pub server_id: U64,
+    pub filename: String,
+    pub path: String,
+    /// The size of the file, in bytes.
+    pub size: i32,
+    pub created_at: i64,
+  }
+}
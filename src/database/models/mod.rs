@@ -22,28 +22,36 @@ pub mod config;
 pub mod ephemeral_messages;
 pub mod delete_all_messages;
 pub mod log_channels;
+pub mod logged_messages;
+pub mod polls;
 pub mod presences;
 pub mod role_check_times;
 pub mod roles;
+pub mod scheduled_messages;
 pub mod tags;
+pub mod temporary_overwrites;
 pub mod temporary_roles;
 pub mod tag_queue;
 pub mod timeouts;
 pub mod verifications;
 
 // pub use self::administrators::{Administrator, NewAdministrator};
-pub use self::auto_replies::{AutoReply, NewAutoReply};
-pub use self::config::{ServerConfig, NewServerConfig, ChannelConfig, NewChannelConfig, Reaction, NewReaction};
+pub use self::auto_replies::{AutoReply, NewAutoReply, NewAutoReplyRecipient, NewAutoReplyCooldown, TriggerKind, Delivery, CooldownScope};
+pub use self::config::{ServerConfig, NewServerConfig, ChannelConfig, NewChannelConfig, Reaction, NewReaction, ReactionGrant, NewReactionGrant, ReactionGroup, NewReactionGroup, ReactionGroupMode, ReactionMenu, NewReactionMenu};
 pub use self::ephemeral_messages::{EphemeralMessage, NewEphemeralMessage};
 pub use self::delete_all_messages::{DeleteAllMessages, NewDeleteAllMessages};
-pub use self::log_channels::{LogChannel, NewLogChannel};
+pub use self::log_channels::{LogChannel, NewLogChannel, LogRoute, NewLogRoute, LogIgnore, NewLogIgnore, LogCategory, IgnoreKind};
+pub use self::logged_messages::{LoggedMessage, NewLoggedMessage, LogSettings, NewLogSettings, LoggedAttachment, NewLoggedAttachment};
+pub use self::polls::{Poll, NewPoll, PollOption, NewPollOption, PollBallot, NewPollBallot};
 pub use self::presences::{Presence, NewPresence, PresenceKind};
 pub use self::role_check_times::{RoleCheckTime, NewRoleCheckTime};
 pub use self::roles::{Role, NewRole};
+pub use self::scheduled_messages::{ScheduledMessage, NewScheduledMessage};
 pub use self::tags::{Tag, NewTag};
+pub use self::temporary_overwrites::{TemporaryOverwrite, NewTemporaryOverwrite};
 pub use self::temporary_roles::{TemporaryRole, NewTemporaryRole};
 pub use self::tag_queue::{TagQueue, NewTagQueue};
-pub use self::timeouts::{Timeout, NewTimeout};
+pub use self::timeouts::{Timeout, NewTimeout, encode_roles};
 pub use self::verifications::{Verification, NewVerification};
 
 use serenity::model::id::{UserId, GuildId, ChannelId, MessageId, RoleId, EmojiId};
@@ -0,0 +1,88 @@
+use crate::database::{
+  schema::*,
+  models::U64,
+};
+
+insertable! {
+  #[derive(Debug, Queryable, Identifiable, AsChangeset)]
+  #[changeset_options(treat_none_as_null = "true")]
+  pub struct Poll,
+  #[derive(Debug, Insertable)]
+  #[table_name = "polls"]
+  pub struct NewPoll {
+    pub server_id: U64,
+    pub channel_id: U64,
+    pub message_id: U64,
+    pub author_id: U64,
+    pub text: String,
+    pub ends_at: Option<i64>,
+    pub closed: bool,
+    /// Whether voting for an option takes back the member's other votes.
+    pub single: bool,
+    pub max_votes: Option<i32>,
+    /// Filters members have to match for their votes to count.
+    pub filters: Option<String>,
+    /// Whether only members who joined before the poll was posted can vote.
+    pub joined_before: bool,
+    /// The final number of members whose votes counted, once the poll is closed.
+    pub voters: Option<i32>,
+    /// Whether members rank the options in a DM instead of reacting to them.
+    pub ranked: bool,
+  }
+}
+
+/// A member's ranking of a ranked poll's options.
+#[derive(Debug, Queryable)]
+pub struct PollBallot {
+  pub poll_id: i32,
+  pub user_id: U64,
+  /// The positions of the options in order of preference, separated by commas.
+  pub ranking: Option<String>,
+  /// Whether the bot is waiting for the member to send their ranking.
+  pub awaiting: bool,
+  pub requested_at: i64,
+}
+
+impl PollBallot {
+  pub fn ranking(&self) -> Vec<usize> {
+    self.ranking.as_ref()
+      .map(|r| r.split(',').filter_map(|p| p.trim().parse().ok()).collect())
+      .unwrap_or_default()
+  }
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "poll_ballots"]
+pub struct NewPollBallot {
+  pub poll_id: i32,
+  pub user_id: U64,
+  pub ranking: Option<String>,
+  pub awaiting: bool,
+  pub requested_at: i64,
+}
+
+impl Poll {
+  /// How many options each member can vote for, if there is a limit.
+  pub fn vote_limit(&self) -> Option<usize> {
+    if self.single {
+      return Some(1);
+    }
+    self.max_votes.map(|m| m.max(1) as usize)
+  }
+}
+
+insertable! {
+  #[derive(Debug, Queryable, Identifiable, AsChangeset)]
+  #[changeset_options(treat_none_as_null = "true")]
+  pub struct PollOption,
+  #[derive(Debug, Insertable)]
+  #[table_name = "poll_options"]
+  pub struct NewPollOption {
+    pub poll_id: i32,
+    pub position: i32,
+    pub emoji: String,
+    pub text: String,
+    /// The final number of votes, once the poll is closed.
+    pub votes: Option<i32>,
+  }
+}
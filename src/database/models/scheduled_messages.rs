@@ -0,0 +1,30 @@
+use crate::database::{
+  schema::*,
+  models::U64,
+};
+use crate::recurrence::Recurrence;
+
+insertable! {
+  #[derive(Debug, Queryable, Identifiable, AsChangeset)]
+  #[changeset_options(treat_none_as_null = "true")]
+  pub struct ScheduledMessage,
+  #[derive(Debug, Insertable)]
+  #[table_name = "scheduled_messages"]
+  pub struct NewScheduledMessage {
+    pub guild_id: U64,
+    pub channel_id: U64,
+    pub author_id: U64,
+    pub content: String,
+    pub title: Option<String>,
+    pub embed: bool,
+    pub next_run: i64,
+    pub recurrence: Option<String>,
+    pub delete_after: Option<i32>,
+  }
+}
+
+impl ScheduledMessage {
+  pub fn recurrence(&self) -> Option<Recurrence> {
+    self.recurrence.as_ref().and_then(|r| r.parse().ok())
+  }
+}
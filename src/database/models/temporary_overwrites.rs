@@ -0,0 +1,39 @@
+use crate::database::{
+  schema::*,
+  models::U64,
+};
+
+insertable! {
+  #[derive(Debug, Queryable, Identifiable, AsChangeset)]
+  pub struct TemporaryOverwrite,
+  #[derive(Debug, Insertable)]
+  #[table_name = "temporary_overwrites"]
+  pub struct NewTemporaryOverwrite {
+    pub guild_id: U64,
+    pub channel_id: U64,
+    pub user_id: U64,
+    pub message_id: U64,
+    pub allow: i64,
+    pub previous_allow: Option<i64>,
+    pub previous_deny: Option<i64>,
+    pub messages: Option<i32>,
+    pub expires_on: Option<i64>,
+  }
+}
+
+impl NewTemporaryOverwrite {
+  #[allow(clippy::too_many_arguments)]
+  pub fn new(guild_id: u64, channel_id: u64, user_id: u64, message_id: u64, allow: u64, previous: Option<(u64, u64)>, messages: Option<i32>, expires_on: Option<i64>) -> Self {
+    NewTemporaryOverwrite {
+      guild_id: guild_id.into(),
+      channel_id: channel_id.into(),
+      user_id: user_id.into(),
+      message_id: message_id.into(),
+      allow: allow as i64,
+      previous_allow: previous.map(|(allow, _)| allow as i64),
+      previous_deny: previous.map(|(_, deny)| deny as i64),
+      messages,
+      expires_on,
+    }
+  }
+}
@@ -3,6 +3,8 @@ use crate::database::{
   models::U64,
 };
 
+use byteorder::{ByteOrder, LittleEndian};
+
 use chrono::{Utc, TimeZone, Duration};
 
 insertable! {
@@ -16,6 +18,7 @@ insertable! {
     pub role_id: U64,
     pub seconds: i32,
     pub start: i64,
+    pub stripped_roles: Option<Vec<u8>>,
   }
 }
 
@@ -23,16 +26,34 @@ impl Timeout {
   pub fn ends(&self) -> i64 {
     (Utc.timestamp(self.start, 0) + Duration::seconds(i64::from(self.seconds))).timestamp()
   }
+
+  /// The roles taken from the member for a strict timeout, if this timeout is strict.
+  pub fn stripped_roles(&self) -> Option<Vec<u64>> {
+    self.stripped_roles
+      .as_ref()
+      .map(|roles| roles.chunks(8).map(|x| LittleEndian::read_u64(x)).collect())
+  }
+}
+
+/// Store the roles taken for a strict timeout.
+pub fn encode_roles(roles: &[u64]) -> Vec<u8> {
+  let mut bytes = vec![0; roles.len() * 8];
+  if !roles.is_empty() {
+    LittleEndian::write_u64_into(roles, &mut bytes);
+  }
+  bytes
 }
 
 impl NewTimeout {
-  pub fn new(user_id: u64, server_id: u64, role_id: u64, seconds: i32, start: i64) -> Self {
+  pub fn new(user_id: u64, server_id: u64, role_id: u64, seconds: i32, start: i64, stripped_roles: Option<&[u64]>) -> Self {
+    let stripped_roles = stripped_roles.map(encode_roles);
     NewTimeout {
       user_id: user_id.into(),
       server_id: server_id.into(),
       role_id: role_id.into(),
       seconds,
       start,
+      stripped_roles,
     }
   }
 }
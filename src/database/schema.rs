@@ -13,6 +13,32 @@ table! {
         on_join -> Bool,
         delay -> Int4,
         filters -> Nullable<Text>,
+        trigger_kind -> Nullable<Text>,
+        trigger -> Nullable<Text>,
+        case_sensitive -> Bool,
+        once_per_user -> Bool,
+        on_leave -> Bool,
+        delivery -> Text,
+        plain -> Bool,
+        embed_title -> Nullable<Text>,
+        embed_color -> Nullable<Int4>,
+        embed_image -> Nullable<Text>,
+        cooldown_scope -> Text,
+    }
+}
+
+table! {
+    auto_reply_cooldowns (auto_reply_id, scope_id) {
+        auto_reply_id -> Int4,
+        scope_id -> Int8,
+        expires_at -> Int8,
+    }
+}
+
+table! {
+    auto_reply_recipients (auto_reply_id, user_id) {
+        auto_reply_id -> Int4,
+        user_id -> Int8,
     }
 }
 
@@ -32,6 +58,15 @@ table! {
         channel_id -> Int8,
         after -> Int4,
         exclude -> Bytea,
+        resume_before -> Nullable<Int8>,
+        sweep_started -> Nullable<Int8>,
+        last_sweep -> Nullable<Int8>,
+        oldest_failed -> Nullable<Int8>,
+        keep_pinned -> Bool,
+        keep_filters -> Nullable<Text>,
+        only_bots -> Bool,
+        attachments -> Nullable<Bool>,
+        keep_latest -> Int4,
     }
 }
 
@@ -52,6 +87,100 @@ table! {
     }
 }
 
+table! {
+    log_ignores (id) {
+        id -> Int4,
+        server_id -> Int8,
+        kind -> Text,
+        target_id -> Int8,
+    }
+}
+
+table! {
+    log_routes (id) {
+        id -> Int4,
+        server_id -> Int8,
+        category -> Text,
+        channel_id -> Nullable<Int8>,
+    }
+}
+
+table! {
+    log_settings (id) {
+        id -> Int4,
+        server_id -> Int8,
+        retention -> Nullable<Int4>,
+        max_messages -> Nullable<Int4>,
+        keep_attachments -> Bool,
+        max_attachment_size -> Nullable<Int4>,
+        attachment_retention -> Nullable<Int4>,
+    }
+}
+
+table! {
+    logged_attachments (id) {
+        id -> Int4,
+        message_id -> Int8,
+        server_id -> Int8,
+        filename -> Text,
+        path -> Text,
+        size -> Int4,
+        created_at -> Int8,
+    }
+}
+
+table! {
+    logged_messages (message_id) {
+        message_id -> Int8,
+        server_id -> Int8,
+        channel_id -> Int8,
+        author_id -> Int8,
+        content -> Text,
+        attachments -> Text,
+        created_at -> Int8,
+    }
+}
+
+table! {
+    poll_ballots (poll_id, user_id) {
+        poll_id -> Int4,
+        user_id -> Int8,
+        ranking -> Nullable<Text>,
+        awaiting -> Bool,
+        requested_at -> Int8,
+    }
+}
+
+table! {
+    poll_options (id) {
+        id -> Int4,
+        poll_id -> Int4,
+        position -> Int4,
+        emoji -> Text,
+        text -> Text,
+        votes -> Nullable<Int4>,
+    }
+}
+
+table! {
+    polls (id) {
+        id -> Int4,
+        server_id -> Int8,
+        channel_id -> Int8,
+        message_id -> Int8,
+        author_id -> Int8,
+        text -> Text,
+        ends_at -> Nullable<Int8>,
+        closed -> Bool,
+        single -> Bool,
+        max_votes -> Nullable<Int4>,
+        filters -> Nullable<Text>,
+        joined_before -> Bool,
+        voters -> Nullable<Int4>,
+        ranked -> Bool,
+    }
+}
+
 table! {
     presences (id) {
         id -> Int4,
@@ -60,6 +189,36 @@ table! {
     }
 }
 
+table! {
+    reaction_grants (id) {
+        id -> Int4,
+        server_id -> Int8,
+        user_id -> Int8,
+        role_id -> Int8,
+    }
+}
+
+table! {
+    reaction_groups (id) {
+        id -> Int4,
+        server_id -> Int8,
+        name -> Text,
+        mode -> Text,
+        max_roles -> Nullable<Int4>,
+    }
+}
+
+table! {
+    reaction_menus (id) {
+        id -> Int4,
+        server_id -> Int8,
+        channel_id -> Int8,
+        message_id -> Int8,
+        title -> Text,
+        description -> Nullable<Text>,
+    }
+}
+
 table! {
     reactions (id) {
         id -> Int4,
@@ -68,6 +227,8 @@ table! {
         message_id -> Int8,
         emoji -> Text,
         role_id -> Int8,
+        group_id -> Nullable<Int4>,
+        filters -> Nullable<Text>,
     }
 }
 
@@ -87,6 +248,21 @@ table! {
     }
 }
 
+table! {
+    scheduled_messages (id) {
+        id -> Int4,
+        guild_id -> Int8,
+        channel_id -> Int8,
+        author_id -> Int8,
+        content -> Text,
+        title -> Nullable<Text>,
+        embed -> Bool,
+        next_run -> Int8,
+        recurrence -> Nullable<Text>,
+        delete_after -> Nullable<Int4>,
+    }
+}
+
 table! {
     server_configs (id) {
         id -> Int4,
@@ -130,6 +306,21 @@ table! {
     }
 }
 
+table! {
+    temporary_overwrites (id) {
+        id -> Int4,
+        guild_id -> Int8,
+        channel_id -> Int8,
+        user_id -> Int8,
+        message_id -> Int8,
+        allow -> Int8,
+        previous_allow -> Nullable<Int8>,
+        previous_deny -> Nullable<Int8>,
+        messages -> Nullable<Int4>,
+        expires_on -> Nullable<Int8>,
+    }
+}
+
 table! {
     timeouts (id) {
         id -> Int4,
@@ -138,6 +329,7 @@ table! {
         role_id -> Int8,
         seconds -> Int4,
         start -> Int8,
+        stripped_roles -> Nullable<Bytea>,
     }
 }
 
@@ -150,22 +342,42 @@ table! {
     }
 }
 
+joinable!(auto_reply_cooldowns -> auto_replies (auto_reply_id));
+joinable!(auto_reply_recipients -> auto_replies (auto_reply_id));
+joinable!(poll_ballots -> polls (poll_id));
+joinable!(poll_options -> polls (poll_id));
+joinable!(reactions -> reaction_groups (group_id));
 joinable!(verifications -> tags (tag_id));
 
 allow_tables_to_appear_in_same_query!(
     administrators,
     auto_replies,
+    auto_reply_cooldowns,
+    auto_reply_recipients,
     channel_configs,
     delete_all_messages,
     ephemeral_messages,
     log_channels,
+    log_ignores,
+    log_routes,
+    log_settings,
+    logged_attachments,
+    logged_messages,
+    poll_ballots,
+    poll_options,
+    polls,
     presences,
+    reaction_grants,
+    reaction_groups,
+    reaction_menus,
     reactions,
     role_check_times,
     roles,
+    scheduled_messages,
     server_configs,
     tag_queue,
     tags,
+    temporary_overwrites,
     temporary_roles,
     timeouts,
     verifications,
@@ -1,3 +1,5 @@
+use chrono::{NaiveDate, TimeZone, Utc};
+
 use lalafell::commands::MentionOrId;
 
 use serenity::model::guild::{Member, Role};
@@ -17,7 +19,9 @@ impl Filter {
     let mut roles = Vec::new();
     let mut last_index = 0;
     loop {
-      let index = input[last_index..].find("role:").or_else(|| input[last_index..].find("user:"));
+      let index = ["role:", "user:", "joined:"].iter()
+        .filter_map(|kind| input[last_index..].find(kind))
+        .min();
       let mut i = some_or!(index, break);
       if i + last_index != 0 && &input[i + last_index - 1..i + last_index] == "!" {
         i -= 1;
@@ -108,6 +112,8 @@ impl Filter {
         member.roles.iter().any(|r| *r == role.id) == include
       },
       FilterKind::User(id) => (member.user.read().id.0 == id) == include,
+      // members without a known join date can't be shown to have joined before anything
+      FilterKind::JoinedBefore(time) => member.joined_at.map(|j| j.timestamp() < time).unwrap_or(false) == include,
     }
   }
 }
@@ -124,6 +130,8 @@ impl ToString for Filter {
 pub enum FilterKind {
   Role(String),
   User(u64),
+  /// Members who joined before a timestamp, written as a date like `joined:2020-06-01`.
+  JoinedBefore(i64),
 }
 
 impl FilterKind {
@@ -139,6 +147,10 @@ impl FilterKind {
         let id = MentionOrId::from_str(value).ok()?;
         Some(FilterKind::User(id.0))
       },
+      "joined" => {
+        let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+        Some(FilterKind::JoinedBefore(date.and_hms(0, 0, 0).timestamp()))
+      },
       _ => None,
     }
   }
@@ -150,6 +162,7 @@ impl ToString for FilterKind {
       FilterKind::Role(ref role) if role.contains(' ') => format!("role:`{}`", role),
       FilterKind::Role(ref role) => format!("role:{}", role),
       FilterKind::User(id) => format!("user:{}", id),
+      FilterKind::JoinedBefore(time) => format!("joined:{}", Utc.timestamp(time, 0).format("%Y-%m-%d")),
     }
   }
 }
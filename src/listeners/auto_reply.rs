@@ -1,35 +1,54 @@
 use crate::{
-  database::models::{ToU64, AutoReply},
+  database::models::{ToU64, U64, AutoReply, NewAutoReplyRecipient, NewAutoReplyCooldown, TriggerKind, CooldownScope},
   error::*,
   filters::Filter,
+  template::{self, Values},
 };
 
 use diesel::prelude::*;
 
 use serenity::{
+  builder::CreateMessage,
   client::{Context, EventHandler},
   model::{
     channel::{Channel, Message},
     guild::{Role, Member},
     id::{ChannelId, GuildId, UserId},
-    misc::Mentionable,
+    user::User,
   },
 };
 
 use chrono::Utc;
 
+use regex::{Regex, RegexBuilder};
+
 use serenity::prelude::Mutex;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+lazy_static! {
+  /// Compiled triggers by auto reply ID, along with the pattern and case sensitivity they were
+  /// compiled from.
+  static ref REGEXES: Mutex<HashMap<i32, (String, bool, Option<Regex>)>> = Mutex::default();
+}
+
+/// Drop the compiled trigger of an auto reply that was removed.
+pub fn forget_trigger(id: i32) {
+  REGEXES.lock().remove(&id);
+}
 
 #[derive(Default)]
 pub struct AutoReplyListener {
-  last_sends: Mutex<HashMap<(UserId, i32), i64>>
+  /// Held while checking and starting cooldowns, so replies set off at the same time can't both
+  /// get past the same cooldown. Also holds the once-per-user replies being sent, so they can't be
+  /// sent twice before they're recorded.
+  cooldowns: Mutex<HashSet<(i32, UserId)>>,
 }
 
 enum UserIdOrMember {
   UserId(UserId),
-  Member(Member)
+  Member(Member),
+  Left(User, Option<Member>)
 }
 
 impl EventHandler for AutoReplyListener {
@@ -43,7 +62,21 @@ impl EventHandler for AutoReplyListener {
           .load(c)
       }).chain_err(|| "could not load auto_replies")?;
       let user = UserIdOrMember::Member(member.clone());
-      self.receive(&ctx, replies, user, guild)
+      self.receive(&ctx, replies, user, guild, None, None)
+    } |e| warn!("{}", e)
+  }
+
+  result_wrap! {
+    fn guild_member_removal(&self, ctx: Context, guild: GuildId, user: User, member: Option<Member>) -> Result<()> {
+      let replies: Vec<AutoReply> = crate::bot::with_connection(|c| {
+        use crate::database::schema::auto_replies::dsl;
+        dsl::auto_replies
+          .filter(dsl::server_id.eq(guild.to_u64())
+            .and(dsl::on_leave.eq(true)))
+          .load(c)
+      }).chain_err(|| "could not load auto_replies")?;
+      let user = UserIdOrMember::Left(user.clone(), member.clone());
+      self.receive(&ctx, replies, user, guild, None, None)
     } |e| warn!("{}", e)
   }
 
@@ -56,7 +89,8 @@ impl EventHandler for AutoReplyListener {
         use crate::database::schema::auto_replies::dsl;
         dsl::auto_replies
           .filter(dsl::channel_id.eq(m.channel_id.to_u64())
-            .and(dsl::on_join.eq(false)))
+            .and(dsl::on_join.eq(false))
+            .and(dsl::on_leave.eq(false)))
           .load(c)
       }).chain_err(|| "could not load auto_replies")?;
       let user = UserIdOrMember::UserId(m.author.id);
@@ -65,43 +99,219 @@ impl EventHandler for AutoReplyListener {
         Ok(_) => bail!("wrong type of channel for auto reply"),
         Err(e) => bail!("could not get channel for auto reply: {}", e)
       };
-      self.receive(&ctx, replies, user, guild)
+      self.receive(&ctx, replies, user, guild, Some(m.channel_id), Some(&m.content))
     } |e| warn!("{}", e)
   }
 }
 
 impl AutoReplyListener {
-  fn receive(&self, ctx: &Context, replies: Vec<AutoReply>, user: UserIdOrMember, guild: GuildId) -> Result<()> {
+  /// Send the replies that apply. `source` is the channel the replies were set off in, if any.
+  fn receive(&self, ctx: &Context, replies: Vec<AutoReply>, user: UserIdOrMember, guild: GuildId, source: Option<ChannelId>, content: Option<&str>) -> Result<()> {
+    let replies: Vec<AutoReply> = replies.into_iter()
+      .filter(|r| content.map(|c| self.triggered(r, c)).unwrap_or(true))
+      .collect();
+    if replies.is_empty() {
+      return Ok(());
+    }
     let live_server = match guild.to_guild_cached(&ctx) {
       Some(g) => g.read().clone(),
       None => bail!("could not find guild")
     };
-    let member = match user {
-      UserIdOrMember::Member(m) => m,
+    let (user, member) = match user {
+      UserIdOrMember::Member(m) => (m.user.read().clone(), Some(m)),
       UserIdOrMember::UserId(u) => match live_server.members.iter().find(|&(id, _)| *id == u) {
-        Some((_, m)) => m.clone(),
+        Some((_, m)) => (m.user.read().clone(), Some(m.clone())),
         None => bail!("could not find member for auto reply")
-      }
+      },
+      UserIdOrMember::Left(u, m) => (u, m)
     };
-    let user_id = member.user.read().id;
+    let user_id = user.id;
     let roles: Vec<Role> = live_server.roles.values().cloned().collect();
-    let mut last_sends = self.last_sends.lock();
-    for reply in replies {
-      if let Some(ref filters_string) = reply.filters {
-        match Filter::all_filters(filters_string) {
-          Some(filters) => if !filters.iter().all(|f| f.matches(&member, &roles)) {
+    let once: Vec<i32> = replies.iter().filter(|r| r.once_per_user).map(|r| r.id).collect();
+    let mut to_send = Vec::new();
+    {
+      let mut sending = self.cooldowns.lock();
+      let replied: Vec<i32> = if once.is_empty() {
+        Vec::new()
+      } else {
+        crate::bot::with_connection(|c| {
+          use crate::database::schema::auto_reply_recipients::dsl;
+          dsl::auto_reply_recipients
+            .filter(dsl::user_id.eq(user_id.to_u64()).and(dsl::auto_reply_id.eq_any(&once)))
+            .select(dsl::auto_reply_id)
+            .load(c)
+        }).chain_err(|| "could not load auto reply recipients")?
+      };
+      let now = Utc::now().timestamp();
+      let ids: Vec<i32> = replies.iter().map(|r| r.id).collect();
+      let cooling_down: Vec<(i32, U64)> = crate::bot::with_connection(|c| {
+        use crate::database::schema::auto_reply_cooldowns::dsl;
+        dsl::auto_reply_cooldowns
+          .filter(dsl::auto_reply_id.eq_any(&ids).and(dsl::expires_at.gt(now)))
+          .select((dsl::auto_reply_id, dsl::scope_id))
+          .load(c)
+      }).chain_err(|| "could not load auto reply cooldowns")?;
+      for reply in replies {
+        if let Some(ref filters_string) = reply.filters {
+          // members who left without being cached can't be checked
+          let member = some_or!(member.as_ref(), continue);
+          match Filter::all_filters(filters_string) {
+            Some(filters) => if !filters.iter().all(|f| f.matches(member, &roles)) {
+              continue;
+            },
+            None => warn!("invalid filters: `{}`", filters_string)
+          }
+        }
+        if replied.contains(&reply.id) || sending.contains(&(reply.id, user_id)) {
+          continue;
+        }
+        let channel = ChannelId(*reply.channel_id);
+        let scope_id = match reply.cooldown_scope() {
+          CooldownScope::User => user_id.0,
+          CooldownScope::Channel => source.unwrap_or(channel).0,
+          CooldownScope::Global => 0,
+        };
+        if cooling_down.iter().any(|&(id, scope)| id == reply.id && *scope == scope_id) {
+          continue;
+        }
+        // start cooldowns before sending, so the lock doesn't have to be held while sending
+        if reply.delay > 0 {
+          let cooldown = NewAutoReplyCooldown {
+            auto_reply_id: reply.id,
+            scope_id: scope_id.into(),
+            expires_at: now + i64::from(reply.delay),
+          };
+          let res = crate::bot::with_connection(|c| {
+            use crate::database::schema::auto_reply_cooldowns::dsl;
+            diesel::insert_into(dsl::auto_reply_cooldowns)
+              .values(&cooldown)
+              .on_conflict((dsl::auto_reply_id, dsl::scope_id))
+              .do_update()
+              .set(dsl::expires_at.eq(cooldown.expires_at))
+              .execute(c)
+          });
+          if let Err(e) = res {
+            warn!("could not store cooldown of auto reply {}: {}", reply.id, e);
             continue;
-          },
-          None => warn!("invalid filters: `{}`", filters_string)
+          }
+        }
+        if reply.once_per_user {
+          sending.insert((reply.id, user_id));
         }
+        to_send.push(reply);
       }
-      let last_send = last_sends.entry((user_id, reply.id)).or_insert(0);
-      if *last_send + i64::from(reply.delay) >= Utc::now().timestamp() {
+    }
+
+    for reply in to_send {
+      let channel = ChannelId(*reply.channel_id);
+      let values = match member {
+        Some(ref m) => Values::new().member(m),
+        None => Values::new().user(&user).tag(guild, user_id),
+      };
+      let values = values.guild(&live_server).channel(channel);
+      let delivery = reply.delivery();
+      let mut sent = false;
+      if delivery.sends_to_channel() {
+        match channel.send_message(&ctx, |m| AutoReplyListener::create_message(m, &reply, &values)) {
+          Ok(_) => sent = true,
+          Err(e) => warn!("could not send auto reply {}: {}", reply.id, e),
+        }
+      }
+      if delivery.sends_dm() {
+        match user.direct_message(&ctx, |m| AutoReplyListener::create_message(m, &reply, &values)) {
+          Ok(_) => sent = true,
+          Err(e) => warn!("could not send auto reply {} to {}: {}", reply.id, user_id, e),
+        }
+      }
+      if !reply.once_per_user {
         continue;
       }
-      ChannelId(*reply.channel_id).send_message(&ctx, |c| c.embed(|e| e.description(&reply.message.replace("{mention}", &member.mention())))).ok();
-      *last_send = Utc::now().timestamp();
+      // only count the reply as given once it reached the user, so a failed send can be retried
+      if sent {
+        let recipient = NewAutoReplyRecipient {
+          auto_reply_id: reply.id,
+          user_id: user_id.into(),
+        };
+        let res = crate::bot::with_connection(|c| {
+          use crate::database::schema::auto_reply_recipients;
+          diesel::insert_into(auto_reply_recipients::table)
+            .values(&recipient)
+            .on_conflict_do_nothing()
+            .execute(c)
+        });
+        if let Err(e) = res {
+          warn!("could not store recipient of auto reply {}: {}", reply.id, e);
+        }
+      }
+      self.cooldowns.lock().remove(&(reply.id, user_id));
     }
     Ok(())
   }
+
+  fn create_message<'a, 'b>(m: &'b mut CreateMessage<'a>, reply: &AutoReply, values: &Values) -> &'b mut CreateMessage<'a> {
+    let message = template::render(&reply.message, values);
+    if reply.plain {
+      return m.content(message);
+    }
+    m.embed(|e| {
+      e.description(message);
+      if let Some(ref title) = reply.embed_title {
+        e.title(template::render(title, values));
+      }
+      if let Some(color) = reply.embed_color {
+        e.colour(color as u32);
+      }
+      if let Some(ref image) = reply.embed_image {
+        e.image(image);
+      }
+      e
+    })
+  }
+
+  /// Check if a message sets off an auto reply's trigger. Auto replies without triggers reply to
+  /// every message.
+  fn triggered(&self, reply: &AutoReply, content: &str) -> bool {
+    let trigger = match reply.trigger {
+      Some(ref t) => t,
+      None => return true,
+    };
+    let pattern = match reply.trigger_kind() {
+      Some(TriggerKind::Exact) => return if reply.case_sensitive {
+        content.trim() == trigger
+      } else {
+        content.trim().to_lowercase() == trigger.to_lowercase()
+      },
+      Some(TriggerKind::Contains) => format!(r"(?:^|\W){}(?:\W|$)", regex::escape(trigger)),
+      Some(TriggerKind::Regex) => trigger.clone(),
+      None => {
+        warn!("auto reply {} has an invalid trigger type", reply.id);
+        return false;
+      },
+    };
+    let mut regexes = REGEXES.lock();
+    // rebuild the regex if the trigger changed since it was compiled
+    let stale = regexes.get(&reply.id)
+      .map(|(p, case_sensitive, _)| *p != pattern || *case_sensitive != reply.case_sensitive)
+      .unwrap_or(true);
+    if stale {
+      let regex = match build_regex(&pattern, reply.case_sensitive) {
+        Ok(r) => Some(r),
+        Err(e) => {
+          warn!("auto reply {} has an invalid regex: {}", reply.id, e);
+          None
+        },
+      };
+      regexes.insert(reply.id, (pattern, reply.case_sensitive, regex));
+    }
+    regexes[&reply.id].2.as_ref().map(|r| r.is_match(content)).unwrap_or(false)
+  }
+}
+
+/// Build a regex for an auto reply trigger, with limits so triggers can't slow the bot down.
+pub fn build_regex(pattern: &str, case_sensitive: bool) -> std::result::Result<Regex, regex::Error> {
+  RegexBuilder::new(pattern)
+    .case_insensitive(!case_sensitive)
+    .size_limit(1 << 16)
+    .dfa_size_limit(1 << 16)
+    .build()
 }
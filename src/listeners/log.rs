@@ -1,66 +1,295 @@
-use crate::database::models::{ToU64, LogChannel};
+use crate::database::models::{ToU64, LogChannel, LogRoute, LogIgnore, LogCategory, IgnoreKind, LogSettings, LoggedAttachment, LoggedMessage, NewLoggedMessage};
 
-use chrono::{Utc, Duration};
+use chrono::{TimeZone, Utc};
 
 use diesel::prelude::*;
 
 use serenity::{
+  builder::CreateEmbed,
   client::{Context, EventHandler},
   model::{
-    channel::Message,
+    channel::{Channel, ChannelType, GuildChannel, Message},
     event::MessageUpdateEvent,
-    guild::Member,
-    id::{GuildId, ChannelId, UserId, MessageId},
+    guild::{Member, Role},
+    id::{GuildId, ChannelId, UserId, MessageId, RoleId},
     user::User,
+    voice::VoiceState,
   },
-  prelude::{Mutex, Mentionable},
+  prelude::{Mentionable, Mutex, RwLock},
 };
 
 use std::{
   collections::HashMap,
-  sync::atomic::{Ordering, AtomicUsize},
+  sync::Arc,
 };
 
-macro_rules! update_message {
-  ($message:expr, $update:expr, $($field:ident),+ $(,)?) => {{
-    $(
-      if let Some(f) = $update.$field {
-        $message.$field = f;
-      }
-    )+
-  }}
+/// Logs joins, leaves, edits, deletions, member changes, bans, channel and role changes and voice
+/// activity to a guild's log channels.
+///
+/// Each category of events goes to its own channel if the guild has routed it, or to the guild's
+/// log channel otherwise. Events in ignored channels or about members with ignored roles are skipped.
+///
+/// Messages that would be logged are kept in the database, so edits and deletions can show the
+/// original content, even across restarts. `MessageLogTask` prunes them.
+#[derive(Default)]
+pub struct Log;
+
+lazy_static! {
+  /// Each guild's log config, loaded the first time it's needed.
+  static ref CONFIGS: Mutex<HashMap<GuildId, Arc<LogConfig>>> = Mutex::default();
 }
 
+/// Forget a guild's log config, so it's loaded again the next time it's needed. Call this after
+/// changing a guild's log channels, routes, ignores or settings.
+pub fn invalidate_config(guild: GuildId) {
+  CONFIGS.lock().remove(&guild);
+}
+
+/// A guild's log channel, routes, ignores and settings.
 #[derive(Default)]
-pub struct Log {
-  messages: Mutex<HashMap<UserId, HashMap<GuildId, Vec<Message>>>>,
-  count: AtomicUsize,
+struct LogConfig {
+  default: Option<ChannelId>,
+  routes: Vec<LogRoute>,
+  ignores: Vec<LogIgnore>,
+  settings: Option<LogSettings>,
+}
+
+impl LogConfig {
+  /// The channel a category of events is logged to, if any.
+  fn channel(&self, category: LogCategory) -> Option<ChannelId> {
+    match self.routes.iter().find(|r| r.category() == Some(category)) {
+      Some(route) => route.channel(),
+      None => self.default,
+    }
+  }
+
+  fn ignores_target(&self, kind: IgnoreKind, id: u64) -> bool {
+    self.ignores.iter().any(|i| i.kind() == Some(kind) && *i.target_id == id)
+  }
+
+  fn ignores_channel(&self, channel: ChannelId) -> bool {
+    self.ignores_target(IgnoreKind::Channel, channel.0)
+  }
+
+  fn ignores_role(&self, role: RoleId) -> bool {
+    self.ignores_target(IgnoreKind::Role, role.0)
+  }
+
+  fn ignores_member(&self, member: &Member) -> bool {
+    member.roles.iter().any(|&r| self.ignores_role(r))
+  }
 }
 
 impl Log {
-  fn get_log_channel<G: Into<GuildId>>(&self, guild: G) -> Option<ChannelId> {
-    let guild = guild.into().to_u64();
-    let log_channel: Option<LogChannel> = crate::bot::with_connection(|c| {
-      use crate::database::schema::log_channels::dsl;
-      dsl::log_channels.filter(dsl::server_id.eq(guild)).first(c)
-    }).ok();
-    log_channel.map(|x| ChannelId(*x.channel_id))
+  fn config<G: Into<GuildId>>(&self, guild: G) -> Arc<LogConfig> {
+    let guild_id = guild.into();
+    if let Some(config) = CONFIGS.lock().get(&guild_id) {
+      return Arc::clone(config);
+    }
+    let guild = guild_id.to_u64();
+    let config = crate::bot::with_connection(|c| {
+      use crate::database::schema::{log_channels, log_ignores, log_routes, log_settings};
+      let default: Option<LogChannel> = log_channels::table
+        .filter(log_channels::server_id.eq(guild))
+        .first(c)
+        .optional()?;
+      let routes = log_routes::table
+        .filter(log_routes::server_id.eq(guild))
+        .load(c)?;
+      let ignores = log_ignores::table
+        .filter(log_ignores::server_id.eq(guild))
+        .load(c)?;
+      let settings = log_settings::table
+        .filter(log_settings::server_id.eq(guild))
+        .first(c)
+        .optional()?;
+      Ok(LogConfig {
+        default: default.map(|x| ChannelId(*x.channel_id)),
+        routes,
+        ignores,
+        settings,
+      })
+    });
+    match config {
+      Ok(c) => {
+        let config = Arc::new(c);
+        CONFIGS.lock().insert(guild_id, Arc::clone(&config));
+        config
+      },
+      // don't keep the empty config, so the next event tries again
+      Err(e) => {
+        warn!("could not load log config for {}: {}", *guild, e);
+        Arc::new(LogConfig::default())
+      },
+    }
+  }
+
+  /// Send an embed to the channel a category of events is logged to, if any.
+  fn send<F>(&self, ctx: &Context, config: &LogConfig, category: LogCategory, f: F)
+    where F: FnOnce(&mut CreateEmbed) -> &mut CreateEmbed,
+  {
+    let channel_id = some_or!(config.channel(category), return);
+    if let Err(e) = channel_id.send_message(ctx, |m| m.embed(|e| f(e).timestamp(&Utc::now()))) {
+      warn!("could not send log message to {}: {}", channel_id, e);
+    }
+  }
+
+  /// Fill in an embed about something a user did or had done to them.
+  fn user_embed<'a>(embed: &'a mut CreateEmbed, user: &User, action: &str) -> &'a mut CreateEmbed {
+    embed
+      .author(|a| a
+        .name(&user.tag())
+        .icon_url(&user.face()))
+      .field("Mention", user.mention(), true)
+      .field("Action", action, true)
+      .footer(|f| f.text(user.id))
+  }
+
+  fn describe_channel(channel: &GuildChannel) -> String {
+    let kind = match channel.kind {
+      ChannelType::Text => "text channel",
+      ChannelType::Voice => "voice channel",
+      ChannelType::Category => "category",
+      ChannelType::News => "news channel",
+      ChannelType::Store => "store channel",
+      _ => "channel",
+    };
+    match channel.kind {
+      ChannelType::Voice | ChannelType::Category => format!("{} ({})", channel.name, kind),
+      _ => format!("{} – #{} ({})", channel.id.mention(), channel.name, kind),
+    }
+  }
+
+  /// List the changes between two versions of a channel, skipping position changes, which happen to
+  /// every channel below one that moved.
+  fn channel_changes(old: &GuildChannel, new: &GuildChannel) -> Vec<(&'static str, String)> {
+    let mut changes = Vec::new();
+    if old.name != new.name {
+      changes.push(("Name", format!("{} → {}", old.name, new.name)));
+    }
+    if old.topic != new.topic {
+      changes.push(("Topic", format!(
+        "{} → {}",
+        old.topic.as_ref().filter(|t| !t.is_empty()).map(String::as_str).unwrap_or("*none*"),
+        new.topic.as_ref().filter(|t| !t.is_empty()).map(String::as_str).unwrap_or("*none*"),
+      )));
+    }
+    if old.nsfw != new.nsfw {
+      changes.push(("NSFW", format!("{} → {}", old.nsfw, new.nsfw)));
+    }
+    if old.rate_limit_per_user != new.rate_limit_per_user {
+      let slowmode = |s: Option<u64>| match s {
+        Some(s) if s > 0 => crate::util::format_duration(s as i64),
+        _ => "off".into(),
+      };
+      changes.push(("Slowmode", format!("{} → {}", slowmode(old.rate_limit_per_user), slowmode(new.rate_limit_per_user))));
+    }
+    if old.category_id != new.category_id {
+      let category = |c: Option<ChannelId>| c.map(|c| c.mention()).unwrap_or_else(|| "*none*".into());
+      changes.push(("Category", format!("{} → {}", category(old.category_id), category(new.category_id))));
+    }
+    if old.bitrate != new.bitrate {
+      changes.push(("Bitrate", format!("{} → {}", old.bitrate.unwrap_or_default(), new.bitrate.unwrap_or_default())));
+    }
+    if old.user_limit != new.user_limit {
+      let limit = |l: Option<u64>| match l {
+        Some(l) if l > 0 => l.to_string(),
+        _ => "none".into(),
+      };
+      changes.push(("User limit", format!("{} → {}", limit(old.user_limit), limit(new.user_limit))));
+    }
+    if old.permission_overwrites.len() != new.permission_overwrites.len()
+      || old.permission_overwrites.iter().zip(&new.permission_overwrites)
+        .any(|(a, b)| a.allow != b.allow || a.deny != b.deny || a.kind != b.kind) {
+      changes.push(("Permissions", "Permission overwrites changed".into()));
+    }
+    changes
   }
 
-  fn prune_messages(&self) {
-    let now = Utc::now();
-    let one_day = Duration::days(1);
-    self.messages
-      .lock()
-      .values_mut()
-      .flat_map(HashMap::values_mut)
-      .for_each(|x| x.retain(|m| m.timestamp.signed_duration_since(now) < one_day));
+  /// List the changes between two versions of a role, skipping position changes.
+  fn role_changes(old: &Role, new: &Role) -> Vec<(&'static str, String)> {
+    let mut changes = Vec::new();
+    if old.name != new.name {
+      changes.push(("Name", format!("{} → {}", old.name, new.name)));
+    }
+    if old.colour.0 != new.colour.0 {
+      changes.push(("Colour", format!("#{:06X} → #{:06X}", old.colour.0, new.colour.0)));
+    }
+    if old.hoist != new.hoist {
+      changes.push(("Shown separately", format!("{} → {}", old.hoist, new.hoist)));
+    }
+    if old.mentionable != new.mentionable {
+      changes.push(("Mentionable", format!("{} → {}", old.mentionable, new.mentionable)));
+    }
+    if old.permissions != new.permissions {
+      let added = new.permissions - old.permissions;
+      let removed = old.permissions - new.permissions;
+      let mut lines = Vec::new();
+      if !added.is_empty() {
+        lines.push(format!("Added: {:?}", added));
+      }
+      if !removed.is_empty() {
+        lines.push(format!("Removed: {:?}", removed));
+      }
+      changes.push(("Permissions", lines.join("\n")));
+    }
+    changes
+  }
+
+  fn roles_list(roles: &[&RoleId]) -> String {
+    roles.iter().map(|r| r.mention()).collect::<Vec<_>>().join(", ")
+  }
+
+  /// Write out deleted messages as plain text, oldest first.
+  fn transcript(channel: &GuildChannel, messages: &[LoggedMessage], names: &HashMap<u64, String>) -> String {
+    let mut lines = vec![format!(
+      "{} deleted messages in #{} ({}), logged {}",
+      messages.len(),
+      channel.name,
+      channel.id,
+      Utc::now().format("%Y-%m-%d %H:%M:%S UTC"),
+    )];
+    for message in messages {
+      let author = names.get(&*message.author_id).map(String::as_str).unwrap_or("unknown user");
+      lines.push(String::new());
+      lines.push(format!(
+        "[{}] {} ({}):",
+        Utc.timestamp(message.created_at, 0).format("%Y-%m-%d %H:%M:%S UTC"),
+        author,
+        *message.author_id,
+      ));
+      if !message.content.is_empty() {
+        lines.push(message.content.clone());
+      }
+      for attachment in message.attachments() {
+        lines.push(format!("Attachment: {}", attachment));
+      }
+    }
+    lines.join("\n")
+  }
+
+  fn logged_message(&self, message: MessageId) -> Option<LoggedMessage> {
+    let logged = crate::bot::with_connection(|c| {
+      use crate::database::schema::logged_messages::dsl;
+      dsl::logged_messages.find(message.to_u64()).first(c).optional()
+    });
+    match logged {
+      Ok(l) => l,
+      Err(e) => {
+        warn!("could not load logged message {}: {}", message, e);
+        None
+      },
+    }
   }
 }
 
 impl EventHandler for Log {
   fn guild_member_removal(&self, ctx: Context, guild: GuildId, user: User, member: Option<Member>) {
-    let channel_id = some_or!(self.get_log_channel(guild), return);
+    let config = self.config(guild);
+    if member.as_ref().map(|m| config.ignores_member(m)).unwrap_or(false) {
+      return;
+    }
+    let channel_id = some_or!(config.channel(LogCategory::Member), return);
     let mention = member.as_ref().map(Mentionable::mention).unwrap_or_else(|| user.mention());
     channel_id.send_message(&ctx, |m| m.embed(|mut embed| {
       embed = embed
@@ -80,7 +309,11 @@ impl EventHandler for Log {
   }
 
   fn guild_member_addition(&self, ctx: Context, guild: GuildId, member: Member) {
-    let channel_id = some_or!(self.get_log_channel(guild), return);
+    let config = self.config(guild);
+    if config.ignores_member(&member) {
+      return;
+    }
+    let channel_id = some_or!(config.channel(LogCategory::Member), return);
     channel_id.send_message(&ctx, |m| m.embed(|mut embed| {
       embed = embed
         .author(|a| a
@@ -119,7 +352,11 @@ impl EventHandler for Log {
     };
     let reader = guild_channel.read();
 
-    let channel_id = some_or!(self.get_log_channel(reader.guild_id), return);
+    let config = self.config(reader.guild_id);
+    if config.ignores_channel(update.channel_id) {
+      return;
+    }
+    let channel_id = some_or!(config.channel(LogCategory::Message), return);
 
     let guild = match reader.guild_id.to_guild_cached(&ctx) {
       Some(g) => g,
@@ -131,28 +368,29 @@ impl EventHandler for Log {
       Some(m) => m,
       None => return,
     };
+    if config.ignores_member(&member) {
+      return;
+    }
 
-    let mut messages = self.messages.lock();
-    let message = messages
-      .get_mut(&author.id)
-      .and_then(|x| x.get_mut(&reader.guild_id))
-      .and_then(|x| x.iter_mut().find(|m| m.id == update.id));
-    let message = match message {
-      Some(m) => m,
-      None => return,
-    };
+    let message = some_or!(self.logged_message(update.id), return);
 
     let original_content = message.content.clone();
     let channel_mention = update.channel_id.mention();
     let message_id = update.id;
 
-    update_message!(
-      message,
-      update,
-      kind, content, tts, pinned, timestamp, author, mention_everyone, mentions, mention_roles,
-      attachments,
-    );
-    message.edited_timestamp = update.edited_timestamp;
+    let attachments = match update.attachments {
+      Some(ref a) => a.iter().map(|a| a.url.as_str()).collect::<Vec<_>>().join("\n"),
+      None => message.attachments.clone(),
+    };
+    let res = crate::bot::with_connection(|c| {
+      use crate::database::schema::logged_messages::dsl;
+      diesel::update(dsl::logged_messages.find(message_id.to_u64()))
+        .set((dsl::content.eq(&new_content), dsl::attachments.eq(&attachments)))
+        .execute(c)
+    });
+    if let Err(e) = res {
+      warn!("could not update logged message {}: {}", message_id, e);
+    }
 
     channel_id.send_message(&ctx, |m| m.embed(|mut embed| {
       embed = embed
@@ -186,7 +424,11 @@ impl EventHandler for Log {
     };
     let reader = guild_channel.read();
 
-    let log_channel = some_or!(self.get_log_channel(reader.guild_id), return);
+    let config = self.config(reader.guild_id);
+    if config.ignores_channel(channel_id) {
+      return;
+    }
+    let log_channel = some_or!(config.channel(LogCategory::Message), return);
 
     let guild = match reader.guild_id.to_guild_cached(&ctx) {
       Some(g) => g,
@@ -195,49 +437,99 @@ impl EventHandler for Log {
 
     let guild_reader = guild.read();
 
-    let mut messages = self.messages.lock();
-    let message = messages
-      .values_mut()
-      .filter_map(|x| x.get_mut(&reader.guild_id))
-      .next()
-      .and_then(|x| x.iter_mut().find(|m| m.id == message_id));
-    let message = match message {
-      Some(m) => m.clone(),
-      None => return,
+    let message = some_or!(self.logged_message(message_id), return);
+    let res = crate::bot::with_connection(|c| {
+      use crate::database::schema::logged_messages::dsl;
+      diesel::delete(dsl::logged_messages.find(message_id.to_u64())).execute(c)
+    });
+    if let Err(e) = res {
+      warn!("could not remove logged message {}: {}", message_id, e);
+    }
+
+    // kept attachments have to be removed whether or not the deletion is logged
+    let saved = match crate::attachments::saved(&[message_id]) {
+      Ok(s) => s,
+      Err(e) => {
+        warn!("could not load stored attachments of message {}: {}", message_id, e);
+        Vec::new()
+      },
+    };
+    let remove_saved = || {
+      if let Err(e) = crate::attachments::remove(&saved) {
+        warn!("could not remove stored attachments of message {}: {}", message_id, e);
+      }
     };
 
-    let deletee = some_or!(guild_reader.members.get(&message.author.id).cloned().or_else(|| guild_reader.member(&ctx, message.author.id).ok()), return);
+    let author = UserId(*message.author_id);
+    let deletee = guild_reader.members.get(&author).cloned().or_else(|| guild_reader.member(&ctx, author).ok());
+    if deletee.as_ref().map(|d| config.ignores_member(d)).unwrap_or(false) {
+      remove_saved();
+      return;
+    }
+    // authors who left can still be named
+    let user = deletee.as_ref().map(|d| d.user.read().clone()).or_else(|| author.to_user(&ctx).ok());
 
-    let original_content = message.content;
+    let original_content = if message.content.is_empty() {
+      "*no text*".to_string()
+    } else {
+      message.content.clone()
+    };
     let channel_mention = channel_id.mention();
 
-    log_channel.send_message(&ctx, |m| m.embed(|mut embed| {
-      embed = embed
-        .author(|a| a
-          .name(&deletee.user.read().tag())
-          .icon_url(&deletee.user.read().face()));
+    // upload any attachments kept for the message, since Discord removes them with it
+    let (files, missing) = crate::attachments::read(&saved, crate::attachments::MAX_UPLOADS);
+    let image = files.iter()
+      .map(|(_, name)| name)
+      .find(|name| {
+        let name = name.to_lowercase();
+        [".png", ".jpg", ".jpeg", ".gif", ".webp"].iter().any(|ext| name.ends_with(ext))
+      })
+      .map(|name| format!("attachment://{}", name));
+    let attachments: Vec<String> = if files.is_empty() {
+      // nothing was kept, so the original links are all there is
+      message.attachments().iter().map(ToString::to_string).collect()
+    } else if missing > 0 {
+      let mut names: Vec<String> = files.iter().map(|(_, name)| name.clone()).collect();
+      names.push(format!("{} more could not be uploaded", missing));
+      names
+    } else {
+      files.iter().map(|(_, name)| name.clone()).collect()
+    };
+    let uploads: Vec<(&[u8], &str)> = files.iter().map(|(data, name)| (data.as_slice(), name.as_str())).collect();
+
+    let res = log_channel.send_files(&ctx, uploads, |m| m.embed(|mut embed| {
+      if let Some(ref user) = user {
+        embed = embed
+          .author(|a| a
+            .name(&user.tag())
+            .icon_url(&user.face()));
+      }
       embed = embed
-        .field("Mention", deletee.mention(), true)
+        .field("Mention", author.mention(), true)
         .field("Action", "Had message deleted", true)
         .field("Channel", channel_mention, true)
         .field("Content", original_content, false)
         .timestamp(&Utc::now())
         .footer(|f| f.text(message_id));
+      if !attachments.is_empty() {
+        embed = embed.field("Attachments", attachments.join("\n"), false);
+      }
+      if let Some(ref image) = image {
+        embed = embed.image(image);
+      }
       embed
-    })).ok();
-  }
-
-  fn message(&self, ctx: Context, message: Message) {
-    if self.count.load(Ordering::SeqCst) == 100 {
-      self.count.store(0, Ordering::SeqCst);
-
-      self.prune_messages();
+    }));
+    if let Err(e) = res {
+      warn!("could not log deletion of message {}: {}", message_id, e);
     }
+    remove_saved();
+  }
 
-    let channel = match message.channel_id.to_channel(&ctx) {
+  fn message_delete_bulk(&self, ctx: Context, channel_id: ChannelId, message_ids: Vec<MessageId>) {
+    let channel = match channel_id.to_channel(&ctx) {
       Ok(c) => c,
       Err(e) => {
-        warn!("could not download channel {} for message history: {}", message.channel_id, e);
+        warn!("could not download channel {} for message history: {}", channel_id, e);
         return;
       },
     };
@@ -246,17 +538,322 @@ impl EventHandler for Log {
       Some(g) => g,
       None => return,
     };
-
     let reader = guild_channel.read();
 
-    self.messages
-      .lock()
-      .entry(message.author.id)
-      .or_default()
-      .entry(reader.guild_id)
-      .or_default()
-      .push(message);
+    let config = self.config(reader.guild_id);
+    if config.ignores_channel(channel_id) {
+      return;
+    }
+    let log_channel = some_or!(config.channel(LogCategory::Message), return);
+
+    let ids: Vec<i64> = message_ids.iter().map(|id| id.0 as i64).collect();
+    let messages: Vec<LoggedMessage> = match crate::bot::with_connection(|c| {
+      use crate::database::schema::logged_messages::dsl;
+      c.transaction(|| {
+        let messages = dsl::logged_messages
+          .filter(dsl::message_id.eq_any(&ids))
+          .order_by((dsl::created_at, dsl::message_id))
+          .load(c)?;
+        diesel::delete(dsl::logged_messages.filter(dsl::message_id.eq_any(&ids))).execute(c)?;
+        Ok(messages)
+      })
+    }) {
+      Ok(m) => m,
+      Err(e) => {
+        warn!("could not load logged messages for bulk delete in {}: {}", channel_id, e);
+        return;
+      },
+    };
+
+    // leave out messages from members with ignored roles, and find everyone else's names
+    let mut names: HashMap<u64, String> = HashMap::new();
+    let mut ignored: Vec<u64> = Vec::new();
+    if let Some(guild) = reader.guild_id.to_guild_cached(&ctx) {
+      let guild = guild.read();
+      for message in &messages {
+        let author = *message.author_id;
+        if names.contains_key(&author) || ignored.contains(&author) {
+          continue;
+        }
+        match guild.members.get(&UserId(author)) {
+          Some(m) if config.ignores_member(m) => ignored.push(author),
+          Some(m) => {
+            names.insert(author, m.user.read().tag());
+          },
+          None => {},
+        }
+      }
+    }
+    let messages: Vec<LoggedMessage> = messages.into_iter()
+      .filter(|m| !ignored.contains(&*m.author_id))
+      .collect();
+
+    // upload the attachments kept for the logged messages next to the transcript, and remove all
+    // of the kept ones, even for ignored authors
+    let saved = match crate::attachments::saved(&message_ids) {
+      Ok(s) => s,
+      Err(e) => {
+        warn!("could not load stored attachments for bulk delete in {}: {}", channel_id, e);
+        Vec::new()
+      },
+    };
+    let uploadable: Vec<LoggedAttachment> = saved.iter()
+      .filter(|a| messages.iter().any(|m| *m.message_id == *a.message_id))
+      .cloned()
+      .collect();
+    let (attachment_files, missing) = crate::attachments::read(&uploadable, crate::attachments::MAX_UPLOADS - 1);
+
+    let mut authors: Vec<(u64, usize)> = Vec::new();
+    for message in &messages {
+      match authors.iter_mut().find(|(a, _)| *a == *message.author_id) {
+        Some((_, count)) => *count += 1,
+        None => authors.push((*message.author_id, 1)),
+      }
+    }
+    authors.sort_by(|a, b| b.1.cmp(&a.1));
+    let mut author_list = authors.iter()
+      .map(|&(a, count)| format!("{} ({})", UserId(a).mention(), count))
+      .collect::<Vec<_>>()
+      .join(", ");
+    // embed fields can't be longer than this
+    if author_list.chars().count() > 1024 {
+      author_list = author_list.chars().take(1023).collect::<String>() + "…";
+    }
+    let unknown = message_ids.len() - messages.len();
+    let count = if unknown == 0 {
+      message_ids.len().to_string()
+    } else {
+      format!("{} ({} not in the log)", message_ids.len(), unknown)
+    };
+
+    let transcript = Log::transcript(&reader, &messages, &names);
+    let filename = format!("deleted-{}-{}.txt", reader.name, Utc::now().format("%Y%m%d-%H%M%S"));
+    let mut files = if messages.is_empty() {
+      Vec::new()
+    } else {
+      vec![(transcript.as_bytes(), filename.as_str())]
+    };
+    files.extend(attachment_files.iter().map(|(data, name)| (data.as_slice(), name.as_str())));
+    let res = log_channel.send_files(&ctx, files, |m| m.embed(|e| {
+      e
+        .title("Messages deleted in bulk")
+        .field("Action", "Bulk delete", true)
+        .field("Channel", channel_id.mention(), true)
+        .field("Messages", count, true)
+        .timestamp(&Utc::now())
+        .footer(|f| f.text(channel_id));
+      if !author_list.is_empty() {
+        e.field("Authors", author_list, false);
+      }
+      if missing > 0 {
+        e.field("Attachments", format!("{} kept attachment{} could not be uploaded", missing, if missing == 1 { "" } else { "s" }), false);
+      }
+      e
+    }));
+    if let Err(e) = res {
+      warn!("could not log bulk delete in {}: {}", channel_id, e);
+    }
+    if let Err(e) = crate::attachments::remove(&saved) {
+      warn!("could not remove stored attachments for bulk delete in {}: {}", channel_id, e);
+    }
+  }
+
+  fn message(&self, ctx: Context, message: Message) {
+    let guild = some_or!(message.guild_id, return);
+    let config = self.config(guild);
+    if config.channel(LogCategory::Message).is_none() || config.ignores_channel(message.channel_id) {
+      return;
+    }
+    let ignored_author = guild.to_guild_cached(&ctx)
+      .and_then(|g| g.read().members.get(&message.author.id).map(|m| config.ignores_member(m)))
+      .unwrap_or(false);
+    if ignored_author {
+      return;
+    }
+    let logged = NewLoggedMessage {
+      message_id: message.id.into(),
+      server_id: guild.into(),
+      channel_id: message.channel_id.into(),
+      author_id: message.author.id.into(),
+      content: message.content.clone(),
+      attachments: message.attachments.iter().map(|a| a.url.as_str()).collect::<Vec<_>>().join("\n"),
+      created_at: message.timestamp.timestamp(),
+    };
+    let res = crate::bot::with_connection(|c| {
+      use crate::database::schema::logged_messages;
+      diesel::insert_into(logged_messages::table)
+        .values(&logged)
+        .on_conflict_do_nothing()
+        .execute(c)
+    });
+    if let Err(e) = res {
+      warn!("could not store message {} for logging: {}", message.id, e);
+      return;
+    }
+    if let Err(e) = crate::attachments::save(config.settings.as_ref(), guild, &message) {
+      warn!("could not store attachments of message {}: {}", message.id, e);
+    }
+  }
+
+  fn guild_member_update(&self, ctx: Context, old: Option<Member>, new: Member) {
+    // without the old member, there's nothing to compare to
+    let old = some_or!(old, return);
+    let nick_changed = old.nick != new.nick;
+    let added: Vec<&RoleId> = new.roles.iter().filter(|r| !old.roles.contains(r)).collect();
+    let removed: Vec<&RoleId> = old.roles.iter().filter(|r| !new.roles.contains(r)).collect();
+    if !nick_changed && added.is_empty() && removed.is_empty() {
+      return;
+    }
+    let config = self.config(new.guild_id);
+    if config.ignores_member(&new) {
+      return;
+    }
+    let user = new.user.read().clone();
+    let action = if nick_changed { "Changed nickname" } else { "Had roles changed" };
+    self.send(&ctx, &config, LogCategory::Member, |e| {
+      Log::user_embed(e, &user, action);
+      if nick_changed {
+        e.field("Old nickname", old.nick.as_ref().map(String::as_str).unwrap_or("*none*"), true);
+        e.field("New nickname", new.nick.as_ref().map(String::as_str).unwrap_or("*none*"), true);
+      }
+      if !added.is_empty() {
+        e.field("Roles added", Log::roles_list(&added), false);
+      }
+      if !removed.is_empty() {
+        e.field("Roles removed", Log::roles_list(&removed), false);
+      }
+      e
+    });
+  }
+
+  fn guild_ban_addition(&self, ctx: Context, guild: GuildId, user: User) {
+    self.send(&ctx, &self.config(guild), LogCategory::Moderation, |e| Log::user_embed(e, &user, "Banned"));
+  }
 
-    self.count.fetch_add(1, Ordering::SeqCst);
+  fn guild_ban_removal(&self, ctx: Context, guild: GuildId, user: User) {
+    self.send(&ctx, &self.config(guild), LogCategory::Moderation, |e| Log::user_embed(e, &user, "Unbanned"));
+  }
+
+  fn channel_create(&self, ctx: Context, channel: Arc<RwLock<GuildChannel>>) {
+    let channel = channel.read();
+    let config = self.config(channel.guild_id);
+    if config.ignores_channel(channel.id) {
+      return;
+    }
+    self.send(&ctx, &config, LogCategory::Server, |e| e
+      .title("Channel created")
+      .description(Log::describe_channel(&channel))
+      .footer(|f| f.text(channel.id)));
+  }
+
+  fn channel_delete(&self, ctx: Context, channel: Arc<RwLock<GuildChannel>>) {
+    let channel = channel.read();
+    let config = self.config(channel.guild_id);
+    if config.ignores_channel(channel.id) {
+      return;
+    }
+    let description = match channel.kind {
+      ChannelType::Voice | ChannelType::Category => Log::describe_channel(&channel),
+      // the mention won't resolve anymore
+      _ => format!("#{}", channel.name),
+    };
+    self.send(&ctx, &config, LogCategory::Server, |e| e
+      .title("Channel deleted")
+      .description(description)
+      .footer(|f| f.text(channel.id)));
+  }
+
+  fn channel_update(&self, ctx: Context, old: Option<Channel>, new: Channel) {
+    let old = some_or!(old.and_then(Channel::guild), return);
+    let new = some_or!(new.guild(), return);
+    let (old, new) = (old.read(), new.read());
+    let changes = Log::channel_changes(&old, &new);
+    if changes.is_empty() {
+      return;
+    }
+    let config = self.config(new.guild_id);
+    if config.ignores_channel(new.id) {
+      return;
+    }
+    self.send(&ctx, &config, LogCategory::Server, |e| {
+      e
+        .title("Channel updated")
+        .description(Log::describe_channel(&new))
+        .footer(|f| f.text(new.id));
+      for (name, change) in changes {
+        e.field(name, change, false);
+      }
+      e
+    });
+  }
+
+  fn guild_role_create(&self, ctx: Context, guild: GuildId, role: Role) {
+    self.send(&ctx, &self.config(guild), LogCategory::Server, |e| e
+      .title("Role created")
+      .description(format!("{} – {}", role.id.mention(), role.name))
+      .footer(|f| f.text(role.id)));
+  }
+
+  fn guild_role_delete(&self, ctx: Context, guild: GuildId, role_id: RoleId, role: Option<Role>) {
+    let config = self.config(guild);
+    if config.ignores_role(role_id) {
+      return;
+    }
+    let name = role.map(|r| r.name).unwrap_or_else(|| "*unknown*".into());
+    self.send(&ctx, &config, LogCategory::Server, |e| e
+      .title("Role deleted")
+      .description(name)
+      .footer(|f| f.text(role_id)));
+  }
+
+  fn guild_role_update(&self, ctx: Context, guild: GuildId, old: Option<Role>, new: Role) {
+    let old = some_or!(old, return);
+    let changes = Log::role_changes(&old, &new);
+    if changes.is_empty() {
+      return;
+    }
+    let config = self.config(guild);
+    if config.ignores_role(new.id) {
+      return;
+    }
+    self.send(&ctx, &config, LogCategory::Server, |e| {
+      e
+        .title("Role updated")
+        .description(format!("{} – {}", new.id.mention(), new.name))
+        .footer(|f| f.text(new.id));
+      for (name, change) in changes {
+        e.field(name, change, false);
+      }
+      e
+    });
+  }
+
+  fn voice_state_update(&self, ctx: Context, guild: Option<GuildId>, old: Option<VoiceState>, new: VoiceState) {
+    let guild = some_or!(guild, return);
+    let old_channel = old.and_then(|o| o.channel_id);
+    let (action, channels) = match (old_channel, new.channel_id) {
+      (None, Some(joined)) => ("Joined voice", joined.mention()),
+      (Some(left), None) => ("Left voice", left.mention()),
+      (Some(from), Some(to)) if from != to => ("Moved voice channels", format!("{} → {}", from.mention(), to.mention())),
+      // mutes, deafens and the like
+      _ => return,
+    };
+    let config = self.config(guild);
+    let ignored = old_channel.map(|c| config.ignores_channel(c)).unwrap_or(false)
+      || new.channel_id.map(|c| config.ignores_channel(c)).unwrap_or(false)
+      || guild.to_guild_cached(&ctx)
+        .and_then(|g| g.read().members.get(&new.user_id).map(|m| config.ignores_member(m)))
+        .unwrap_or(false);
+    if ignored {
+      return;
+    }
+    let user = match new.user_id.to_user(&ctx) {
+      Ok(u) => u,
+      Err(e) => {
+        warn!("could not get user {} for voice log: {}", new.user_id, e);
+        return;
+      },
+    };
+    self.send(&ctx, &config, LogCategory::Voice, |e| Log::user_embed(e, &user, action).field("Channel", channels, true));
   }
 }
@@ -24,9 +24,10 @@ macro_rules! result_wrap {
 pub mod auto_reply;
 pub mod guilds_ext;
 pub mod log;
-pub mod poll_tagger;
+pub mod polls;
 pub mod random_presence;
 pub mod reaction_authorize;
+pub mod temporary_overwrites;
 pub mod temporary_roles;
 pub mod timeouts;
 
@@ -34,9 +35,10 @@ pub use self::{
   auto_reply::AutoReplyListener,
   guilds_ext::GuildsExt,
   log::Log,
-  poll_tagger::PollTagger,
+  polls::PollListener,
   random_presence::RandomPresenceListener,
   reaction_authorize::ReactionAuthorize,
+  temporary_overwrites::TemporaryOverwritesListener,
   temporary_roles::TemporaryRolesListener,
   timeouts::Timeouts,
 };
@@ -0,0 +1,107 @@
+use crate::{
+  commands::polling::{find_poll, poll_options, has_reacted, ranked},
+  database::models::{Poll, PollOption},
+  error::*,
+  util::parse_emoji,
+};
+
+use serenity::{
+  client::{Context, EventHandler},
+  model::channel::{Message, Reaction},
+};
+
+/// Keeps reactions on closed polls from looking like votes, enforces polls' vote limits and collects
+/// ranked poll ballots.
+pub struct PollListener;
+
+impl EventHandler for PollListener {
+  result_wrap! {
+    fn reaction_add(&self, ctx: Context, reaction: Reaction) -> Result<()> {
+      if reaction.user_id == ctx.cache.read().user.id {
+        return Ok(());
+      }
+      let poll = match find_poll(reaction.message_id)? {
+        Some(p) => p,
+        None => return Ok(()),
+      };
+      if poll.closed {
+        reaction.delete(&ctx).chain_err(|| "could not remove reaction from closed poll")?;
+        return Ok(());
+      }
+      let emoji = reaction.emoji.to_string();
+      if poll.ranked {
+        if ranked::is_ballot_emoji(&emoji) {
+          // take the reaction back so ballots stay private and can be asked for again
+          reaction.delete(&ctx).chain_err(|| "could not remove ballot reaction")?;
+          ranked::request_ballot(&ctx, &poll, reaction.user_id)?;
+        }
+        return Ok(());
+      }
+      let limit = some_or!(poll.vote_limit(), return Ok(()));
+      let options = poll_options(&poll)?;
+      if !options.iter().any(|o| o.emoji == emoji) {
+        return Ok(());
+      }
+      let others = PollListener::other_votes(&ctx, &reaction, &options)?;
+      if poll.single {
+        for other in others {
+          reaction.channel_id.delete_reaction(&ctx, reaction.message_id, Some(reaction.user_id), parse_emoji(&other.emoji))
+            .chain_err(|| "could not remove other poll vote")?;
+        }
+      } else if others.len() >= limit {
+        reaction.delete(&ctx).chain_err(|| "could not remove poll vote over the limit")?;
+        PollListener::explain_limit(&ctx, &reaction, &poll, limit);
+      }
+      Ok(())
+    } |e| warn!("{}", e)
+  }
+
+  result_wrap! {
+    fn message(&self, ctx: Context, msg: Message) -> Result<()> {
+      if msg.author.bot || msg.guild_id.is_some() {
+        return Ok(());
+      }
+      ranked::record_ballot(&ctx, &msg).map_err(Into::into)
+    } |e| warn!("{}", e)
+  }
+}
+
+impl PollListener {
+  /// Get the other options the member has voted for.
+  fn other_votes<'a>(ctx: &Context, reaction: &Reaction, options: &'a [PollOption]) -> Result<Vec<&'a PollOption>> {
+    let emoji = reaction.emoji.to_string();
+    let message = reaction.message(&ctx).chain_err(|| "could not get poll message")?;
+    let mut others = Vec::new();
+    for option in options.iter().filter(|o| o.emoji != emoji) {
+      // skip asking about options nobody but the bot has reacted with
+      let voted = message.reactions.iter()
+        .any(|r| r.reaction_type.to_string() == option.emoji && r.count > if r.me { 1 } else { 0 });
+      if voted && has_reacted(&ctx, reaction.channel_id, reaction.message_id, &option.emoji, reaction.user_id)? {
+        others.push(option);
+      }
+    }
+    Ok(others)
+  }
+
+  fn explain_limit(ctx: &Context, reaction: &Reaction, poll: &Poll, limit: usize) {
+    let user = match reaction.user(&ctx) {
+      Ok(u) => u,
+      Err(e) => {
+        warn!("could not get user to explain poll limit to: {}", e);
+        return;
+      },
+    };
+    let res = user.direct_message(&ctx, |m| m.embed(|e| e
+      .title("Vote not counted")
+      .description(format!(
+        "You can only vote for up to {} options in [this poll](https://discordapp.com/channels/{}/{}/{}). Remove one of your other votes first.",
+        limit,
+        *poll.server_id,
+        *poll.channel_id,
+        *poll.message_id,
+      ))));
+    if let Err(e) = res {
+      warn!("could not explain poll limit to {}: {}", reaction.user_id, e);
+    }
+  }
+}
@@ -1,15 +1,34 @@
 use crate::{
-  database::models::{ToU64, Reaction as DbReaction},
+  database::models::{ToU64, U64, Reaction as DbReaction, NewReactionGrant, ReactionGroup, ReactionGroupMode},
   error::*,
+  filters::Filter,
+  util::parse_emoji,
 };
 
 use diesel::prelude::*;
 
 use serenity::{
   client::{Context, EventHandler},
-  model::channel::{Channel, Reaction},
+  model::{
+    channel::{Channel, Reaction, ReactionType},
+    guild::{Member, Role},
+    id::{ChannelId, GuildId, MessageId, RoleId, UserId},
+  },
 };
 
+use std::{
+  borrow::Borrow,
+  collections::{HashMap, HashSet},
+  thread,
+};
+
+use parking_lot::Mutex;
+
+lazy_static! {
+  /// Reactions the bot removed itself, so the events for them don't take roles away.
+  static ref BOT_REMOVED: Mutex<HashSet<(MessageId, UserId, String)>> = Mutex::default();
+}
+
 pub struct ReactionAuthorize;
 
 impl EventHandler for ReactionAuthorize {
@@ -20,34 +39,380 @@ impl EventHandler for ReactionAuthorize {
   fn reaction_remove(&self, context: Context, reaction: Reaction) {
     ReactionAuthorize::receive(context, &reaction, false);
   }
+
+  fn cache_ready(&self, context: Context, guilds: Vec<GuildId>) {
+    // reactions may have changed while the bot was offline, and this takes a while
+    thread::spawn(move || {
+      for guild in guilds {
+        match ReactionAuthorize::reconcile(&context, guild) {
+          Ok(summary) => if summary.messages > 0 {
+            info!(
+              "Reconciled reaction roles in {} across {} message{}: {} granted, {} revoked",
+              guild,
+              summary.messages,
+              if summary.messages == 1 { "" } else { "s" },
+              summary.granted,
+              summary.revoked,
+            );
+          },
+          Err(e) => warn!("could not reconcile reaction roles in {}: {}", guild, e),
+        }
+      }
+    });
+  }
+}
+
+#[derive(Debug, Default)]
+struct ReconcileSummary {
+  messages: usize,
+  granted: usize,
+  revoked: usize,
+}
+
+/// What reconciling knows about one role given out by reactions.
+#[derive(Debug, Default)]
+struct RoleState {
+  /// The group of the first reaction giving the role, with its mode and role limit.
+  group: Option<(i32, Option<ReactionGroupMode>, Option<i32>)>,
+  /// Members who reacted for the role and match its filters.
+  reacted: HashSet<UserId>,
+  /// Members who reacted for the role at all, whether they match its filters or not.
+  seen: HashSet<UserId>,
+  /// Members who reacted on a drop-only reaction for the role.
+  dropped: HashSet<UserId>,
+  /// Whether the role is taken away when reactions are removed.
+  revocable: bool,
+  /// Whether every reaction for the role could be checked.
+  complete: bool,
 }
 
 impl ReactionAuthorize {
   result_wrap! {
     fn receive(ctx: Context, r: &Reaction, added: bool) -> Result<()> {
+      // the bot adds reactions to menus itself, and shouldn't get roles for them
+      if r.user_id == ctx.cache.read().user.id {
+        return Ok(());
+      }
+      if !added && BOT_REMOVED.lock().remove(&(r.message_id, r.user_id, r.emoji.to_string())) {
+        return Ok(());
+      }
       let channel = match r.channel_id.to_channel(&ctx).chain_err(|| "could not get channel")? {
         Channel::Guild(c) => c.read().clone(),
         _ => return Ok(()),
       };
-      let reactions: Vec<DbReaction> = crate::bot::with_connection(|c| {
-        use crate::database::schema::reactions::dsl;
+      let reactions: Vec<(DbReaction, Option<ReactionGroup>)> = crate::bot::with_connection(|c| {
+        use crate::database::schema::{reactions::dsl, reaction_groups};
         dsl::reactions
+          .left_join(reaction_groups::table)
           .filter(dsl::channel_id.eq(r.channel_id.to_u64())
             .and(dsl::server_id.eq(channel.guild_id.to_u64()))
             .and(dsl::message_id.eq(r.message_id.to_u64()))
             .and(dsl::emoji.eq(r.emoji.to_string())))
           .load(c)
       }).chain_err(|| "could not load reactions")?;
+      if reactions.is_empty() {
+        return Ok(());
+      }
       let guild = channel.guild_id.to_partial_guild(&ctx).chain_err(|| "could not get guild")?;
       let mut member = guild.member(&ctx, r.user_id).chain_err(|| "could not get member")?;
-      for reac in reactions {
-        if added {
-          member.add_role(&ctx, *reac.role_id).chain_err(|| "could not add role")?;
-        } else {
-          member.remove_role(&ctx, *reac.role_id).chain_err(|| "could not remove role")?;
+      let roles: Vec<&Role> = guild.roles.values().collect();
+      for (reac, group) in reactions {
+        let mode = group.as_ref().and_then(ReactionGroup::mode);
+        if added && mode != Some(ReactionGroupMode::Drop) && !ReactionAuthorize::allowed(&reac, &member, &roles) {
+          ReactionAuthorize::remove_reaction(&ctx, r.channel_id, r.message_id, r.user_id, r.emoji.clone())
+            .chain_err(|| "could not remove reaction from member not matching filters")?;
+          let role_name = guild.roles.get(&RoleId(*reac.role_id)).map(|r| r.name.as_str()).unwrap_or("that");
+          let reason = format!(
+            "You can't get the **{}** role in **{}**, because you don't meet its requirements (`{}`).",
+            role_name,
+            guild.name,
+            reac.filters.as_ref().map(String::as_str).unwrap_or_default(),
+          );
+          if let Err(e) = r.user_id.create_dm_channel(&ctx).and_then(|dm| dm.say(&ctx, &reason)) {
+            warn!("could not tell {} why their reaction was removed: {}", r.user_id, e);
+          }
+          continue;
+        }
+        match (mode, added) {
+          (Some(ReactionGroupMode::Unique), true) => {
+            ReactionAuthorize::drop_others(&ctx, &mut member, &reac)?;
+            ReactionAuthorize::grant(&ctx, &mut member, RoleId(*reac.role_id))?;
+          },
+          (Some(ReactionGroupMode::Limit), true) => {
+            let max = group.as_ref().and_then(|g| g.max_roles).unwrap_or(1).max(1) as usize;
+            if !member.roles.contains(&RoleId(*reac.role_id)) && ReactionAuthorize::held_roles(&member, &reac)?.len() >= max {
+              ReactionAuthorize::remove_reaction(&ctx, r.channel_id, r.message_id, r.user_id, r.emoji.clone())
+                .chain_err(|| "could not remove reaction past the limit")?;
+              continue;
+            }
+            ReactionAuthorize::grant(&ctx, &mut member, RoleId(*reac.role_id))?;
+          },
+          (Some(ReactionGroupMode::Verify), false) | (Some(ReactionGroupMode::Drop), false) => {},
+          (Some(ReactionGroupMode::Drop), true) | (_, false) => {
+            ReactionAuthorize::revoke(&ctx, &mut member, RoleId(*reac.role_id))?;
+          },
+          (_, true) => {
+            ReactionAuthorize::grant(&ctx, &mut member, RoleId(*reac.role_id))?;
+          },
         }
       }
       Ok(())
     } |e| warn!("{}", e)
   }
+
+  /// Catch up on reactions added or removed while the bot was offline.
+  ///
+  /// Missing roles are given out following the same group rules as live reactions. Roles are only
+  /// taken away for drop reactions, or if the bot gave them out for a reaction that's now gone.
+  ///
+  /// Grants are only recorded from the moment `reaction_grants` exists, so roles given out for
+  /// reactions before that have no record and are never taken away here. Live reaction removals
+  /// still revoke them as before.
+  fn reconcile(ctx: &Context, guild_id: GuildId) -> Result<ReconcileSummary> {
+    let reactions: Vec<(DbReaction, Option<ReactionGroup>)> = crate::bot::with_connection(|c| {
+      use crate::database::schema::{reactions::dsl, reaction_groups};
+      dsl::reactions
+        .left_join(reaction_groups::table)
+        .filter(dsl::server_id.eq(guild_id.to_u64()))
+        .load(c)
+    }).chain_err(|| "could not load reactions")?;
+    let mut summary = ReconcileSummary::default();
+    if reactions.is_empty() {
+      return Ok(summary);
+    }
+    let grants: Vec<(U64, U64)> = crate::bot::with_connection(|c| {
+      use crate::database::schema::reaction_grants::dsl;
+      dsl::reaction_grants
+        .filter(dsl::server_id.eq(guild_id.to_u64()))
+        .select((dsl::user_id, dsl::role_id))
+        .load(c)
+    }).chain_err(|| "could not load reaction role grants")?;
+    let grants: HashSet<(UserId, RoleId)> = grants.into_iter()
+      .map(|(user, role)| (UserId(*user), RoleId(*role)))
+      .collect();
+    let bot_id = ctx.cache.read().user.id;
+
+    let guild = some_or!(guild_id.to_guild_cached(&ctx), bail!("could not find guild in cache"));
+    let guild_roles: Vec<Role> = guild.read().roles.values().cloned().collect();
+    let mut members: HashMap<UserId, Member> = guild.read().members.iter()
+      .map(|(id, m)| (*id, m.clone()))
+      .collect();
+
+    // the roles in each group, for checking group rules
+    let mut group_roles: HashMap<i32, HashSet<RoleId>> = HashMap::new();
+    let mut roles: HashMap<RoleId, RoleState> = HashMap::new();
+    let mut messages = HashSet::new();
+    for (reac, group) in &reactions {
+      messages.insert(*reac.message_id);
+      if let Some(group_id) = reac.group_id {
+        group_roles.entry(group_id).or_default().insert(RoleId(*reac.role_id));
+      }
+      let mode = group.as_ref().and_then(ReactionGroup::mode);
+      let state = roles.entry(RoleId(*reac.role_id)).or_insert_with(|| RoleState {
+        revocable: true,
+        complete: true,
+        ..Default::default()
+      });
+      if state.group.is_none() {
+        state.group = group.as_ref().map(|g| (g.id, mode, g.max_roles));
+      }
+      if mode == Some(ReactionGroupMode::Verify) || mode == Some(ReactionGroupMode::Drop) {
+        state.revocable = false;
+      }
+      let users = match ReactionAuthorize::reaction_users(ctx, reac) {
+        Ok(u) => u,
+        Err(e) => {
+          warn!("could not get reactions for reaction role {}: {}", reac.id, e);
+          state.complete = false;
+          continue;
+        },
+      };
+      for user in users {
+        if user == bot_id {
+          continue;
+        }
+        if mode == Some(ReactionGroupMode::Drop) {
+          state.dropped.insert(user);
+          continue;
+        }
+        state.seen.insert(user);
+        // big guilds may not have every member cached yet
+        if !members.contains_key(&user) {
+          match guild_id.member(ctx, user) {
+            Ok(m) => { members.insert(user, m); },
+            Err(_) => continue,
+          }
+        }
+        if ReactionAuthorize::allowed(reac, &members[&user], &guild_roles) {
+          state.reacted.insert(user);
+        }
+      }
+    }
+    summary.messages = messages.len();
+
+    for (role, state) in &roles {
+      for user in &state.reacted {
+        if state.dropped.contains(user) {
+          continue;
+        }
+        let member = some_or!(members.get_mut(user), continue);
+        if member.roles.contains(role) || member.user.read().bot {
+          continue;
+        }
+        if let Some((group_id, mode, max_roles)) = state.group {
+          let in_group = &group_roles[&group_id];
+          let held = member.roles.iter().filter(|r| in_group.contains(r)).count();
+          let max = match mode {
+            Some(ReactionGroupMode::Unique) => 1,
+            Some(ReactionGroupMode::Limit) => max_roles.unwrap_or(1).max(1) as usize,
+            _ => usize::max_value(),
+          };
+          // there's no telling which reactions came first, so leave members at the limit alone
+          if held >= max {
+            continue;
+          }
+        }
+        match ReactionAuthorize::grant(ctx, member, *role) {
+          Ok(()) => summary.granted += 1,
+          Err(e) => warn!("could not add role {} to {} while reconciling: {}", role, user, e),
+        }
+      }
+
+      // only take roles away when every reaction that could give it has been seen
+      let revoke: Vec<UserId> = members.iter()
+        .filter(|(_, m)| m.roles.contains(role))
+        .map(|(id, _)| *id)
+        .filter(|id| state.dropped.contains(id) || (
+          state.revocable
+            && state.complete
+            && !state.seen.contains(id)
+            && grants.contains(&(*id, *role))
+        ))
+        .collect();
+      for user in revoke {
+        let member = members.get_mut(&user).unwrap();
+        match ReactionAuthorize::revoke(ctx, member, *role) {
+          Ok(()) => summary.revoked += 1,
+          Err(e) => warn!("could not remove role {} from {} while reconciling: {}", role, user, e),
+        }
+      }
+    }
+    Ok(summary)
+  }
+
+  /// Give a member a role for a reaction, remembering that the bot gave it if they didn't have it.
+  fn grant(ctx: &Context, member: &mut Member, role: RoleId) -> Result<()> {
+    if member.roles.contains(&role) {
+      return Ok(());
+    }
+    member.add_role(ctx, role).chain_err(|| "could not add role")?;
+    if !member.roles.contains(&role) {
+      member.roles.push(role);
+    }
+    let user_id = member.user.read().id;
+    crate::bot::with_connection(|c| {
+      use crate::database::schema::reaction_grants::dsl;
+      diesel::insert_into(dsl::reaction_grants)
+        .values(&NewReactionGrant {
+          server_id: member.guild_id.into(),
+          user_id: user_id.into(),
+          role_id: role.into(),
+        })
+        .on_conflict_do_nothing()
+        .execute(c)
+    }).chain_err(|| "could not record reaction role grant")?;
+    Ok(())
+  }
+
+  /// Take a role away from a member for a reaction, forgetting that the bot gave it.
+  fn revoke(ctx: &Context, member: &mut Member, role: RoleId) -> Result<()> {
+    member.remove_role(ctx, role).chain_err(|| "could not remove role")?;
+    let user_id = member.user.read().id;
+    crate::bot::with_connection(|c| {
+      use crate::database::schema::reaction_grants::dsl;
+      diesel::delete(dsl::reaction_grants.filter(dsl::server_id.eq(member.guild_id.to_u64())
+        .and(dsl::user_id.eq(user_id.to_u64()))
+        .and(dsl::role_id.eq(role.to_u64()))))
+        .execute(c)
+    }).chain_err(|| "could not forget reaction role grant")?;
+    Ok(())
+  }
+
+  /// Check if a member matches the filters on a reaction role. Invalid filters match nobody.
+  fn allowed<I: Borrow<Role>>(reac: &DbReaction, member: &Member, roles: &[I]) -> bool {
+    let filters = some_or!(reac.filters.as_ref(), return true);
+    match Filter::all_filters(filters) {
+      Some(f) => f.iter().all(|f| f.matches(member, roles)),
+      None => {
+        warn!("invalid filters on reaction role {}: `{}`", reac.id, filters);
+        false
+      },
+    }
+  }
+
+  /// Get everyone who reacted with a reaction role's emoji.
+  fn reaction_users(ctx: &Context, reac: &DbReaction) -> Result<Vec<UserId>> {
+    let channel = ChannelId(*reac.channel_id);
+    let emoji = parse_emoji(&reac.emoji);
+    let mut users = Vec::new();
+    let mut after: Option<UserId> = None;
+    loop {
+      let page = channel.reaction_users(ctx, MessageId(*reac.message_id), emoji.clone(), Some(100), after)
+        .chain_err(|| "could not get reaction users")?;
+      let full = page.len() >= 100;
+      after = page.iter().map(|u| u.id).max();
+      users.extend(page.into_iter().map(|u| u.id));
+      if !full {
+        break;
+      }
+    }
+    Ok(users)
+  }
+
+  /// Get the other reaction roles in the same group as `reac` that the member has.
+  fn held_roles(member: &Member, reac: &DbReaction) -> Result<Vec<DbReaction>> {
+    let group_id = some_or!(reac.group_id, return Ok(Vec::new()));
+    let others: Vec<DbReaction> = crate::bot::with_connection(|c| {
+      use crate::database::schema::reactions::dsl;
+      dsl::reactions
+        .filter(dsl::group_id.eq(group_id).and(dsl::role_id.ne(reac.role_id)))
+        .load(c)
+    }).chain_err(|| "could not load reaction group")?;
+    let mut held: Vec<DbReaction> = others.into_iter()
+      .filter(|o| member.roles.contains(&RoleId(*o.role_id)))
+      .collect();
+    held.sort_by_key(|o| *o.role_id);
+    held.dedup_by_key(|o| *o.role_id);
+    Ok(held)
+  }
+
+  /// Remove a member's reaction, without the resulting event taking any roles away.
+  fn remove_reaction(ctx: &Context, channel: ChannelId, message: MessageId, user: UserId, emoji: ReactionType) -> serenity::Result<()> {
+    let key = (message, user, emoji.to_string());
+    BOT_REMOVED.lock().insert(key.clone());
+    let res = channel.delete_reaction(ctx, message, Some(user), emoji);
+    if res.is_err() {
+      BOT_REMOVED.lock().remove(&key);
+    }
+    res
+  }
+
+  /// Take away the other roles in `reac`'s group from the member, along with their reactions.
+  fn drop_others(ctx: &Context, member: &mut Member, reac: &DbReaction) -> Result<()> {
+    for other in ReactionAuthorize::held_roles(member, reac)? {
+      ReactionAuthorize::revoke(ctx, member, RoleId(*other.role_id))?;
+      let user_id = member.user.read().id;
+      let removed = ReactionAuthorize::remove_reaction(
+        ctx,
+        ChannelId(*other.channel_id),
+        MessageId(*other.message_id),
+        user_id,
+        parse_emoji(&other.emoji),
+      );
+      if let Err(e) = removed {
+        warn!("could not remove reaction for role {} from {}: {}", *other.role_id, user_id, e);
+      }
+    }
+    Ok(())
+  }
 }
@@ -0,0 +1,65 @@
+use crate::{
+  database::models::{ToU64, TemporaryOverwrite},
+  error::*,
+};
+
+use diesel::prelude::*;
+
+use serenity::{
+  client::{Context, EventHandler},
+  model::channel::{GuildChannel, Message},
+  prelude::RwLock,
+};
+
+use std::sync::Arc;
+
+pub struct TemporaryOverwritesListener;
+
+impl EventHandler for TemporaryOverwritesListener {
+  result_wrap! {
+    fn message(&self, ctx: Context, message: Message) -> Result<()> {
+      if message.guild_id.is_none() {
+        return Ok(());
+      }
+
+      let temps: Vec<TemporaryOverwrite> = crate::bot::with_connection(|c| {
+        use crate::database::schema::temporary_overwrites::dsl;
+
+        dsl::temporary_overwrites
+          .filter(dsl::user_id.eq(message.author.id.to_u64())
+            .and(dsl::channel_id.eq(message.channel_id.to_u64()))
+            .and(dsl::messages.is_not_null()))
+          .load(c)
+      }).chain_err(|| "could not get temporary overwrites")?;
+
+      for mut temp in temps {
+        if message.id == *temp.message_id {
+          continue;
+        }
+
+        temp.messages = temp.messages.map(|x| x - 1);
+
+        if temp.messages == Some(0) {
+          crate::tasks::temporary_overwrites::restore_overwrite(&ctx, &temp)?;
+        } else {
+          crate::bot::with_connection(|c| {
+            temp.save_changes::<TemporaryOverwrite>(c)
+          }).chain_err(|| "could not save temporary overwrite changes")?;
+        }
+      }
+      Ok(())
+    } |e| warn!("{}", e)
+  }
+
+  result_wrap! {
+    fn channel_delete(&self, _ctx: Context, channel: Arc<RwLock<GuildChannel>>) -> Result<()> {
+      let channel_id = channel.read().id;
+      crate::bot::with_connection(|c| {
+        use crate::database::schema::temporary_overwrites::dsl;
+
+        diesel::delete(dsl::temporary_overwrites.filter(dsl::channel_id.eq(channel_id.to_u64()))).execute(c)
+      }).chain_err(|| "could not delete temporary overwrites for deleted channel")?;
+      Ok(())
+    } |e| warn!("{}", e)
+  }
+}
@@ -7,7 +7,11 @@ use diesel::prelude::*;
 
 use serenity::{
   client::{Context, EventHandler},
-  model::channel::{Channel, Message},
+  model::{
+    channel::{Channel, Message},
+    guild::Member,
+    id::RoleId,
+  },
 };
 
 pub struct TemporaryRolesListener;
@@ -48,6 +52,7 @@ impl EventHandler for TemporaryRolesListener {
 
         if temp.messages == Some(0) {
           member.remove_role(&ctx, *temp.role_id).chain_err(|| "could not remove role")?;
+          crate::commands::timeout::forget_stripped_role(guild_id, message.author.id, RoleId(*temp.role_id))?;
           crate::bot::with_connection(|c| {
             diesel::delete(&temp).execute(c)
           }).chain_err(|| "could not delete temporary role")?;
@@ -60,4 +65,35 @@ impl EventHandler for TemporaryRolesListener {
       Ok(())
     } |e| warn!("{}", e)
   }
+
+  result_wrap! {
+    fn guild_member_update(&self, _ctx: Context, _old: Option<Member>, member: Member) -> Result<()> {
+      let temps: Vec<TemporaryRole> = crate::bot::with_connection(|c| {
+        use crate::database::schema::temporary_roles::dsl;
+
+        dsl::temporary_roles
+          .filter(dsl::user_id.eq(member.user.read().id.to_u64())
+            .and(dsl::guild_id.eq(member.guild_id.to_u64())))
+          .load(c)
+      }).chain_err(|| "could not get temporary roles")?;
+
+      if temps.is_empty() {
+        return Ok(());
+      }
+      // strict timeouts take roles away for a while, and give them back when they end
+      let stripped = crate::commands::timeout::stripped_roles(member.guild_id, member.user.read().id)?;
+
+      // forget about temporary roles that were taken away by hand
+      for temp in temps {
+        let role = RoleId(*temp.role_id);
+        if member.roles.contains(&role) || stripped.contains(&role) {
+          continue;
+        }
+        crate::bot::with_connection(|c| {
+          diesel::delete(&temp).execute(c)
+        }).chain_err(|| "could not delete temporary role")?;
+      }
+      Ok(())
+    } |e| warn!("{}", e)
+  }
 }
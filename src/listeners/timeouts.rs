@@ -37,8 +37,9 @@ impl EventHandler for Timeouts {
       };
 
       if timeout.ends() < Utc::now().timestamp() {
-        if let Err(e) = crate::bot::with_connection(|c| diesel::delete(&timeout).execute(c)) {
-          warn!("could not delete timeout: {}", e);
+        let mut member = channel.read().guild_id.member(&ctx, message.author.id).chain_err(|| "could not get member")?;
+        if let Err(e) = crate::commands::timeout::lift_timeout(&ctx, &mut member, &timeout) {
+          warn!("could not lift timeout: {}", e);
         }
         return Ok(());
       }
@@ -39,6 +39,7 @@ macro_rules! some_or {
   }}
 }
 
+mod attachments;
 mod bot;
 mod commands;
 mod config;
@@ -48,7 +49,9 @@ mod filters;
 mod listeners;
 mod lodestone;
 mod logging;
+mod recurrence;
 mod tasks;
+mod template;
 mod util;
 
 use crate::{
@@ -0,0 +1,349 @@
+use crate::util::parse_duration_secs;
+
+use chrono::{
+  Date,
+  DateTime,
+  Datelike,
+  Duration,
+  Timelike,
+  Utc,
+  Weekday,
+};
+
+use std::{
+  fmt::{Display, Formatter, Result as FmtResult},
+  str::FromStr,
+};
+
+/// How often something repeats.
+///
+/// All times are in UTC. The accepted forms are:
+///
+/// - `every <duration>`, such as `every 6h`
+/// - `daily <HH:MM>`, such as `daily 08:00`
+/// - `weekly <day> <HH:MM>`, such as `weekly tuesday 08:00`
+/// - a five-field cron expression, such as `0 8 * * tue`
+#[derive(Debug, Clone)]
+pub enum Recurrence {
+  Every(u64),
+  Daily(u32, u32),
+  Weekly(Weekday, u32, u32),
+  Cron(Cron),
+}
+
+impl Recurrence {
+  /// The first time this recurrence happens strictly after `after`.
+  pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    match *self {
+      Recurrence::Every(secs) => Some(after + Duration::seconds(secs as i64)),
+      Recurrence::Daily(hour, minute) => {
+        let candidate = after.date().and_hms(hour, minute, 0);
+        if candidate > after {
+          Some(candidate)
+        } else {
+          Some(candidate + Duration::days(1))
+        }
+      },
+      Recurrence::Weekly(day, hour, minute) => {
+        let offset = (7 + day.num_days_from_monday() - after.weekday().num_days_from_monday()) % 7;
+        let candidate = (after.date() + Duration::days(i64::from(offset))).and_hms(hour, minute, 0);
+        if candidate > after {
+          Some(candidate)
+        } else {
+          Some(candidate + Duration::weeks(1))
+        }
+      },
+      Recurrence::Cron(ref cron) => cron.next_after(after),
+    }
+  }
+
+  /// The first time this recurrence happens after `after` that is also after `now`, skipping any
+  /// occurrences that were missed.
+  pub fn next_after_now(&self, after: DateTime<Utc>, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let mut next = self.next_after(after)?;
+    while next <= now {
+      next = self.next_after(next)?;
+    }
+    Some(next)
+  }
+}
+
+impl FromStr for Recurrence {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let lower = s.trim().to_lowercase();
+    let mut parts: Vec<&str> = lower.split_whitespace().collect();
+    // everything is in UTC, but people like to say so
+    if parts.last() == Some(&"utc") {
+      parts.pop();
+    }
+    match parts.first() {
+      Some(&"every") => {
+        let secs = parse_duration_secs(parts[1..].join(" ")).map_err(|e| e.to_string())?;
+        if secs < 60 {
+          return Err("recurrences must be at least one minute apart".into());
+        }
+        Ok(Recurrence::Every(secs))
+      },
+      Some(&"daily") if parts.len() == 2 => {
+        let (hour, minute) = parse_time(parts[1])?;
+        Ok(Recurrence::Daily(hour, minute))
+      },
+      Some(&"weekly") if parts.len() == 3 => {
+        let day = Weekday::from_str(parts[1]).map_err(|_| format!("invalid day: {}", parts[1]))?;
+        let (hour, minute) = parse_time(parts[2])?;
+        Ok(Recurrence::Weekly(day, hour, minute))
+      },
+      _ if parts.len() == 5 => Cron::from_str(&parts.join(" ")).map(Recurrence::Cron),
+      _ => Err("expected `every <duration>`, `daily <HH:MM>`, `weekly <day> <HH:MM>` or a cron expression".into()),
+    }
+  }
+}
+
+impl Display for Recurrence {
+  fn fmt(&self, f: &mut Formatter) -> FmtResult {
+    match *self {
+      Recurrence::Every(secs) => write!(f, "every {}s", secs),
+      Recurrence::Daily(hour, minute) => write!(f, "daily {:02}:{:02}", hour, minute),
+      Recurrence::Weekly(day, hour, minute) => write!(f, "weekly {:?} {:02}:{:02}", day, hour, minute),
+      Recurrence::Cron(ref cron) => write!(f, "{}", cron.source),
+    }
+  }
+}
+
+fn parse_time(s: &str) -> Result<(u32, u32), String> {
+  let parts: Vec<&str> = s.split(':').collect();
+  if parts.len() != 2 {
+    return Err(format!("invalid time: {}", s));
+  }
+  let hour: u32 = parts[0].parse().map_err(|_| format!("invalid hour: {}", parts[0]))?;
+  let minute: u32 = parts[1].parse().map_err(|_| format!("invalid minute: {}", parts[1]))?;
+  if hour > 23 || minute > 59 {
+    return Err(format!("invalid time: {}", s));
+  }
+  Ok((hour, minute))
+}
+
+/// A standard five-field cron expression: minute, hour, day of month, month and day of week.
+#[derive(Debug, Clone)]
+pub struct Cron {
+  source: String,
+  minutes: CronField,
+  hours: CronField,
+  days_of_month: CronField,
+  months: CronField,
+  days_of_week: CronField,
+}
+
+impl Cron {
+  fn day_matches(&self, date: &Date<Utc>) -> bool {
+    if !self.months.contains(date.month()) {
+      return false;
+    }
+    let dom = self.days_of_month.contains(date.day());
+    let dow = self.days_of_week.contains(date.weekday().num_days_from_sunday());
+    // like cron, if both day fields are restricted, either one matching is enough
+    match (self.days_of_month.any, self.days_of_week.any) {
+      (false, false) => dom || dow,
+      _ => dom && dow,
+    }
+  }
+
+  fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let start = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+    let mut date = start.date();
+    // some expressions (like the 31st of February) never happen, so don't look forever
+    for day in 0..366 * 5 {
+      if self.day_matches(&date) {
+        let (first_hour, first_minute) = if day == 0 { (start.hour(), start.minute()) } else { (0, 0) };
+        for hour in (first_hour..24).filter(|&h| self.hours.contains(h)) {
+          let from = if hour == first_hour { first_minute } else { 0 };
+          if let Some(minute) = (from..60).find(|&m| self.minutes.contains(m)) {
+            return Some(date.and_hms(hour, minute, 0));
+          }
+        }
+      }
+      date = date.succ();
+    }
+    None
+  }
+}
+
+impl FromStr for Cron {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let fields: Vec<&str> = s.split_whitespace().collect();
+    if fields.len() != 5 {
+      return Err("cron expressions need five fields".into());
+    }
+    Ok(Cron {
+      source: fields.join(" "),
+      minutes: CronField::parse(fields[0], 0, 59, &[])?,
+      hours: CronField::parse(fields[1], 0, 23, &[])?,
+      days_of_month: CronField::parse(fields[2], 1, 31, &[])?,
+      months: CronField::parse(fields[3], 1, 12, &["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"])?,
+      days_of_week: CronField::parse(fields[4], 0, 7, &["sun", "mon", "tue", "wed", "thu", "fri", "sat"])?,
+    })
+  }
+}
+
+#[derive(Debug, Clone)]
+struct CronField {
+  any: bool,
+  mask: u64,
+}
+
+impl CronField {
+  fn contains(&self, value: u32) -> bool {
+    self.mask & (1 << value) != 0
+  }
+
+  /// Parse one field. `names` are alternatives for the numbers starting at `min`.
+  fn parse(field: &str, min: u32, max: u32, names: &[&str]) -> Result<CronField, String> {
+    let value = |s: &str| -> Result<u32, String> {
+      if let Some(i) = names.iter().position(|n| *n == s) {
+        return Ok(min + i as u32);
+      }
+      match s.parse::<u32>() {
+        Ok(v) if v >= min && v <= max => Ok(v),
+        _ => Err(format!("invalid value `{}` in `{}`", s, field)),
+      }
+    };
+    let mut mask = 0;
+    for part in field.split(',') {
+      let (range, step) = match part.find('/') {
+        Some(i) => {
+          let step: u32 = part[i + 1..].parse().map_err(|_| format!("invalid step in `{}`", field))?;
+          if step == 0 {
+            return Err(format!("invalid step in `{}`", field));
+          }
+          (&part[..i], step)
+        },
+        None => (part, 1),
+      };
+      let (start, end) = if range == "*" {
+        (min, max)
+      } else if let Some(i) = range.find('-') {
+        (value(&range[..i])?, value(&range[i + 1..])?)
+      } else {
+        let v = value(range)?;
+        (v, if step == 1 { v } else { max })
+      };
+      if start > end {
+        return Err(format!("invalid range in `{}`", field));
+      }
+      for v in (start..=end).step_by(step as usize) {
+        mask |= 1 << v;
+      }
+    }
+    // cron allows 7 as another name for Sunday
+    if max == 7 && mask & (1 << 7) != 0 {
+      mask |= 1;
+    }
+    // `*/2` still covers the whole range, so it doesn't restrict the day fields either
+    Ok(CronField {
+      any: field.starts_with('*'),
+      mask,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use chrono::TimeZone;
+
+  fn at(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Utc> {
+    Utc.ymd(y, mo, d).and_hms(h, mi, 0)
+  }
+
+  fn next(recurrence: &str, after: DateTime<Utc>) -> DateTime<Utc> {
+    recurrence.parse::<Recurrence>().unwrap().next_after(after).unwrap()
+  }
+
+  #[test]
+  fn every() {
+    assert_eq!(next("every 6h", at(2020, 6, 1, 10, 0)), at(2020, 6, 1, 16, 0));
+    assert_eq!(next("every 1d", at(2020, 6, 30, 12, 0)), at(2020, 7, 1, 12, 0));
+    assert!("every 30s".parse::<Recurrence>().is_err());
+  }
+
+  #[test]
+  fn daily() {
+    assert_eq!(next("daily 08:00", at(2020, 6, 1, 7, 0)), at(2020, 6, 1, 8, 0));
+    assert_eq!(next("daily 08:00", at(2020, 6, 1, 8, 0)), at(2020, 6, 2, 8, 0));
+    assert_eq!(next("daily 08:00", at(2020, 12, 31, 9, 0)), at(2021, 1, 1, 8, 0));
+    assert!("daily 24:00".parse::<Recurrence>().is_err());
+    assert!("daily 8".parse::<Recurrence>().is_err());
+  }
+
+  #[test]
+  fn weekly() {
+    // 2020-06-01 is a Monday
+    assert_eq!(next("weekly tuesday 08:00", at(2020, 6, 1, 9, 0)), at(2020, 6, 2, 8, 0));
+    assert_eq!(next("weekly tuesday 08:00", at(2020, 6, 2, 8, 0)), at(2020, 6, 9, 8, 0));
+    assert_eq!(next("weekly tue 08:00", at(2020, 6, 30, 9, 0)), at(2020, 7, 7, 8, 0));
+    assert!("weekly someday 08:00".parse::<Recurrence>().is_err());
+  }
+
+  #[test]
+  fn utc_suffix() {
+    assert_eq!(next("daily 08:00 UTC", at(2020, 6, 1, 7, 0)), at(2020, 6, 1, 8, 0));
+    assert_eq!(next("0 8 * * tue utc", at(2020, 6, 1, 0, 0)), at(2020, 6, 2, 8, 0));
+  }
+
+  #[test]
+  fn cron() {
+    assert_eq!(next("0 8 * * tue", at(2020, 6, 1, 0, 0)), at(2020, 6, 2, 8, 0));
+    assert_eq!(next("30 * * * *", at(2020, 6, 1, 10, 30)), at(2020, 6, 1, 11, 30));
+    assert!("61 * * * *".parse::<Recurrence>().is_err());
+    assert!("0 0 * * 8".parse::<Recurrence>().is_err());
+    assert!("0 0 * *".parse::<Recurrence>().is_err());
+    assert!("*/0 * * * *".parse::<Recurrence>().is_err());
+  }
+
+  #[test]
+  fn cron_ranges_and_steps() {
+    let business = "*/15 9-17 * * mon-fri";
+    assert_eq!(next(business, at(2020, 6, 1, 9, 7)), at(2020, 6, 1, 9, 15));
+    assert_eq!(next(business, at(2020, 6, 1, 17, 45)), at(2020, 6, 2, 9, 0));
+    // Friday evening to Monday morning
+    assert_eq!(next(business, at(2020, 6, 5, 17, 50)), at(2020, 6, 8, 9, 0));
+    assert_eq!(next("0 0,12 * * *", at(2020, 6, 1, 1, 0)), at(2020, 6, 1, 12, 0));
+    assert_eq!(next("0 5/6 * * *", at(2020, 6, 1, 12, 0)), at(2020, 6, 1, 17, 0));
+  }
+
+  #[test]
+  fn cron_sunday_as_seven() {
+    assert_eq!(next("0 0 * * 7", at(2020, 6, 1, 0, 0)), at(2020, 6, 7, 0, 0));
+    assert_eq!(next("0 0 * * 0", at(2020, 6, 1, 0, 0)), at(2020, 6, 7, 0, 0));
+    assert_eq!(next("0 0 * * sun", at(2020, 6, 1, 0, 0)), at(2020, 6, 7, 0, 0));
+  }
+
+  #[test]
+  fn cron_month_boundaries() {
+    assert_eq!(next("0 0 1 * *", at(2020, 6, 15, 0, 0)), at(2020, 7, 1, 0, 0));
+    assert_eq!(next("0 0 1 * *", at(2020, 12, 15, 0, 0)), at(2021, 1, 1, 0, 0));
+    assert_eq!(next("0 0 31 * *", at(2020, 6, 1, 0, 0)), at(2020, 7, 31, 0, 0));
+    assert_eq!(next("0 0 29 feb *", at(2020, 3, 1, 0, 0)), at(2024, 2, 29, 0, 0));
+    assert!("0 0 31 feb *".parse::<Recurrence>().unwrap().next_after(at(2020, 1, 1, 0, 0)).is_none());
+  }
+
+  #[test]
+  fn cron_day_fields() {
+    // both restricted: either one matching is enough, so the first Friday comes before the 13th
+    assert_eq!(next("0 0 13 * fri", at(2020, 6, 1, 0, 0)), at(2020, 6, 5, 0, 0));
+    // a stepped `*` isn't a restriction, so both have to match: an odd day that's a Monday
+    assert_eq!(next("0 0 */2 * mon", at(2020, 6, 1, 0, 0)), at(2020, 6, 15, 0, 0));
+    assert_eq!(next("0 0 * * mon", at(2020, 6, 1, 0, 0)), at(2020, 6, 8, 0, 0));
+  }
+
+  #[test]
+  fn skips_missed_runs() {
+    let daily: Recurrence = "daily 08:00".parse().unwrap();
+    assert_eq!(daily.next_after_now(at(2020, 6, 1, 8, 0), at(2020, 6, 5, 12, 0)), Some(at(2020, 6, 6, 8, 0)));
+  }
+}
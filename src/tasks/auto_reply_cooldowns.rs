@@ -0,0 +1,38 @@
+use crate::{
+  bot::BotEnv,
+  tasks::RunsTask,
+};
+
+use chrono::{
+  Duration,
+  prelude::*,
+};
+
+use diesel::prelude::*;
+
+use std::{
+  sync::Arc,
+  thread,
+};
+
+/// Removes auto reply cooldowns that have run out, so the table doesn't keep growing.
+#[derive(Debug, Default)]
+pub struct AutoReplyCooldownsTask;
+
+impl RunsTask for AutoReplyCooldownsTask {
+  fn start(self, _: Arc<BotEnv>) {
+    loop {
+      thread::sleep(Duration::hours(1).to_std().unwrap());
+      let now = Utc::now().timestamp();
+      let res = crate::bot::with_connection(|c| {
+        use crate::database::schema::auto_reply_cooldowns::dsl;
+        diesel::delete(dsl::auto_reply_cooldowns.filter(dsl::expires_at.le(now))).execute(c)
+      });
+      match res {
+        Ok(0) => {},
+        Ok(n) => info!("Removed {} expired auto reply cooldown{}", n, if n == 1 { "" } else { "s" }),
+        Err(e) => warn!("could not remove expired auto reply cooldowns: {}", e),
+      }
+    }
+  }
+}
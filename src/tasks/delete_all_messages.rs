@@ -1,10 +1,20 @@
 use crate::{
   bot::BotEnv,
-  database::models::DeleteAllMessages,
+  database::models::{DeleteAllMessages, NewDeleteAllMessages},
+  filters::Filter,
   tasks::RunsTask,
 };
 
-use serenity::model::id::ChannelId;
+use serenity::{
+  Error as SError,
+  http::{Http, HttpError, StatusCode},
+  model::{
+    channel::Message,
+    guild::{Guild, Member, Role},
+    id::{ChannelId, GuildId, MessageId, UserId},
+  },
+  prelude::RwLock,
+};
 
 use chrono::{
   Duration,
@@ -16,10 +26,216 @@ use crate::error::*;
 use diesel::prelude::*;
 
 use std::{
+  collections::HashMap,
   sync::Arc,
   thread,
 };
 
+/// How many pages of 100 messages to go through per channel each time the task runs. Progress is
+/// saved, so the next run picks up where this one left off.
+const PAGES_PER_RUN: usize = 10;
+
+/// How long to wait between deleting messages that are too old to be bulk deleted.
+const SINGLE_DELETE_DELAY: i64 = 1;
+
+/// The rules deciding which messages in a channel get deleted.
+#[derive(Debug)]
+pub struct DeleteRules {
+  after: Duration,
+  exclude: Vec<u64>,
+  keep_pinned: bool,
+  keep_filters: Option<Vec<Filter>>,
+  only_bots: bool,
+  attachments: Option<bool>,
+  keep_latest: usize,
+}
+
+impl DeleteRules {
+  /// Whether these rules can keep a message now and delete it later, meaning a sweep has to go
+  /// through the whole history instead of stopping where the last one started.
+  fn changes_over_time(&self) -> bool {
+    self.keep_pinned || self.keep_filters.is_some() || self.keep_latest > 0
+  }
+
+  /// Whether a message should be deleted, ignoring how old it is. `latest` are the IDs of the
+  /// newest messages in the channel when `keep_latest` is set, and `fetched` are the authors that
+  /// weren't cached, from [`DeleteRules::fetch_members`].
+  fn should_delete(&self, message: &Message, latest: &[MessageId], guild: Option<&Guild>, fetched: &HashMap<UserId, Option<Member>>) -> bool {
+    if self.exclude.contains(&message.id.0) || latest.contains(&message.id) {
+      return false;
+    }
+    if self.keep_pinned && message.pinned {
+      return false;
+    }
+    if self.only_bots && !message.author.bot {
+      return false;
+    }
+    if let Some(with) = self.attachments {
+      if message.attachments.is_empty() == with {
+        return false;
+      }
+    }
+    if let Some(ref filters) = self.keep_filters {
+      // without the guild, there's no way to know if the author should be kept, so play it safe
+      let guild = match guild {
+        Some(g) => g,
+        None => return false,
+      };
+      let member = match guild.members.get(&message.author.id) {
+        Some(m) => m,
+        None => match fetched.get(&message.author.id) {
+          Some(Some(m)) => m,
+          // authors that have left can't match any filters
+          Some(None) => return true,
+          // the author couldn't be fetched, so there's no telling if they should be kept
+          None => return false,
+        },
+      };
+      let roles: Vec<&Role> = guild.roles.values().collect();
+      if filters.iter().all(|f| f.matches(member, &roles)) {
+        return false;
+      }
+    }
+    true
+  }
+
+  /// Fetch the authors of `messages` that aren't in the cache, so `keep_filters` can be checked
+  /// against them. Authors that are no longer in the guild map to `None`, and authors that
+  /// couldn't be fetched are left out.
+  fn fetch_members<H: AsRef<Http>>(&self, http: H, guild: Option<&Arc<RwLock<Guild>>>, messages: &[Message]) -> HashMap<UserId, Option<Member>> {
+    let mut fetched = HashMap::new();
+    let guild = match (&self.keep_filters, guild) {
+      (Some(_), Some(g)) => g,
+      _ => return fetched,
+    };
+    let (guild_id, missing) = {
+      let guild = guild.read();
+      let mut missing: Vec<UserId> = messages.iter()
+        .map(|m| m.author.id)
+        .filter(|id| !guild.members.contains_key(id))
+        .collect();
+      missing.sort();
+      missing.dedup();
+      (guild.id, missing)
+    };
+    for user in missing {
+      match http.as_ref().get_member(guild_id.0, user.0) {
+        Ok(member) => {
+          fetched.insert(user, Some(member));
+        },
+        Err(SError::Http(box HttpError::UnsuccessfulRequest(ref r))) if r.status_code == StatusCode::NOT_FOUND => {
+          fetched.insert(user, None);
+        },
+        Err(e) => warn!("Could not fetch member {} of {}: {}", user.0, guild_id.0, e),
+      }
+    }
+    fetched
+  }
+
+  /// Get the IDs of the messages kept by `keep_latest`.
+  fn latest<H: AsRef<Http>>(&self, http: H, channel: ChannelId) -> Result<Vec<MessageId>> {
+    let mut latest: Vec<MessageId> = Vec::with_capacity(self.keep_latest);
+    while latest.len() < self.keep_latest {
+      let limit = std::cmp::min(self.keep_latest - latest.len(), 100) as u64;
+      let before = latest.last().cloned();
+      let messages = channel.messages(&http, |m| match before {
+        Some(b) => m.before(b).limit(limit),
+        None => m.limit(limit),
+      }).chain_err(|| format!("could not get latest messages for channel {}", channel))?;
+      let done = (messages.len() as u64) < limit;
+      let mut ids: Vec<MessageId> = messages.into_iter().map(|m| m.id).collect();
+      ids.sort_by(|a, b| b.cmp(a));
+      latest.extend(ids);
+      if done {
+        break;
+      }
+    }
+    Ok(latest)
+  }
+
+  fn from_parts(after: i32, exclude: Vec<u64>, keep_pinned: bool, keep_filters: Option<&str>, only_bots: bool, attachments: Option<bool>, keep_latest: i32) -> Self {
+    let keep_filters = keep_filters.and_then(|f| {
+      let filters = Filter::all_filters(f);
+      if filters.is_none() {
+        warn!("invalid delete_all_messages filters: `{}`", f);
+      }
+      filters
+    });
+    DeleteRules {
+      after: Duration::seconds(i64::from(after)),
+      exclude,
+      keep_pinned,
+      keep_filters,
+      only_bots,
+      attachments,
+      keep_latest: keep_latest.max(0) as usize,
+    }
+  }
+}
+
+impl<'a> From<&'a DeleteAllMessages> for DeleteRules {
+  fn from(dam: &DeleteAllMessages) -> Self {
+    DeleteRules::from_parts(dam.after, dam.exclude(), dam.keep_pinned, dam.keep_filters.as_ref().map(String::as_str), dam.only_bots, dam.attachments, dam.keep_latest)
+  }
+}
+
+impl<'a> From<&'a NewDeleteAllMessages> for DeleteRules {
+  fn from(dam: &NewDeleteAllMessages) -> Self {
+    DeleteRules::from_parts(dam.after, dam.exclude(), dam.keep_pinned, dam.keep_filters.as_ref().map(String::as_str), dam.only_bots, dam.attachments, dam.keep_latest)
+  }
+}
+
+/// What a sweep would do right now, without deleting anything.
+#[derive(Debug, Default)]
+pub struct DryRun {
+  /// How many messages were looked at.
+  pub checked: usize,
+  /// How many of those would be deleted.
+  pub deleted: usize,
+  /// How many of the deleted messages are too old to be bulk deleted.
+  pub old: usize,
+  /// Whether there were more messages than were looked at.
+  pub more: bool,
+}
+
+/// Go through the newest messages in a channel, counting which ones a sweep would delete.
+pub fn dry_run<H: AsRef<Http>>(http: H, guild: Option<Arc<RwLock<Guild>>>, channel: ChannelId, rules: &DeleteRules) -> Result<DryRun> {
+  let now = Utc::now();
+  let bulk_cutoff = now - Duration::days(14) + Duration::hours(1);
+  let latest = rules.latest(&http, channel)?;
+
+  let mut run = DryRun::default();
+  let mut before: Option<MessageId> = None;
+  for page in 0..PAGES_PER_RUN {
+    let messages = channel.messages(&http, |m| match before {
+      Some(b) => m.before(b).limit(100),
+      None => m.limit(100),
+    }).chain_err(|| format!("could not get messages for channel {}", channel))?;
+    before = messages.iter().map(|m| m.id).min();
+    let full_page = messages.len() >= 100;
+    let fetched = rules.fetch_members(&http, guild.as_ref(), &messages);
+    {
+      let guild = guild.as_ref().map(|g| g.read());
+      for message in &messages {
+        run.checked += 1;
+        let timestamp = message.timestamp.with_timezone(&Utc);
+        if timestamp + rules.after > now || !rules.should_delete(message, &latest, guild.as_ref().map(|g| &**g), &fetched) {
+          continue;
+        }
+        run.deleted += 1;
+        if timestamp <= bulk_cutoff {
+          run.old += 1;
+        }
+      }
+    }
+    if !full_page {
+      break;
+    }
+    run.more = page + 1 == PAGES_PER_RUN;
+  }
+  Ok(run)
+}
+
 #[derive(Debug)]
 pub struct DeleteAllMessagesTask {
   next_sleep: i64,
@@ -31,6 +247,140 @@ impl DeleteAllMessagesTask {
       next_sleep: 30,
     }
   }
+
+  /// Go through a channel's history from newest to oldest, deleting messages as configured.
+  ///
+  /// Messages older than the start of the last complete sweep (minus the configured delay) were
+  /// already dealt with, so a sweep stops there, unless the rules could have changed their minds.
+  /// If a message couldn't be deleted, the next sweep goes back far enough to try it again.
+  fn sweep(env: &BotEnv, dam: &mut DeleteAllMessages) -> Result<()> {
+    let channel = ChannelId(*dam.channel_id);
+    let now = Utc::now();
+    let rules = DeleteRules::from(&*dam);
+    if dam.sweep_started.is_none() {
+      dam.sweep_started = Some(now.timestamp());
+      dam.resume_before = None;
+    }
+    let stop_at = if rules.changes_over_time() {
+      None
+    } else {
+      dam.last_sweep.map(|l| l - rules.after.num_seconds())
+    };
+    let latest = rules.latest(env.http(), channel)?;
+    let guild = env.cache_lock().read().guild(GuildId(*dam.server_id));
+
+    let mut finished = false;
+    for _ in 0..PAGES_PER_RUN {
+      let resume_before = dam.resume_before;
+      let messages = channel.messages(env.http(), |m| match resume_before {
+        Some(before) => m.before(before as u64).limit(100),
+        None => m.limit(100),
+      }).chain_err(|| format!("could not get messages for channel {}", channel))?;
+      let oldest = match messages.iter().map(|m| m.id).min() {
+        Some(o) => o,
+        None => {
+          finished = true;
+          break;
+        },
+      };
+      let full_page = messages.len() >= 100;
+
+      let fetched = rules.fetch_members(env.http(), guild.as_ref(), &messages);
+      let mut reached_end = false;
+      let mut to_delete = Vec::new();
+      {
+        let guild = guild.as_ref().map(|g| g.read());
+        for message in messages {
+          let timestamp = message.timestamp.with_timezone(&Utc);
+          if stop_at.map(|s| timestamp.timestamp() < s).unwrap_or(false) {
+            reached_end = true;
+            continue;
+          }
+          if timestamp + rules.after > now {
+            continue;
+          }
+          if !rules.should_delete(&message, &latest, guild.as_ref().map(|g| &**g), &fetched) {
+            continue;
+          }
+          to_delete.push(message);
+        }
+      }
+      let failed = DeleteAllMessagesTask::delete(env, channel, &to_delete);
+      if let Some(f) = failed.iter().map(|m| m.timestamp.timestamp()).min() {
+        dam.oldest_failed = Some(dam.oldest_failed.map(|o| o.min(f)).unwrap_or(f));
+      }
+
+      dam.resume_before = Some(oldest.0 as i64);
+      crate::bot::with_connection(|c| dam.save_changes::<DeleteAllMessages>(c)).chain_err(|| "could not save delete_all_messages progress")?;
+
+      if reached_end || !full_page {
+        finished = true;
+        break;
+      }
+    }
+
+    if finished {
+      dam.last_sweep = match (dam.sweep_started, dam.oldest_failed) {
+        (Some(started), Some(failed)) => Some(started.min(failed + rules.after.num_seconds())),
+        (started, _) => started,
+      };
+      dam.sweep_started = None;
+      dam.oldest_failed = None;
+      dam.resume_before = None;
+      crate::bot::with_connection(|c| dam.save_changes::<DeleteAllMessages>(c)).chain_err(|| "could not save delete_all_messages progress")?;
+    }
+    Ok(())
+  }
+
+  /// Delete messages, bulk deleting where Discord allows it and deleting the rest one at a time.
+  ///
+  /// Returns the messages that couldn't be deleted. Messages that are already gone don't count.
+  fn delete<'a>(env: &BotEnv, channel: ChannelId, messages: &'a [Message]) -> Vec<&'a Message> {
+    if messages.is_empty() {
+      return Vec::new();
+    }
+    let mut failed = Vec::new();
+    info!("{} message{} to delete", messages.len(), if messages.len() == 1 { "" } else { "s" });
+
+    // bulk deletes are rejected for messages older than two weeks, so leave some leeway
+    let bulk_cutoff = Utc::now() - Duration::days(14) + Duration::hours(1);
+    let (recent, old): (Vec<&Message>, Vec<&Message>) = messages.iter()
+      .partition(|m| m.timestamp.with_timezone(&Utc) > bulk_cutoff);
+
+    for chunk in recent.chunks(100) {
+      info!("Deleting chunk of {} message{}", chunk.len(), if chunk.len() == 1 { "" } else { "s" });
+      let result = if chunk.len() == 1 {
+        channel.delete_message(env.http(), chunk[0].id)
+      } else {
+        let ids: Vec<_> = chunk.iter().map(|m| m.id).collect();
+        channel.delete_messages(env.http(), ids)
+      };
+      match result {
+        Ok(()) => {},
+        Err(SError::Http(box HttpError::UnsuccessfulRequest(ref r))) if r.status_code == StatusCode::NOT_FOUND => {},
+        Err(e) => {
+          warn!("Could not delete messages: {}", e);
+          failed.extend(chunk);
+        },
+      }
+    }
+
+    if !old.is_empty() {
+      info!("Deleting {} old message{} one at a time", old.len(), if old.len() == 1 { "" } else { "s" });
+    }
+    for message in old {
+      match channel.delete_message(env.http(), message.id) {
+        Ok(()) => {},
+        Err(SError::Http(box HttpError::UnsuccessfulRequest(ref r))) if r.status_code == StatusCode::NOT_FOUND => {},
+        Err(e) => {
+          warn!("Could not delete message {}: {}", message.id, e);
+          failed.push(message);
+        },
+      }
+      thread::sleep(Duration::seconds(SINGLE_DELETE_DELAY).to_std().unwrap());
+    }
+    failed
+  }
 }
 
 impl RunsTask for DeleteAllMessagesTask {
@@ -49,40 +399,9 @@ impl RunsTask for DeleteAllMessagesTask {
           continue;
         },
       };
-      for dam in dams {
-        let channel = ChannelId(*dam.channel_id);
-        let messages = match channel.messages(env.http(), |m| m) {
-          Ok(m) => m,
-          Err(e) => {
-            warn!("Could not get messages for channel {}: {}", channel, e);
-            continue;
-          },
-        };
-        let mut to_delete = Vec::new();
-        let exclude = dam.exclude();
-        for message in messages {
-          if exclude.contains(&message.id.0) {
-            continue;
-          }
-          if message.timestamp.with_timezone(&Utc) + Duration::seconds(i64::from(dam.after)) > Utc::now() {
-            continue;
-          }
-          to_delete.push(message);
-        }
-        if !to_delete.is_empty() {
-          info!("{} message{} to delete", to_delete.len(), if to_delete.len() == 1 { "" } else { "s" });
-        }
-        for chunk in to_delete.chunks(100) {
-          info!("Deleting chunk of {} message{}", chunk.len(), if chunk.len() == 1 { "" } else { "s" });
-          let result = if chunk.len() == 1 {
-            channel.delete_message(env.http(), chunk[0].id)
-          } else {
-            let ids: Vec<_> = chunk.iter().map(|m| m.id).collect();
-            channel.delete_messages(env.http(), ids)
-          };
-          if let Err(e) = result {
-            warn!("Could not delete messages: {}", e);
-          }
+      for mut dam in dams {
+        if let Err(e) = DeleteAllMessagesTask::sweep(&env, &mut dam) {
+          warn!("Could not delete messages in {}: {}", *dam.channel_id, e);
         }
       }
       info!("Delete messages task done");
@@ -1,15 +1,18 @@
 use crate::{
   bot::BotEnv,
-  database::models::EphemeralMessage,
+  database::models::{ToU64, EphemeralMessage, NewEphemeralMessage},
   error::*,
   tasks::{RunsTask, Wait},
 };
 
-use chrono::{Utc, Duration};
+use chrono::{Utc, Duration, TimeZone};
 
 use diesel::prelude::*;
 
-use serenity::model::id::ChannelId;
+use serenity::{
+  http::Http,
+  model::id::{ChannelId, MessageId},
+};
 
 use std::{
   sync::Arc,
@@ -21,6 +24,67 @@ pub struct EphemeralMessageTask {
   next_sleep: i64,
 }
 
+/// Store a message to be deleted at a later time.
+///
+/// The task only looks ahead half an hour at a time, so messages expiring sooner than that get a
+/// thread of their own.
+pub fn schedule_deletion(http: Arc<Http>, nem: &NewEphemeralMessage) -> Result<()> {
+  crate::bot::with_connection(|c| {
+    use crate::database::schema::ephemeral_messages::dsl;
+
+    diesel::insert_into(dsl::ephemeral_messages).values(nem).execute(c)
+  }).chain_err(|| "could not insert new ephemeral message")?;
+
+  let expires_on = Utc.timestamp(nem.expires_on, 0);
+  if expires_on <= Utc::now() + Duration::minutes(30) {
+    let dur = expires_on.signed_duration_since(Utc::now()).max(Duration::zero());
+    spawn_task(http, ChannelId(*nem.channel_id), MessageId(*nem.message_id), dur);
+  }
+  Ok(())
+}
+
+/// Check if a message is still set to be deleted, since it may have been cancelled in the meantime.
+fn still_ephemeral(channel: ChannelId, message: MessageId) -> bool {
+  let count: QueryResult<i64> = crate::bot::with_connection(|c| {
+    use crate::database::schema::ephemeral_messages::dsl;
+
+    dsl::ephemeral_messages
+      .filter(dsl::channel_id.eq(channel.to_u64()).and(dsl::message_id.eq(message.to_u64())))
+      .count()
+      .get_result(c)
+  });
+  match count {
+    Ok(c) => c > 0,
+    Err(e) => {
+      warn!("could not check ephemeral message ({} in {}): {}", message, channel, e);
+      false
+    },
+  }
+}
+
+fn spawn_task(http: Arc<Http>, channel: ChannelId, message: MessageId, after: Duration) {
+  std::thread::spawn(move || {
+    std::thread::sleep(after.to_std().unwrap());
+    if !still_ephemeral(channel, message) {
+      return;
+    }
+    if let Err(e) = channel.delete_message(http, message) {
+      warn!("could not delete ephemeral message {} in {}: {}", message, channel, e);
+      return;
+    }
+    let res = crate::bot::with_connection(|c| {
+      use crate::database::schema::ephemeral_messages::dsl;
+
+      diesel::delete(dsl::ephemeral_messages
+        .filter(dsl::channel_id.eq(channel.to_u64()).and(dsl::message_id.eq(message.to_u64()))))
+        .execute(c)
+    });
+    if let Err(e) = res {
+      warn!("could not delete ephemeral message from database ({} in {}): {}", message, channel, e);
+    }
+  });
+}
+
 impl RunsTask for EphemeralMessageTask {
   fn start(mut self, env: Arc<BotEnv>) {
     loop {
@@ -56,6 +120,9 @@ impl RunsTask for EphemeralMessageTask {
           std::thread::sleep(Duration::seconds(wait).to_std().unwrap());
 
           let channel = ChannelId(*eph.channel_id);
+          if !still_ephemeral(channel, MessageId(*eph.message_id)) {
+            continue;
+          }
           match channel.delete_message(thread_env.http(), *eph.message_id) {
             Ok(_) => {
               if let Err(e) = crate::bot::with_connection(|c| diesel::delete(&eph).execute(c)) {
@@ -0,0 +1,91 @@
+use crate::{
+  bot::BotEnv,
+  database::models::{U64, LogSettings},
+  error::*,
+  tasks::RunsTask,
+};
+
+use chrono::{
+  Duration,
+  prelude::*,
+};
+
+use diesel::prelude::*;
+
+use std::{
+  collections::HashMap,
+  sync::Arc,
+  thread,
+};
+
+/// Prunes the messages and attachments kept for logging, keeping each guild within its retention
+/// settings.
+#[derive(Debug, Default)]
+pub struct MessageLogTask;
+
+impl MessageLogTask {
+  fn prune() -> Result<usize> {
+    let now = Utc::now().timestamp();
+    let (servers, settings): (Vec<U64>, Vec<LogSettings>) = crate::bot::with_connection(|c| {
+      let servers = {
+        use crate::database::schema::logged_messages::dsl;
+        dsl::logged_messages.select(dsl::server_id).distinct().load(c)?
+      };
+      let settings = crate::database::schema::log_settings::table.load(c)?;
+      Ok((servers, settings))
+    }).chain_err(|| "could not load message log settings")?;
+    let settings: HashMap<u64, LogSettings> = settings.into_iter().map(|s| (*s.server_id, s)).collect();
+
+    let mut pruned = 0;
+    for server in servers {
+      let (retention, max) = match settings.get(&*server) {
+        Some(s) => (
+          s.retention.unwrap_or(LogSettings::DEFAULT_RETENTION),
+          s.max_messages.unwrap_or(LogSettings::DEFAULT_MAX_MESSAGES),
+        ),
+        None => (LogSettings::DEFAULT_RETENTION, LogSettings::DEFAULT_MAX_MESSAGES),
+      };
+      let oldest = now - i64::from(retention);
+      pruned += crate::bot::with_connection(|c| {
+        use crate::database::schema::logged_messages::dsl;
+        let mut deleted = diesel::delete(dsl::logged_messages
+          .filter(dsl::server_id.eq(server).and(dsl::created_at.lt(oldest))))
+          .execute(c)?;
+        // message IDs go up over time, so everything at or before the first message past the
+        // limit is the oldest
+        let cutoff: Option<U64> = dsl::logged_messages
+          .filter(dsl::server_id.eq(server))
+          .select(dsl::message_id)
+          .order_by(dsl::message_id.desc())
+          .offset(i64::from(max))
+          .first(c)
+          .optional()?;
+        if let Some(cutoff) = cutoff {
+          deleted += diesel::delete(dsl::logged_messages
+            .filter(dsl::server_id.eq(server).and(dsl::message_id.le(cutoff))))
+            .execute(c)?;
+        }
+        Ok(deleted)
+      }).chain_err(|| format!("could not prune logged messages for {}", *server))?;
+    }
+    Ok(pruned)
+  }
+}
+
+impl RunsTask for MessageLogTask {
+  fn start(self, _: Arc<BotEnv>) {
+    loop {
+      thread::sleep(Duration::minutes(10).to_std().unwrap());
+      match MessageLogTask::prune() {
+        Ok(0) => {},
+        Ok(n) => info!("Pruned {} logged message{}", n, if n == 1 { "" } else { "s" }),
+        Err(e) => warn!("could not prune logged messages: {}", e),
+      }
+      match crate::attachments::prune() {
+        Ok(0) => {},
+        Ok(n) => info!("Pruned {} stored attachment{}", n, if n == 1 { "" } else { "s" }),
+        Err(e) => warn!("could not prune stored attachments: {}", e),
+      }
+    }
+  }
+}
@@ -11,22 +11,32 @@ pub trait RunsTask {
   fn start(self, env: Arc<BotEnv>);
 }
 
+pub mod auto_reply_cooldowns;
 pub mod autotag;
 pub mod delete_all_messages;
 pub mod ephemeral_messages;
+pub mod message_log;
+pub mod polls;
 pub mod random_presence;
 pub mod role_check;
+pub mod scheduled_messages;
 pub mod tag_queue;
+pub mod temporary_overwrites;
 pub mod temporary_roles;
 pub mod timeout_check;
 
 pub use self::{
+  auto_reply_cooldowns::AutoReplyCooldownsTask,
   autotag::AutoTagTask,
   delete_all_messages::DeleteAllMessagesTask,
   ephemeral_messages::EphemeralMessageTask,
+  message_log::MessageLogTask,
+  polls::PollsTask,
   random_presence::RandomPresenceTask,
   role_check::RoleCheckTask,
+  scheduled_messages::ScheduledMessagesTask,
   tag_queue::TagQueueTask,
+  temporary_overwrites::TemporaryOverwritesTask,
   temporary_roles::TemporaryRolesTask,
   timeout_check::TimeoutCheckTask,
 };
@@ -0,0 +1,62 @@
+use crate::{
+  bot::BotEnv,
+  commands::polling::close_poll,
+  database::models::Poll,
+  error::*,
+  tasks::RunsTask,
+};
+
+use chrono::{
+  Duration,
+  prelude::*,
+};
+
+use diesel::prelude::*;
+
+use serenity::model::id::GuildId;
+
+use std::{
+  sync::Arc,
+  thread,
+};
+
+/// Closes polls that have reached their deadline.
+#[derive(Debug, Default)]
+pub struct PollsTask {
+  next_sleep: i64,
+}
+
+impl RunsTask for PollsTask {
+  fn start(mut self, env: Arc<BotEnv>) {
+    loop {
+      thread::sleep(Duration::seconds(self.next_sleep).to_std().unwrap());
+      if self.next_sleep == 0 {
+        self.next_sleep = 30;
+      }
+      let now = Utc::now().timestamp();
+      let res: Result<Vec<Poll>> = crate::bot::with_connection(|c| {
+        use crate::database::schema::polls::dsl;
+        dsl::polls
+          .filter(dsl::closed.eq(false).and(dsl::ends_at.le(now)))
+          .order_by(dsl::ends_at)
+          .load(c)
+      }).chain_err(|| "could not load polls");
+      let due = match res {
+        Ok(p) => p,
+        Err(e) => {
+          warn!("error loading polls: {}", e);
+          continue;
+        },
+      };
+
+      let bot = env.cache_lock().read().user.id;
+      for poll in due {
+        let id = poll.id;
+        let guild = GuildId(*poll.server_id).to_guild_cached(env.cache_lock());
+        if let Err(e) = close_poll(env.http(), bot, guild, poll) {
+          warn!("could not close poll {}: {}", id, e);
+        }
+      }
+    }
+  }
+}
@@ -2,6 +2,7 @@ use crate::{
   error::*,
   bot::BotEnv,
   tasks::RunsTask,
+  template::{self, Values},
   util::parse_duration_secs,
   database::models::{RoleCheckTime, NewRoleCheckTime},
 };
@@ -144,8 +145,12 @@ impl RunsTask for RoleCheckTask {
         if reminders.is_empty() {
           continue;
         }
-        let mentions = reminders.join(" ");
-        if let Err(e) = ChannelId(check.channel).send_message(env.http(), |m| m.content(check.reminder.message.replace("{mentions}", &mentions))) {
+        let values = Values::new()
+          .guild(&guild.read())
+          .channel(ChannelId(check.channel))
+          .set("mentions", reminders.join(" "));
+        let message = template::render(&check.reminder.message, &values);
+        if let Err(e) = ChannelId(check.channel).send_message(env.http(), |m| m.content(&message)) {
           warn!("Could not send reminder message for check {}: {}", check.id, e);
         }
       }
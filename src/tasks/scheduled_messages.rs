@@ -0,0 +1,130 @@
+use crate::{
+  bot::BotEnv,
+  database::models::{ScheduledMessage, NewEphemeralMessage},
+  error::*,
+  tasks::RunsTask,
+  template::{self, Values},
+};
+
+use chrono::{
+  Duration,
+  prelude::*,
+};
+
+use diesel::prelude::*;
+
+use serenity::{
+  Error as SError,
+  http::{HttpError, StatusCode},
+  model::id::{ChannelId, GuildId},
+};
+
+use std::{
+  sync::Arc,
+  thread,
+};
+
+/// How many seconds to wait before trying to post a one-off message again.
+const RETRY_DELAY: i64 = 600;
+
+#[derive(Debug, Default)]
+pub struct ScheduledMessagesTask {
+  next_sleep: i64,
+}
+
+/// Post a scheduled message, then either schedule its next run or remove it if it doesn't repeat.
+pub fn post_scheduled_message(env: &BotEnv, mut scheduled: ScheduledMessage) {
+  let now = Utc::now();
+  let channel = ChannelId(*scheduled.channel_id);
+  let mut values = Values::new().channel(channel);
+  if let Some(guild) = GuildId(*scheduled.guild_id).to_guild_cached(env.cache_lock()) {
+    values = values.guild(&guild.read());
+  }
+  let content = template::render(&scheduled.content, &values);
+  let title = scheduled.title.as_ref().map(|t| template::render(t, &values));
+  let posted = channel.send_message(env.http(), |m| if scheduled.embed {
+    m.embed(|e| {
+      if let Some(ref title) = title {
+        e.title(title);
+      }
+      e.description(&content)
+    })
+  } else {
+    m.content(&content)
+  });
+  // whether posting failed in a way that trying again won't fix
+  let mut gone = false;
+  let sent = match posted {
+    Ok(posted) => {
+      if let Some(after) = scheduled.delete_after {
+        let expires_on = (now + Duration::seconds(i64::from(after))).timestamp();
+        let nem = NewEphemeralMessage::new(*scheduled.guild_id, channel.0, posted.id.0, expires_on);
+        if let Err(e) = crate::tasks::ephemeral_messages::schedule_deletion(env.http(), &nem) {
+          warn!("could not schedule deletion of scheduled message {}: {}", scheduled.id, e);
+        }
+      }
+      true
+    },
+    Err(e) => {
+      warn!("could not post scheduled message {}: {}", scheduled.id, e);
+      if let SError::Http(box HttpError::UnsuccessfulRequest(ref r)) = e {
+        gone = r.status_code == StatusCode::NOT_FOUND
+          || (scheduled.recurrence().is_none() && r.status_code == StatusCode::FORBIDDEN);
+      }
+      false
+    },
+  };
+
+  let next = scheduled.recurrence().and_then(|r| r.next_after_now(Utc.timestamp(scheduled.next_run, 0), now));
+  let res = match next {
+    // the channel was deleted, or the bot can't post a one-off message there anymore
+    _ if gone => {
+      info!("removing scheduled message {}, since it can't be posted", scheduled.id);
+      crate::bot::with_connection(|c| diesel::delete(&scheduled).execute(c).map(|_| ()))
+    },
+    Some(next) => {
+      scheduled.next_run = next.timestamp();
+      crate::bot::with_connection(|c| scheduled.save_changes::<ScheduledMessage>(c).map(|_| ()))
+    },
+    // keep one-off messages that couldn't be posted, so they're tried again and still show up in
+    // the list until they're posted or cancelled
+    None if !sent => {
+      scheduled.next_run = (now + Duration::seconds(RETRY_DELAY)).timestamp();
+      crate::bot::with_connection(|c| scheduled.save_changes::<ScheduledMessage>(c).map(|_| ()))
+    },
+    None => crate::bot::with_connection(|c| diesel::delete(&scheduled).execute(c).map(|_| ())),
+  };
+  if let Err(e) = res {
+    warn!("could not update scheduled message {} after posting: {}", scheduled.id, e);
+  }
+}
+
+impl RunsTask for ScheduledMessagesTask {
+  fn start(mut self, env: Arc<BotEnv>) {
+    loop {
+      thread::sleep(Duration::seconds(self.next_sleep).to_std().unwrap());
+      if self.next_sleep == 0 {
+        self.next_sleep = 30;
+      }
+      let now = Utc::now().timestamp();
+      let res: Result<Vec<ScheduledMessage>> = crate::bot::with_connection(|c| {
+        use crate::database::schema::scheduled_messages::dsl;
+        dsl::scheduled_messages
+          .filter(dsl::next_run.le(now))
+          .order_by(dsl::next_run)
+          .load(c)
+      }).chain_err(|| "could not load scheduled messages");
+      let due = match res {
+        Ok(m) => m,
+        Err(e) => {
+          warn!("error loading scheduled messages: {}", e);
+          continue;
+        },
+      };
+
+      for scheduled in due {
+        post_scheduled_message(&env, scheduled);
+      }
+    }
+  }
+}
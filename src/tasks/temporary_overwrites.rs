@@ -0,0 +1,135 @@
+use crate::{
+  bot::BotEnv,
+  database::models::TemporaryOverwrite,
+  error::*,
+  tasks::{RunsTask, Wait},
+};
+
+use chrono::{
+  Duration,
+  prelude::*,
+};
+
+use diesel::prelude::*;
+
+use serenity::{
+  Error as SError,
+  http::{Http, HttpError, StatusCode},
+  model::{
+    channel::{Channel, PermissionOverwrite, PermissionOverwriteType},
+    id::{ChannelId, UserId},
+    permissions::Permissions,
+  },
+};
+
+use std::{
+  sync::Arc,
+  thread,
+};
+
+#[derive(Default)]
+pub struct TemporaryOverwritesTask {
+  next_sleep: i64,
+}
+
+/// Put a channel's overwrite for a member back to how it was before the temporary access was given,
+/// then forget about the temporary access.
+///
+/// If the overwrite was changed after the access was given, it's left alone, so those changes
+/// aren't undone.
+pub fn restore_overwrite<H: AsRef<Http>>(http: H, temp: &TemporaryOverwrite) -> Result<()> {
+  let http = http.as_ref();
+  let channel = ChannelId(*temp.channel_id);
+  let kind = PermissionOverwriteType::Member(UserId(*temp.user_id));
+  let grant = Permissions::from_bits_truncate(temp.allow as u64);
+  let previous_allow = Permissions::from_bits_truncate(temp.previous_allow.unwrap_or(0) as u64);
+  let previous_deny = Permissions::from_bits_truncate(temp.previous_deny.unwrap_or(0) as u64);
+
+  let current = match http.get_channel(channel.0) {
+    Ok(Channel::Guild(c)) => c.read().permission_overwrites.iter()
+      .find(|o| o.kind == kind)
+      .map(|o| (o.allow, o.deny)),
+    Ok(_) => None,
+    // the channel was deleted, taking the overwrite with it
+    Err(SError::Http(box HttpError::UnsuccessfulRequest(ref r))) if r.status_code == StatusCode::NOT_FOUND => {
+      crate::bot::with_connection(|c| diesel::delete(temp).execute(c)).chain_err(|| "could not delete temporary overwrite")?;
+      return Ok(());
+    },
+    Err(e) => return Err(e).chain_err(|| "could not get channel"),
+  };
+  // the overwrite the access was given with
+  let granted = (previous_allow | grant, previous_deny - grant);
+  if current == Some(granted) {
+    match (temp.previous_allow, temp.previous_deny) {
+      (Some(_), Some(_)) => {
+        let overwrite = PermissionOverwrite {
+          kind,
+          allow: previous_allow,
+          deny: previous_deny,
+        };
+        channel.create_permission(http, &overwrite).chain_err(|| "could not restore previous overwrite")?;
+      },
+      _ => channel.delete_permission(http, kind).chain_err(|| "could not delete overwrite")?,
+    }
+  } else {
+    info!("overwrite for temp overwrite {} changed since it was given, so leaving it as is", temp.id);
+  }
+  crate::bot::with_connection(|c| diesel::delete(temp).execute(c)).chain_err(|| "could not delete temporary overwrite")?;
+  Ok(())
+}
+
+pub fn remove_temporary_overwrite(env: &BotEnv, temp: &TemporaryOverwrite) {
+  // the temporary access may have been cancelled since this removal was scheduled
+  let current: Option<TemporaryOverwrite> = match crate::bot::with_connection(|c| {
+    use crate::database::schema::temporary_overwrites::dsl;
+    dsl::temporary_overwrites.find(temp.id).first(c).optional()
+  }) {
+    Ok(t) => t,
+    Err(e) => {
+      warn!("could not reload temp overwrite {}: {}", temp.id, e);
+      return;
+    },
+  };
+  let temp = some_or!(current, return);
+  if let Err(e) = restore_overwrite(env.http(), &temp) {
+    warn!("could not remove temp overwrite {}: {}", temp.id, e);
+  }
+}
+
+impl RunsTask for TemporaryOverwritesTask {
+  fn start(mut self, env: Arc<BotEnv>) {
+    loop {
+      thread::sleep(Duration::seconds(self.next_sleep).to_std().unwrap());
+      if self.next_sleep == 0 {
+        self.next_sleep = 600;
+      }
+      let now = Utc::now();
+      let next_ten_minutes = (now + Duration::minutes(10)).timestamp();
+      let temp_overwrites: Vec<TemporaryOverwrite> = match crate::bot::with_connection(|c| {
+        use crate::database::schema::temporary_overwrites::dsl;
+        dsl::temporary_overwrites
+          .filter(dsl::expires_on.le(next_ten_minutes))
+          .order_by(dsl::expires_on)
+          .load(c)
+      }) {
+        Ok(t) => t,
+        Err(e) => {
+          warn!("could not load temporary overwrites: {}", e);
+          continue;
+        },
+      };
+
+      if temp_overwrites.is_empty() {
+        continue;
+      }
+
+      let thread_env = Arc::clone(&env);
+      std::thread::spawn(move || {
+        for (wait, temp) in Wait::new(temp_overwrites.into_iter().map(|t| (t.expires_on.unwrap(), t))) {
+          std::thread::sleep(Duration::seconds(wait).to_std().unwrap());
+          remove_temporary_overwrite(&thread_env, &temp);
+        }
+      });
+    }
+  }
+}
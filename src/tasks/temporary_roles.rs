@@ -11,6 +11,8 @@ use chrono::{
 
 use diesel::prelude::*;
 
+use serenity::model::id::RoleId;
+
 use std::{
   sync::Arc,
   thread,
@@ -22,6 +24,21 @@ pub struct TemporaryRolesTask {
 }
 
 pub fn remove_temporary_role(env: &BotEnv, temp: &TemporaryRole) {
+  // the temporary role may have been cancelled or extended since this removal was scheduled
+  let current: Option<TemporaryRole> = match crate::bot::with_connection(|c| {
+    use crate::database::schema::temporary_roles::dsl;
+    dsl::temporary_roles.find(temp.id).first(c).optional()
+  }) {
+    Ok(t) => t,
+    Err(e) => {
+      warn!("could not reload temp role {}: {}", temp.id, e);
+      return;
+    },
+  };
+  let temp = match current {
+    Some(ref t) if t.expires_on.map(|e| e <= Utc::now().timestamp()).unwrap_or(false) => t,
+    _ => return,
+  };
   let mut member = match env.cache().read().member(*temp.guild_id, *temp.user_id) {
     Some(m) => m,
     None => {
@@ -32,6 +49,10 @@ pub fn remove_temporary_role(env: &BotEnv, temp: &TemporaryRole) {
   if let Err(e) = member.remove_role(env.http(), *temp.role_id) {
     warn!("could not remove temp role {}: {}", temp.id, e);
   }
+  // a strict timeout would otherwise give the role back for good when it ends
+  if let Err(e) = crate::commands::timeout::forget_stripped_role(member.guild_id, member.user.read().id, RoleId(*temp.role_id)) {
+    warn!("could not keep temp role {} from coming back after a timeout: {}", temp.id, e);
+  }
   if let Err(e) = crate::bot::with_connection(|c| diesel::delete(temp).execute(c)) {
     warn!("could not delete temp role {} from database: {}", temp.id, e);
   }
@@ -21,20 +21,23 @@ pub struct TimeoutCheckTask {
   ran_once: bool,
 }
 
+/// Lift a timeout that has run out. If the member can't be found or their roles can't all be
+/// restored, the timeout is kept and tried again on the next check.
 pub fn remove_timeout(env: &BotEnv, timeout: &Timeout) {
-  // FIXME: this may not work if the character is not in the cache, but this is needed to compile
-  let mut member = match env.cache().read().member(*timeout.server_id, *timeout.user_id) {
-    Some(m) => m,
-    None => {
-      warn!("could not get member for timeout check: missing in cache");
+  let cached = env.cache().read().member(*timeout.server_id, *timeout.user_id);
+  let member = match cached {
+    Some(m) => Ok(m),
+    None => env.http().get_member(*timeout.server_id, *timeout.user_id),
+  };
+  let mut member = match member {
+    Ok(m) => m,
+    Err(e) => {
+      warn!("could not get member for timeout {}: {}", timeout.id, e);
       return;
     },
   };
-  if let Err(e) = member.remove_role(env.http(), *timeout.role_id) {
-    warn!("could not remove timeout role from {}: {}", *timeout.user_id, e);
-  }
-  if let Err(e) = crate::bot::with_connection(|c| diesel::delete(timeout).execute(c)) {
-    warn!("could not delete timeout {}: {}", timeout.id, e);
+  if let Err(e) = crate::commands::timeout::lift_timeout(env.http(), &mut member, timeout) {
+    warn!("could not lift timeout {}: {}", timeout.id, e);
   }
 }
 
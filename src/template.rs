@@ -0,0 +1,325 @@
+//! Templates for messages the bot posts on its own, such as auto replies and scheduled messages.
+//!
+//! Placeholders are written in braces, like `{user.mention}`. The current time can be formatted
+//! with a strftime-style format, like `{now:%A %H:%M}`. Parts of a template can be shown only when
+//! a placeholder has a value with `{if tag.character}...{else}...{end}`, and `{if !tag.character}`
+//! does the opposite. Literal braces are written as `{{` and `}}`, though a `{` that's never closed
+//! is kept as it is.
+//!
+//! Placeholders that aren't known are left as they were written, so mistakes are easy to spot.
+
+use crate::database::models::{ToU64, Tag};
+
+use chrono::{
+  Utc,
+  format::{Item, StrftimeItems},
+};
+
+use diesel::prelude::*;
+
+use serenity::{
+  model::{
+    guild::{Guild, Member},
+    id::{ChannelId, GuildId, UserId},
+    user::User,
+  },
+  prelude::Mentionable,
+};
+
+use std::collections::HashMap;
+
+/// The default format for `{now}`.
+const NOW_FORMAT: &str = "%Y-%m-%d %H:%M UTC";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+  Text(String),
+  Value(String),
+  Now(String),
+  If {
+    name: String,
+    negated: bool,
+    then: Vec<Part>,
+    otherwise: Vec<Part>,
+  },
+}
+
+/// A parsed template.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+  parts: Vec<Part>,
+}
+
+impl Template {
+  pub fn parse(s: &str) -> Result<Template, String> {
+    let mut stack: Vec<(String, bool, Vec<Part>, Option<Vec<Part>>)> = Vec::new();
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    let mut chars = s.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+      match c {
+        '{' if chars.peek().map(|&(_, c)| c) == Some('{') => {
+          chars.next();
+          text.push('{');
+        },
+        '}' if chars.peek().map(|&(_, c)| c) == Some('}') => {
+          chars.next();
+          text.push('}');
+        },
+        '{' => {
+          let end = match s[i..].find('}') {
+            Some(e) => i + e,
+            None => {
+              text.push('{');
+              continue;
+            },
+          };
+          let inner = s[i + 1..end].trim();
+          while chars.peek().map(|&(j, _)| j <= end).unwrap_or(false) {
+            chars.next();
+          }
+          if !text.is_empty() {
+            parts.push(Part::Text(std::mem::replace(&mut text, String::new())));
+          }
+
+          if inner.starts_with("if ") {
+            let name = inner[3..].trim();
+            let (negated, name) = if name.starts_with('!') { (true, name[1..].trim()) } else { (false, name) };
+            stack.push((name.to_string(), negated, std::mem::replace(&mut parts, Vec::new()), None));
+          } else if inner == "else" {
+            match stack.last_mut() {
+              Some(open) if open.3.is_none() => open.3 = Some(std::mem::replace(&mut parts, Vec::new())),
+              _ => return Err("`{else}` without `{if}`".into()),
+            }
+          } else if inner == "end" {
+            let (name, negated, outer, then) = match stack.pop() {
+              Some(s) => s,
+              None => return Err("`{end}` without `{if}`".into()),
+            };
+            let current = std::mem::replace(&mut parts, outer);
+            let (then, otherwise) = match then {
+              Some(then) => (then, current),
+              None => (current, Vec::new()),
+            };
+            parts.push(Part::If { name, negated, then, otherwise });
+          } else if inner == "now" {
+            parts.push(Part::Now(NOW_FORMAT.to_string()));
+          } else if inner.starts_with("now:") {
+            let format = &inner[4..];
+            if StrftimeItems::new(format).any(|i| i == Item::Error) {
+              return Err(format!("invalid time format `{}`", format));
+            }
+            parts.push(Part::Now(format.to_string()));
+          } else {
+            parts.push(Part::Value(inner.to_string()));
+          }
+        },
+        _ => text.push(c),
+      }
+    }
+
+    if !stack.is_empty() {
+      return Err("`{if}` without `{end}`".into());
+    }
+    if !text.is_empty() {
+      parts.push(Part::Text(text));
+    }
+    Ok(Template { parts })
+  }
+
+  pub fn render(&self, values: &Values) -> String {
+    // only look up tags for templates that use them
+    let loaded;
+    let values = if values.tag.is_some() && Template::uses(&self.parts, "tag.") {
+      loaded = values.clone().load_tag();
+      &loaded
+    } else {
+      values
+    };
+    let mut out = String::new();
+    Template::render_parts(&self.parts, values, &mut out);
+    out
+  }
+
+  /// Whether any placeholder in `parts` starts with `prefix`.
+  fn uses(parts: &[Part], prefix: &str) -> bool {
+    parts.iter().any(|part| match *part {
+      Part::Value(ref name) => name.starts_with(prefix),
+      Part::If { ref name, ref then, ref otherwise, .. } => name.starts_with(prefix)
+        || Template::uses(then, prefix)
+        || Template::uses(otherwise, prefix),
+      _ => false,
+    })
+  }
+
+  fn render_parts(parts: &[Part], values: &Values, out: &mut String) {
+    for part in parts {
+      match *part {
+        Part::Text(ref t) => out.push_str(t),
+        Part::Value(ref name) => match values.get(name) {
+          Some(v) => out.push_str(v),
+          None => {
+            out.push('{');
+            out.push_str(name);
+            out.push('}');
+          },
+        },
+        Part::Now(ref format) => out.push_str(&Utc::now().format(format).to_string()),
+        Part::If { ref name, negated, ref then, ref otherwise } => {
+          let set = values.get(name).map(|v| !v.is_empty()).unwrap_or(false);
+          let branch = if set != negated { then } else { otherwise };
+          Template::render_parts(branch, values, out);
+        },
+      }
+    }
+  }
+}
+
+/// Render a template, falling back to the text as written if it isn't a valid template.
+pub fn render(template: &str, values: &Values) -> String {
+  match Template::parse(template) {
+    Ok(t) => t.render(values),
+    Err(e) => {
+      warn!("invalid template ({}): {}", e, template);
+      template.to_string()
+    },
+  }
+}
+
+/// The values placeholders are filled in with.
+#[derive(Debug, Default, Clone)]
+pub struct Values {
+  values: HashMap<String, String>,
+  /// The guild and user to look up a tag for, if a template needs it.
+  tag: Option<(GuildId, UserId)>,
+}
+
+impl Values {
+  pub fn new() -> Self {
+    Values::default()
+  }
+
+  pub fn get(&self, name: &str) -> Option<&str> {
+    self.values.get(name).map(String::as_str)
+  }
+
+  pub fn set<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+    self.values.insert(key.into(), value.into());
+    self
+  }
+
+  pub fn user(self, user: &User) -> Self {
+    let mention = user.mention();
+    self
+      .set("user.name", user.name.clone())
+      .set("user.nick", user.name.clone())
+      .set("user.id", user.id.to_string())
+      .set("user.mention", mention.clone())
+      // older templates used this before there were other placeholders
+      .set("mention", mention)
+  }
+
+  /// Fill in a member's user, nickname and FFXIV tag in the member's guild.
+  pub fn member(self, member: &Member) -> Self {
+    let user = member.user.read().clone();
+    let nick = member.display_name().to_string();
+    self
+      .user(&user)
+      .set("user.nick", nick)
+      .tag(member.guild_id, user.id)
+  }
+
+  pub fn guild(self, guild: &Guild) -> Self {
+    self
+      .set("guild.name", guild.name.clone())
+      .set("guild.member_count", guild.member_count.to_string())
+  }
+
+  pub fn channel(self, channel: ChannelId) -> Self {
+    self.set("channel", channel.mention())
+  }
+
+  /// Fill in the character a user is tagged as in a guild, if they are. The tag is only looked up
+  /// when rendering a template that uses it.
+  pub fn tag(mut self, guild: GuildId, user: UserId) -> Self {
+    self.tag = Some((guild, user));
+    self
+  }
+
+  fn load_tag(mut self) -> Self {
+    let (guild, user) = match self.tag.take() {
+      Some(t) => t,
+      None => return self,
+    };
+    let tag: Option<Tag> = match crate::bot::with_connection(|c| {
+      use crate::database::schema::tags::dsl;
+      dsl::tags
+        .filter(dsl::user_id.eq(user.to_u64()).and(dsl::server_id.eq(guild.to_u64())))
+        .first(c)
+        .optional()
+    }) {
+      Ok(t) => t,
+      Err(e) => {
+        warn!("could not load tag for template: {}", e);
+        None
+      },
+    };
+    match tag {
+      Some(tag) => self
+        .set("tag.character", tag.character)
+        .set("tag.world", tag.server),
+      None => self,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn render(template: &str, values: &Values) -> String {
+    Template::parse(template).unwrap().render(values)
+  }
+
+  #[test]
+  fn escapes() {
+    let values = Values::new().set("user.name", "Duvivi");
+    assert_eq!(render("{{user.name}}", &values), "{user.name}");
+    assert_eq!(render("}} and {{", &values), "} and {");
+    assert_eq!(render("{{{user.name}}}", &values), "{Duvivi}");
+  }
+
+  #[test]
+  fn lone_braces() {
+    let values = Values::new();
+    assert_eq!(render("a { b", &values), "a { b");
+    assert_eq!(render("a } b", &values), "a } b");
+    assert_eq!(render(":{", &values), ":{");
+  }
+
+  #[test]
+  fn unknown_keys() {
+    let values = Values::new().set("user.name", "Duvivi");
+    assert_eq!(render("hi {user.name}, {nope}", &values), "hi Duvivi, {nope}");
+    assert_eq!(render("{if nope}yes{else}no{end}", &values), "no");
+    assert_eq!(render("{if !nope}yes{end}", &values), "yes");
+  }
+
+  #[test]
+  fn unbalanced_ifs() {
+    assert!(Template::parse("{if user.name}").is_err());
+    assert!(Template::parse("{else}").is_err());
+    assert!(Template::parse("{end}").is_err());
+  }
+
+  #[test]
+  fn tags_are_only_needed_when_used() {
+    let values = Values::new();
+    let template = Template::parse("{if tag.character}{tag.character}{end}").unwrap();
+    assert!(Template::uses(&template.parts, "tag."));
+    let template = Template::parse("{user.name}").unwrap();
+    assert!(!Template::uses(&template.parts, "tag."));
+    assert_eq!(template.render(&values), "{user.name}");
+  }
+}
@@ -1,6 +1,11 @@
+use chrono::{DateTime, Duration, Utc};
+
 use serenity::{
-  model::channel::ReactionType,
+  model::channel::{Channel, Message, ReactionType},
+  model::id::{ChannelId, GuildId, UserId},
   model::misc::EmojiIdentifier,
+  model::permissions::Permissions,
+  prelude::Context,
 };
 
 use std::{
@@ -73,6 +78,77 @@ pub fn parse_duration_secs<S: AsRef<str>>(duration: S) -> Result<u64, ParsedDura
   Ok(total_time)
 }
 
+/// A point in time, given either as an RFC 3339 date or as a duration from now, like `2h`.
+#[derive(Debug, Clone, Copy)]
+pub struct ParsedTime(pub DateTime<Utc>);
+
+impl Deref for ParsedTime {
+  type Target = DateTime<Utc>;
+
+  fn deref(&self) -> &DateTime<Utc> {
+    &self.0
+  }
+}
+
+impl FromStr for ParsedTime {
+  type Err = ParsedDurationError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    if let Ok(time) = DateTime::<Utc>::from_str(s) {
+      return Ok(ParsedTime(time));
+    }
+    let secs = parse_duration_secs(s)?;
+    Ok(ParsedTime(Utc::now() + Duration::seconds(secs as i64)))
+  }
+}
+
+/// The text given to a command that posts a message.
+///
+/// Anything after the first line of the command is used as-is, so messages can keep their
+/// formatting. Otherwise, the trailing words of the command are used.
+pub fn message_content(msg: &Message, words: &[String]) -> String {
+  match msg.content.find('\n') {
+    Some(i) => msg.content[i + 1..].trim().to_string(),
+    None => words.join(" "),
+  }
+}
+
+/// Make sure a channel given to a command is in the guild and, if `needed` is given, that the
+/// user has those permissions in it.
+pub fn check_channel(ctx: &Context, guild: GuildId, channel: ChannelId, needed: Option<(UserId, Permissions)>) -> Result<(), String> {
+  match channel.to_channel(ctx) {
+    Ok(Channel::Guild(c)) if c.read().guild_id == guild => {},
+    _ => return Err("That channel is not in this guild.".into()),
+  }
+  if let Some((user, needed)) = needed {
+    let allowed = guild.to_guild_cached(ctx)
+      .map(|g| g.read().permissions_in(channel, user).contains(needed))
+      .unwrap_or(false);
+    if !allowed {
+      return Err("You don't have enough permissions in that channel.".into());
+    }
+  }
+  Ok(())
+}
+
+/// Format a number of seconds as a short, human-readable duration, such as `1d 2h 5m`.
+pub fn format_duration(secs: i64) -> String {
+  if secs <= 0 {
+    return String::from("0s");
+  }
+  let units = [(86400, "d"), (3600, "h"), (60, "m"), (1, "s")];
+  let mut remaining = secs;
+  let mut parts = Vec::new();
+  for &(size, suffix) in &units {
+    let amount = remaining / size;
+    if amount > 0 {
+      parts.push(format!("{}{}", amount, suffix));
+      remaining -= amount * size;
+    }
+  }
+  parts.join(" ")
+}
+
 #[derive(Debug)]
 pub struct ParsedEmoji(pub ReactionType);
 